@@ -0,0 +1,209 @@
+use attacks::{pawn_attacks, pseudo_attacks};
+use interface::game::{BoardMask, PieceArrangement, Shiftable, Side, Square};
+use oxide_interface::engine::{OxidePosition, TaperedScore};
+use oxide_interface::game::{OxideBitboard, OxidePiece, OxideSide, OxideSquare};
+use crate::parameters::EvaluationParameters;
+use crate::pawns::{backward_shift, forward_fill, pawns, relative_rank};
+use crate::trace::EvaluationTracer;
+
+// Rooks with this many moves or fewer can be trapped by their own king
+const TRAPPED_ROOK_MOBILITY: usize = 3;
+
+// Mask of a rank counted from `side`'s first rank
+#[inline]
+fn relative_rank_mask(rank: usize, side: OxideSide) -> OxideBitboard {
+    let rank = if side.is_white() { rank } else { 7 - rank };
+
+    OxideBitboard(OxideBitboard::RANK_1.0 << (8 * rank))
+}
+
+/// Squares `side`'s pieces count towards their mobility, those not attacked by enemy pawns or occupied by blocked friendly pawns
+pub fn mobility_area(position: &OxidePosition, side: OxideSide) -> OxideBitboard {
+    let ours = pawns(position, side);
+    let blocked = ours & backward_shift(position.occupied(), side);
+    let their_pawn_attacks = pawn_attacks::<OxidePosition>(pawns(position, side.opposite_side()), side.opposite_side());
+
+    !(blocked | their_pawn_attacks)
+}
+
+/// Squares in the enemy half defended by `side`'s pawns that enemy pawns can never attack
+pub fn outpost_squares(position: &OxidePosition, side: OxideSide) -> OxideBitboard {
+    let them = side.opposite_side();
+    let enemy_half = relative_rank_mask(3, side) | relative_rank_mask(4, side) | relative_rank_mask(5, side);
+    let defended = pawn_attacks::<OxidePosition>(pawns(position, side), side);
+    let their_attack_span = forward_fill(pawn_attacks::<OxidePosition>(pawns(position, them), them), them);
+
+    enemy_half & defended & !their_attack_span
+}
+
+// Number of squares a piece attacks in the mobility area, capped to the table's largest entry
+#[inline]
+fn mobility(piece: OxidePiece, square: OxideSquare, occupied: OxideBitboard, area: OxideBitboard, max: usize) -> usize {
+    ((pseudo_attacks::<OxidePosition>(piece, square, occupied) & area).0.count_ones() as usize).min(max)
+}
+
+// If a bishop sits on a7 or h7 (from its side) with an enemy pawn cutting off its retreat on b6 or g6
+fn is_trapped_bishop(square: OxideSquare, their_pawns: OxideBitboard, side: OxideSide) -> bool {
+    let corners = relative_rank_mask(6, side) & (OxideBitboard::A_FILE | OxideBitboard::H_FILE);
+    let retreat = backward_shift(square.to_mask(), side);
+
+    square.to_mask() & corners != OxideBitboard::EMPTY && (retreat.east_shift() | retreat.west_shift()) & their_pawns != OxideBitboard::EMPTY
+}
+
+// If a rook on the first rank is stuck between its own king there and the corner
+fn is_trapped_rook(square: OxideSquare, king: OxideSquare, side: OxideSide) -> bool {
+    if relative_rank(square, side) != 0 || relative_rank(king, side) != 0 {
+        return false;
+    }
+    let (rook_file, king_file) = (square.x_offset(), king.x_offset());
+
+    (king_file >= 4 && rook_file > king_file) || (king_file < 4 && rook_file < king_file)
+}
+
+/// Mobility of `side`'s pieces, and their outposts, rook files, bishop pair and trapped pieces
+pub fn activity<T: EvaluationTracer>(position: &OxidePosition, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> (TaperedScore, TaperedScore) {
+    let occupied = position.occupied();
+    let ours = position.mask_for_side(side);
+    let our_pawns = pawns(position, side);
+    let their_pawns = pawns(position, side.opposite_side());
+    let area = mobility_area(position, side);
+    let outposts = outpost_squares(position, side);
+    let king = position.king_square(side);
+    let (mut mobility_score, mut score) = (TaperedScore::ZERO, TaperedScore::ZERO);
+
+    for square in position.piece_mask(OxidePiece::Knight) & ours {
+        let moves = mobility(OxidePiece::Knight, square, occupied, area, 8);
+        tracer.record_score_weight("knight_mobility", moves, side, 1.0);
+        mobility_score += parameters.knight_mobility[moves];
+        if square.to_mask() & outposts != OxideBitboard::EMPTY {
+            tracer.record_score_weight("knight_outpost", 0, side, 1.0);
+            score += parameters.knight_outpost;
+        }
+    }
+
+    let bishops = position.piece_mask(OxidePiece::Bishop) & ours;
+    for square in bishops {
+        let moves = mobility(OxidePiece::Bishop, square, occupied, area, 13);
+        tracer.record_score_weight("bishop_mobility", moves, side, 1.0);
+        mobility_score += parameters.bishop_mobility[moves];
+        if square.to_mask() & outposts != OxideBitboard::EMPTY {
+            tracer.record_score_weight("bishop_outpost", 0, side, 1.0);
+            score += parameters.bishop_outpost;
+        }
+        if is_trapped_bishop(square, their_pawns, side) {
+            tracer.record_score_weight("trapped_bishop", 0, side, 1.0);
+            score += parameters.trapped_bishop;
+        }
+    }
+    if bishops.0.count_ones() >= 2 {
+        tracer.record_score_weight("bishop_pair", 0, side, 1.0);
+        score += parameters.bishop_pair;
+    }
+
+    for square in position.piece_mask(OxidePiece::Rook) & ours {
+        let moves = mobility(OxidePiece::Rook, square, occupied, area, 14);
+        tracer.record_score_weight("rook_mobility", moves, side, 1.0);
+        mobility_score += parameters.rook_mobility[moves];
+        let file = square.to_mask().file_fill();
+        if file & our_pawns == OxideBitboard::EMPTY {
+            let (name, bonus) = if file & their_pawns == OxideBitboard::EMPTY {
+                ("rook_open_file", parameters.rook_open_file)
+            } else {
+                ("rook_semi_open_file", parameters.rook_semi_open_file)
+            };
+            tracer.record_score_weight(name, 0, side, 1.0);
+            score += bonus;
+        }
+        if relative_rank(square, side) == 6 {
+            tracer.record_score_weight("rook_on_seventh", 0, side, 1.0);
+            score += parameters.rook_on_seventh;
+        }
+        if moves <= TRAPPED_ROOK_MOBILITY && is_trapped_rook(square, king, side) {
+            tracer.record_score_weight("trapped_rook", 0, side, 1.0);
+            score += parameters.trapped_rook;
+        }
+    }
+
+    for square in position.piece_mask(OxidePiece::Queen) & ours {
+        let moves = mobility(OxidePiece::Queen, square, occupied, area, 27);
+        tracer.record_score_weight("queen_mobility", moves, side, 1.0);
+        mobility_score += parameters.queen_mobility[moves];
+    }
+
+    (mobility_score, score)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+    use crate::trace::NoTrace;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn position(fen: &str) -> OxidePosition {
+        OxidePosition::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn mobility_area_works() {
+        let area = mobility_area(&position("4k3/8/8/3p4/3P4/8/6P1/4K3 w - - 0 1"), OxideSide::White);
+        // Blocked d4 pawn and the squares attacked by d5 are excluded, the free g2 pawn isn't
+        assert_eq!(area & (D4.to_mask() | C4.to_mask() | E4.to_mask()), OxideBitboard::EMPTY);
+        assert_ne!(area & G2.to_mask(), OxideBitboard::EMPTY);
+    }
+
+    #[test]
+    fn outpost_squares_works() {
+        let outposts = outpost_squares(&position("4k3/1p6/8/8/3P4/8/8/4K3 w - - 0 1"), OxideSide::White);
+        assert_eq!(outposts, E5.to_mask());
+        let outposts = outpost_squares(&position("4k3/p7/8/3P4/8/8/8/4K3 w - - 0 1"), OxideSide::White);
+        assert_eq!(outposts, C6.to_mask() | E6.to_mask());
+        // The b7 pawn can kick a piece off c6 but not e6
+        let outposts = outpost_squares(&position("4k3/1p6/8/3P4/8/8/8/4K3 w - - 0 1"), OxideSide::White);
+        assert_eq!(outposts, E6.to_mask());
+    }
+
+    #[test]
+    fn rook_files_works() {
+        let parameters = EvaluationParameters::default();
+        let activity = |fen: &str| {
+            let (mobility, activity) = activity(&position(fen), OxideSide::White, &parameters, &mut NoTrace);
+            mobility + activity
+        };
+        let open = activity("4k3/p7/8/8/8/8/P7/3RK3 w - - 0 1");
+        let semi_open = activity("4k3/3p4/8/8/8/8/P7/3RK3 w - - 0 1");
+        let closed = activity("4k3/3p4/8/8/8/8/3P4/3RK3 w - - 0 1");
+        assert!(open.midgame() > semi_open.midgame());
+        assert!(semi_open.midgame() > closed.midgame());
+    }
+
+    #[test]
+    fn bishop_pair_works() {
+        let parameters = EvaluationParameters::default();
+        let (pair_mobility, pair) = activity(&position("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"), OxideSide::White, &parameters, &mut NoTrace);
+        let (single_mobility, single) = activity(&position("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), OxideSide::White, &parameters, &mut NoTrace);
+        assert_eq!(pair_mobility - single_mobility, parameters.bishop_mobility[7]);
+        assert_eq!(pair - single, parameters.bishop_pair);
+    }
+
+    #[test]
+    fn trapped_pieces_works() {
+        assert!(is_trapped_bishop(A7, G6.to_mask() | B6.to_mask(), OxideSide::White));
+        assert!(is_trapped_bishop(H7, G6.to_mask(), OxideSide::White));
+        assert!(!is_trapped_bishop(H7, B6.to_mask(), OxideSide::White));
+        assert!(is_trapped_bishop(A2, B3.to_mask(), OxideSide::Black));
+        assert!(is_trapped_rook(H1, F1, OxideSide::White));
+        assert!(is_trapped_rook(A1, B1, OxideSide::White));
+        assert!(!is_trapped_rook(A1, G1, OxideSide::White));
+        assert!(is_trapped_rook(H8, G8, OxideSide::Black));
+        assert!(!is_trapped_rook(H8, G8, OxideSide::White));
+    }
+
+    #[test]
+    fn activity_is_symmetric() {
+        let parameters = EvaluationParameters::default();
+        let white = activity(&position("r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 0 1"), OxideSide::White, &parameters, &mut NoTrace);
+        let black = activity(&position("rnbqk2r/ppp2ppp/3p1n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQK2R b KQkq - 0 1"), OxideSide::Black, &parameters, &mut NoTrace);
+        assert_eq!(white, black);
+    }
+}
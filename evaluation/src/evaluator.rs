@@ -0,0 +1,244 @@
+use oxide_interface::engine::{OxidePosition, OxideScore, TaperedScore};
+use oxide_interface::game::{OxidePiece, OxideSide};
+use interface::engine::{Evaluator, PositionalScore};
+use interface::game::{Piece, PieceArrangement, Position, Side, Square};
+use crate::parameters::{EvaluationParameters, PIECE_SQUARE_NAMES};
+use crate::pawns::{PawnTable, passed_pawn_score, pawn_structure_score};
+use crate::king_safety::king_safety;
+use crate::activity::activity;
+use crate::trace::{EvaluationTerm, EvaluationTrace, EvaluationTracer, NoTrace};
+
+/// Name of the option loading a parameter file (empty for the compiled-in parameters)
+pub const EVAL_PARAMETERS_OPTION: &str = "EvalParams";
+
+/// Handcrafted evaluation tapered between the midgame and endgame by the non-pawn material left
+#[derive(Clone, Debug, Default)]
+pub struct HandcraftedEvaluator {
+    parameters: EvaluationParameters,
+    pawn_table: PawnTable,
+    // The position only keeps sums for the compiled-in tables, other tables are recounted every evaluation
+    custom_piece_squares: bool,
+}
+
+// Material and piece-square sum of one side from its own perspective with the parameters' tables
+fn piece_square_score<T: EvaluationTracer>(position: &OxidePosition, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    let ours = position.mask_for_side(side);
+    // Tables have a8 first so white mirrors them
+    let flip = if side.is_white() { 56 } else { 0 };
+    let mut score = TaperedScore::ZERO;
+    for (index, &piece) in <OxidePiece as Piece<OxidePosition>>::PIECES.iter().enumerate() {
+        for square in position.piece_mask(piece) & ours {
+            let offset = square.offset() as usize ^ flip;
+            let material = parameters.piece_values[index];
+            let bonus = parameters.piece_squares[index][offset];
+            tracer.record_score_weight("piece_values", index, side, 1.0);
+            tracer.record_score_weight(PIECE_SQUARE_NAMES[index], offset, side, 1.0);
+            tracer.record(EvaluationTerm::Material, side, material);
+            tracer.record(EvaluationTerm::PieceSquares, side, bonus);
+            score += material + bonus;
+        }
+    }
+
+    score
+}
+
+impl HandcraftedEvaluator {
+    pub fn new(parameters: EvaluationParameters) -> Self {
+        Self {
+            parameters,
+            pawn_table: PawnTable::default(),
+            custom_piece_squares: !parameters.has_compiled_piece_squares(),
+        }
+    }
+
+    /// Weights the evaluation uses
+    #[inline]
+    pub fn parameters(&self) -> &EvaluationParameters {
+        &self.parameters
+    }
+
+    /// Replace the weights, dropping pawn structures cached with the old ones
+    pub fn set_parameters(&mut self, parameters: EvaluationParameters) {
+        self.parameters = parameters;
+        self.pawn_table.clear();
+        self.custom_piece_squares = !parameters.has_compiled_piece_squares();
+    }
+
+    /// Evaluate a position with every term recorded
+    pub fn trace(&mut self, position: &OxidePosition) -> EvaluationTrace {
+        let mut trace = EvaluationTrace::default();
+        self.evaluate_with(position, &mut trace);
+
+        trace
+    }
+
+    /// Tapered score from white's perspective, passing each term to the tracer as it's computed
+    pub fn evaluate_with<T: EvaluationTracer>(&mut self, position: &OxidePosition, tracer: &mut T) -> i32 {
+        let parameters = &self.parameters;
+        let (mut score, phase) = position.piece_squares();
+        if self.custom_piece_squares {
+            score = piece_square_score(position, OxideSide::White, parameters, &mut NoTrace) - piece_square_score(position, OxideSide::Black, parameters, &mut NoTrace);
+        }
+        let pawns = self.pawn_table.probe(position, parameters);
+        score += pawns.score;
+        // Only the combined totals are cached so split them by side when tracing
+        if T::ENABLED {
+            tracer.record_phase(phase);
+            for &side in &[OxideSide::White, OxideSide::Black] {
+                piece_square_score(position, side, parameters, tracer);
+                let pawn_structure = pawn_structure_score(position, side, parameters, tracer);
+                tracer.record(EvaluationTerm::Pawns, side, pawn_structure);
+            }
+        }
+
+        for &(side, sign) in &[(OxideSide::White, 1), (OxideSide::Black, -1)] {
+            let passed_pawns = passed_pawn_score(position, &pawns, side, parameters, tracer);
+            let king = king_safety(position, side, parameters, tracer);
+            let (mobility, activity) = activity(position, side, parameters, tracer);
+            tracer.record(EvaluationTerm::PassedPawns, side, passed_pawns);
+            tracer.record(EvaluationTerm::KingSafety, side, king);
+            tracer.record(EvaluationTerm::Mobility, side, mobility);
+            tracer.record(EvaluationTerm::Activity, side, activity);
+            score += (passed_pawns + king + mobility + activity) * sign;
+        }
+
+        score.taper(phase)
+    }
+}
+
+impl Evaluator<OxidePosition> for HandcraftedEvaluator {
+    type Score = OxideScore;
+
+    fn evaluate(&mut self, position: &OxidePosition) -> OxideScore {
+        let white_score = self.evaluate_with(position, &mut NoTrace);
+
+        if position.side_to_move().is_white() {
+            OxideScore::new(white_score)
+        } else {
+            OxideScore::new(-white_score)
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        if !name.eq_ignore_ascii_case(EVAL_PARAMETERS_OPTION) {
+            return Ok(false);
+        }
+
+        let parameters = match value.trim() {
+            "" | "<empty>" => EvaluationParameters::default(),
+            path => EvaluationParameters::load(path).map_err(|error| error.to_string())?,
+        };
+        self.set_parameters(parameters);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oxide_interface::game::OxideSquare;
+
+    fn evaluate(fen: &str) -> i32 {
+        HandcraftedEvaluator::default().evaluate(&OxidePosition::from_fen(fen).unwrap()).centipawns()
+    }
+
+    #[test]
+    fn start_position_is_balanced() {
+        assert_eq!(evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+        assert_eq!(evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"), 0);
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        let white = evaluate("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let black = evaluate("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3");
+        assert_eq!(white, black);
+    }
+
+    #[test]
+    fn extra_material_is_good() {
+        let up_a_queen = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(evaluate(up_a_queen) > 800);
+        assert!(evaluate(&up_a_queen.replace(" w ", " b ")) < -800);
+    }
+
+    #[test]
+    fn pawn_structure_works() {
+        let healthy = evaluate("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1");
+        let doubled_isolated = evaluate("4k3/pppp4/8/8/8/2P5/P1P5/4K3 w - - 0 1");
+        assert_eq!(healthy, 0);
+        assert!(doubled_isolated < 0);
+        assert!(evaluate("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1") > evaluate("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn set_parameters_works() {
+        let position = OxidePosition::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut evaluator = HandcraftedEvaluator::default();
+        let before = evaluator.evaluate(&position).centipawns();
+        let mut parameters = *evaluator.parameters();
+        parameters.passed_pawn = [TaperedScore::ZERO; 8];
+        parameters.free_passed_pawn = TaperedScore::ZERO;
+        evaluator.set_parameters(parameters);
+        assert!(evaluator.evaluate(&position).centipawns() < before);
+    }
+
+    #[test]
+    fn trace_matches_evaluation() {
+        let mut evaluator = HandcraftedEvaluator::default();
+        for fen in &[
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 0 1",
+            "4k3/pp4pp/8/3P4/8/6q1/P4rPP/6K1 b - - 0 1",
+        ] {
+            let position = OxidePosition::from_fen(fen).unwrap();
+            let trace = evaluator.trace(&position);
+            assert_eq!(trace.score(), evaluator.evaluate_with(&position, &mut NoTrace));
+            assert_eq!(trace.phase(), position.piece_squares().1);
+            let piece_squares = trace.net(EvaluationTerm::Material) + trace.net(EvaluationTerm::PieceSquares);
+            assert_eq!(piece_squares, position.piece_squares().0);
+        }
+    }
+
+    #[test]
+    fn custom_piece_squares_are_recounted() {
+        let position = OxidePosition::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut evaluator = HandcraftedEvaluator::default();
+        let before = evaluator.evaluate(&position).centipawns();
+        let mut parameters = *evaluator.parameters();
+        // The white knight on f3, tables have a8 first
+        parameters.piece_squares[1][OxideSquare::F3.offset() as usize ^ 56] += TaperedScore::new(50, 50);
+        evaluator.set_parameters(parameters);
+        assert_eq!(evaluator.evaluate(&position).centipawns(), before + 50);
+        let trace = evaluator.trace(&position);
+        assert_eq!(trace.score(), before + 50);
+        assert_eq!(trace.net(EvaluationTerm::PieceSquares), position.piece_squares().0 - trace.net(EvaluationTerm::Material) + TaperedScore::new(50, 50));
+    }
+
+    #[test]
+    fn parameters_option_works() {
+        let position = OxidePosition::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let mut evaluator = HandcraftedEvaluator::default();
+        let before = evaluator.evaluate(&position).centipawns();
+        let path = std::env::temp_dir().join(format!("oxide-parameters-{}.toml", std::process::id()));
+        std::fs::write(&path, "piece_values = [[82, 94], [437, 381], [365, 297], [477, 512], [1025, 936], [0, 0]]\n").unwrap();
+        assert_eq!(evaluator.set_option("EvalParams", path.to_str().unwrap()), Ok(true));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(evaluator.evaluate(&position).centipawns(), before + 100);
+
+        assert!(evaluator.set_option("EvalParams", "/nonexistent/parameters.toml").is_err());
+        assert_eq!(evaluator.evaluate(&position).centipawns(), before + 100);
+        assert_eq!(evaluator.set_option("evalparams", "<empty>"), Ok(true));
+        assert_eq!(evaluator.evaluate(&position).centipawns(), before);
+        assert_eq!(evaluator.set_option("Hash", "16"), Ok(false));
+    }
+
+    #[test]
+    fn phase_works() {
+        let phase = |fen: &str| OxidePosition::from_fen(fen).unwrap().piece_squares().1;
+        assert_eq!(phase("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 24);
+        assert_eq!(phase("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1"), 0);
+        assert_eq!(phase("3qk3/8/8/8/8/8/8/R3K3 w - - 0 1"), 6);
+    }
+}
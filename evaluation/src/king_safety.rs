@@ -0,0 +1,196 @@
+use attacks::{king_attacks, pawn_attacks, pseudo_attacks};
+use interface::game::{BoardMask, PieceArrangement, Shiftable, Side, Square};
+use oxide_interface::engine::{OxidePosition, TaperedScore};
+use oxide_interface::game::{OxideBitboard, OxidePiece, OxideSide};
+use crate::parameters::EvaluationParameters;
+use crate::pawns::{forward_fill, forward_shift, pawns, relative_rank};
+use crate::trace::EvaluationTracer;
+
+// Pieces weighted by the attack and safe check units, in parameter order
+const KING_ATTACKERS: [OxidePiece; 4] = [OxidePiece::Knight, OxidePiece::Bishop, OxidePiece::Rook, OxidePiece::Queen];
+// Attack units past this don't make the king any less safe
+const MAX_ATTACK_UNITS: i32 = 50;
+
+/// Every square attacked by `side`
+pub fn side_attacks(position: &OxidePosition, side: OxideSide) -> OxideBitboard {
+    let occupied = position.occupied();
+    let ours = position.mask_for_side(side);
+    let king = position.king_square(side).to_mask();
+
+    KING_ATTACKERS.iter().fold(pawn_attacks::<OxidePosition>(pawns(position, side), side) | king_attacks::<OxidePosition>(king), |attacks, &piece| {
+        (position.piece_mask(piece) & ours).fold(attacks, |attacks, square| attacks | pseudo_attacks::<OxidePosition>(piece, square, occupied))
+    })
+}
+
+/// Squares around a king that attacks are counted against, extended a rank toward the enemy
+pub fn king_zone(position: &OxidePosition, side: OxideSide) -> OxideBitboard {
+    let king = position.king_square(side).to_mask();
+    let ring = king | king_attacks::<OxidePosition>(king);
+
+    ring | forward_shift(ring, side)
+}
+
+// Pieces of each attacking type (in parameter order) attacking the zone around `side`'s king, and the types with a safe check on it
+fn king_attackers(position: &OxidePosition, side: OxideSide) -> ([i32; 4], [bool; 4]) {
+    let attacker = side.opposite_side();
+    let occupied = position.occupied();
+    let theirs = position.mask_for_side(attacker);
+    let king = position.king_square(side);
+    let zone = king_zone(position, side);
+    let unsafe_squares = theirs | side_attacks(position, side);
+    let (mut attackers, mut safe_checks) = ([0; 4], [false; 4]);
+
+    for (index, &piece) in KING_ATTACKERS.iter().enumerate() {
+        // Squares this piece type would check the king from
+        let check_squares = pseudo_attacks::<OxidePosition>(piece, king, occupied);
+        let mut reachable = OxideBitboard::EMPTY;
+        for square in position.piece_mask(piece) & theirs {
+            let attacks = pseudo_attacks::<OxidePosition>(piece, square, occupied);
+            if attacks & zone != OxideBitboard::EMPTY {
+                attackers[index] += 1;
+            }
+            reachable |= attacks;
+        }
+
+        safe_checks[index] = reachable & check_squares & !unsafe_squares != OxideBitboard::EMPTY;
+    }
+
+    (attackers, safe_checks)
+}
+
+// A lone attacker can't do much without support
+#[inline]
+fn is_supported_attack(attackers: &[i32; 4]) -> bool {
+    attackers.iter().sum::<i32>() >= 2
+}
+
+// Attack units against a king from the pieces attacking its zone and the safe checks available to them
+fn attack_units(attackers: &[i32; 4], safe_checks: &[bool; 4], parameters: &EvaluationParameters) -> i32 {
+    let mut units = 0;
+    for index in 0..KING_ATTACKERS.len() {
+        if is_supported_attack(attackers) {
+            units += attackers[index] * parameters.king_attack_units[index];
+        }
+        if safe_checks[index] {
+            units += parameters.safe_check_units[index];
+        }
+    }
+
+    units.min(MAX_ATTACK_UNITS)
+}
+
+/// Pawn shield, pawn storm and open files on and beside `side`'s king's file
+pub fn king_shelter<T: EvaluationTracer>(position: &OxidePosition, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    let king = position.king_square(side);
+    let king_file = king.to_mask().file_fill();
+    // Pawns behind the king don't shelter it or attack it
+    let in_front = forward_fill(king.to_mask().rank_fill(), side);
+    let ours = pawns(position, side);
+    let theirs = pawns(position, side.opposite_side());
+    let nearest_rank = |mask: OxideBitboard| mask.map(|square| relative_rank(square, side)).min().unwrap_or(0);
+
+    [king_file.west_shift(), king_file, king_file.east_shift()].iter()
+        .filter(|&&file| file != OxideBitboard::EMPTY)
+        .fold(TaperedScore::ZERO, |mut score, &file| {
+            let (shield_rank, storm_rank) = (nearest_rank(ours & file & in_front), nearest_rank(theirs & file & in_front));
+            tracer.record_score_weight("pawn_shield", shield_rank, side, 1.0);
+            tracer.record_score_weight("pawn_storm", storm_rank, side, 1.0);
+            score += parameters.pawn_shield[shield_rank];
+            score += parameters.pawn_storm[storm_rank];
+            if (ours | theirs) & file == OxideBitboard::EMPTY {
+                tracer.record_score_weight("king_open_file", 0, side, 1.0);
+                score += parameters.king_open_file;
+            }
+
+            score
+        })
+}
+
+/// Safety of `side`'s king, positive when it's safe
+pub fn king_safety<T: EvaluationTracer>(position: &OxidePosition, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    let (attackers, safe_checks) = king_attackers(position, side);
+    let units = attack_units(&attackers, &safe_checks, parameters);
+    if T::ENABLED {
+        tracer.record_score_weight("king_danger", 0, side, (units * units) as f64 / 256.0);
+        // Below the cap each unit moves the squared penalty by twice the units times the danger
+        if units < MAX_ATTACK_UNITS {
+            let rate = 2.0 * units as f64 / 256.0;
+            let (midgame, endgame) = (rate * parameters.king_danger.midgame() as f64, rate * parameters.king_danger.endgame() as f64);
+            for index in 0..KING_ATTACKERS.len() {
+                if is_supported_attack(&attackers) && attackers[index] > 0 {
+                    let count = attackers[index] as f64;
+                    tracer.record_value_weight("king_attack_units", index, side, midgame * count, endgame * count);
+                }
+                if safe_checks[index] {
+                    tracer.record_value_weight("safe_check_units", index, side, midgame, endgame);
+                }
+            }
+        }
+    }
+
+    king_shelter(position, side, parameters, tracer) + parameters.king_danger.scale(units * units, 256)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+    use crate::trace::NoTrace;
+
+    fn position(fen: &str) -> OxidePosition {
+        OxidePosition::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn king_zone_works() {
+        let zone = king_zone(&position("6k1/8/8/8/8/8/8/6K1 w - - 0 1"), OxideSide::White);
+        assert_eq!(zone, OxideBitboard(0xE0E0E0));
+        let zone = king_zone(&position("6k1/8/8/8/8/8/8/6K1 w - - 0 1"), OxideSide::Black);
+        assert_eq!(zone, OxideBitboard(0xE0E0E0 << 40));
+    }
+
+    #[test]
+    fn side_attacks_works() {
+        let attacks = side_attacks(&position("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"), OxideSide::White);
+        assert_eq!(attacks, OxideBitboard(0x28_3828));
+    }
+
+    #[test]
+    fn king_attack_units_works() {
+        let parameters = EvaluationParameters::default();
+        let units = |fen: &str| {
+            let (attackers, safe_checks) = king_attackers(&position(fen), OxideSide::White);
+            attack_units(&attackers, &safe_checks, &parameters)
+        };
+        assert_eq!(units("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1"), 0);
+        // A lone knight only counts for its safe check
+        assert_eq!(units("4k3/8/8/8/8/5n2/5PPP/6K1 w - - 0 1"), 0);
+        assert_eq!(units("4k3/8/8/8/8/2n5/5PPP/6K1 w - - 0 1"), parameters.safe_check_units[0]);
+        // Checks on defended squares aren't safe
+        assert_eq!(units("4k3/8/8/8/7n/8/5PPP/6K1 w - - 0 1"), 0);
+        // Queen and rook both attack the zone
+        let queen_and_rook = units("4k3/8/8/8/8/6q1/5P1P/5rK1 w - - 0 1");
+        assert!(queen_and_rook >= parameters.king_attack_units[2] + parameters.king_attack_units[3]);
+    }
+
+    #[test]
+    fn king_shelter_works() {
+        let parameters = EvaluationParameters::default();
+        let shelter = |fen: &str| king_shelter(&position(fen), OxideSide::White, &parameters, &mut NoTrace);
+        let sheltered = shelter("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(sheltered, parameters.pawn_shield[1] * 3);
+        assert!(shelter("4k3/8/8/8/5PPP/8/8/6K1 w - - 0 1").midgame() < sheltered.midgame());
+        assert!(shelter("4k3/8/8/8/8/8/8/6K1 w - - 0 1").midgame() < shelter("4k3/8/8/8/8/8/6P1/6K1 w - - 0 1").midgame());
+        assert!(shelter("4k3/8/8/8/8/5ppp/5PPP/6K1 w - - 0 1").midgame() < sheltered.midgame());
+        // Only the king's file and the one beside it on the edge count
+        assert_eq!(shelter("4k3/8/8/8/8/8/PP6/K7 w - - 0 1"), parameters.pawn_shield[1] * 2);
+    }
+
+    #[test]
+    fn king_safety_is_symmetric() {
+        let parameters = EvaluationParameters::default();
+        let white = king_safety(&position("r4rk1/5ppp/8/8/8/6q1/5P1P/5RK1 w - - 0 1"), OxideSide::White, &parameters, &mut NoTrace);
+        let black = king_safety(&position("5rk1/5p1p/6Q1/8/8/8/5PPP/R4RK1 b - - 0 1"), OxideSide::Black, &parameters, &mut NoTrace);
+        assert_eq!(white, black);
+    }
+}
@@ -0,0 +1,17 @@
+mod activity;
+mod evaluator;
+mod king_safety;
+mod nnue;
+mod parameter_file;
+mod parameters;
+mod pawns;
+mod selectable;
+mod trace;
+
+pub use evaluator::{HandcraftedEvaluator, EVAL_PARAMETERS_OPTION};
+pub use parameters::{EvaluationParameters, ParameterVisitor};
+pub use parameter_file::{ParameterFileError, MAX_PARAMETER_VALUE};
+pub use pawns::{PawnEntry, PawnTable, DEFAULT_PAWN_TABLE_SIZE};
+pub use trace::{EvaluationTerm, EvaluationTrace, EvaluationTracer, NoTrace};
+pub use nnue::{Accumulator, FeatureSet, Network, NetworkError, NnueEvaluator, NETWORK_MAGIC, NETWORK_VERSION, MAX_HIDDEN_SIZE, HIDDEN_SIZE_ALIGNMENT, ACTIVATION_SCALE, OUTPUT_WEIGHT_SCALE, EVALUATION_SCALE};
+pub use selectable::{SelectableEvaluator, EVAL_FILE_OPTION, USE_NNUE_OPTION};
@@ -0,0 +1,91 @@
+use interface::game::{PieceArrangement, SidedPiece, Side};
+use oxide_interface::engine::OxidePosition;
+use oxide_interface::game::{OxideSide, OxideSidedPiece, OxideSquare};
+use crate::nnue::network::Network;
+use crate::nnue::simd::{add_assign, sub_assign};
+
+#[inline]
+fn side_index(side: OxideSide) -> usize {
+    if side.is_white() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Hidden layer of each perspective for a position, with the kings it was computed for
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+    kings: [Option<OxideSquare>; 2],
+}
+
+impl Accumulator {
+    /// An accumulator to refresh before use
+    pub fn new(network: &Network) -> Self {
+        Self {
+            values: [vec![0; network.hidden_size()], vec![0; network.hidden_size()]],
+            kings: [None; 2],
+        }
+    }
+
+    /// Hidden layer seen from a side
+    #[inline]
+    pub fn values(&self, perspective: OxideSide) -> &[i16] {
+        &self.values[side_index(perspective)]
+    }
+
+    /// Recompute a perspective's hidden layer from every piece on the board
+    pub fn refresh(&mut self, network: &Network, position: &OxidePosition, perspective: OxideSide) {
+        let king = position.king_square(perspective);
+        let values = &mut self.values[side_index(perspective)];
+        values.copy_from_slice(network.feature_biases());
+        for &piece in <OxideSidedPiece as SidedPiece<OxidePosition>>::PIECES.iter() {
+            for square in position.sided_piece_mask(piece) {
+                if let Some(feature) = network.feature_set().feature_index(perspective, king, piece, square) {
+                    add_assign(values, network.feature_weights(feature));
+                }
+            }
+        }
+        self.kings[side_index(perspective)] = Some(king);
+    }
+
+    /// Recompute both perspectives
+    pub fn refresh_all(&mut self, network: &Network, position: &OxidePosition) {
+        self.refresh(network, position, OxideSide::White);
+        self.refresh(network, position, OxideSide::Black);
+    }
+
+    /// Take on another accumulator's values, reusing this one's allocations
+    #[inline]
+    pub fn copy_from(&mut self, other: &Accumulator) {
+        self.values[0].copy_from_slice(&other.values[0]);
+        self.values[1].copy_from_slice(&other.values[1]);
+        self.kings = other.kings;
+    }
+
+    /// Bring an accumulator of a move's parent up to date with the position the move reached from the pieces it removed and added,
+    /// refreshing a perspective instead when its king moved (every one of its features depends on the king's square)
+    pub fn apply(&mut self, network: &Network, position: &OxidePosition, removed: &[(OxideSidedPiece, OxideSquare)], added: &[(OxideSidedPiece, OxideSquare)]) {
+        for &perspective in &[OxideSide::White, OxideSide::Black] {
+            let king = position.king_square(perspective);
+            if self.kings[side_index(perspective)] != Some(king) {
+                self.refresh(network, position, perspective);
+                continue;
+            }
+
+            let values = &mut self.values[side_index(perspective)];
+            let feature_set = network.feature_set();
+            for &(piece, square) in removed {
+                if let Some(feature) = feature_set.feature_index(perspective, king, piece, square) {
+                    sub_assign(values, network.feature_weights(feature));
+                }
+            }
+            for &(piece, square) in added {
+                if let Some(feature) = feature_set.feature_index(perspective, king, piece, square) {
+                    add_assign(values, network.feature_weights(feature));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+pub(crate) mod network;
+mod accumulator;
+mod simd;
+
+pub use network::{Network, NetworkError, FeatureSet, NETWORK_MAGIC, NETWORK_VERSION, MAX_HIDDEN_SIZE, HIDDEN_SIZE_ALIGNMENT, ACTIVATION_SCALE, OUTPUT_WEIGHT_SCALE, EVALUATION_SCALE};
+pub use accumulator::Accumulator;
+
+use std::sync::Arc;
+use interface::engine::{Evaluator, PositionalScore};
+use interface::game::{Position, Side};
+use oxide_interface::engine::{OxidePosition, OxideScore};
+use oxide_interface::game::{OxideSidedPiece, OxideSquare};
+use crate::nnue::simd::clipped_dot;
+
+// Keeps network scores clear of mate scores
+const MAX_NETWORK_SCORE: i32 = 20_000;
+
+/// Neural network evaluation, each clone keeping its own accumulators so search threads can share the network
+#[derive(Clone, Debug)]
+pub struct NnueEvaluator {
+    network: Arc<Network>,
+    // Accumulator of the position each pushed move reached, the first `ply` in use and the rest kept allocated for later plies
+    accumulators: Vec<Accumulator>,
+    ply: usize,
+    // Position the pushed moves start from, refreshed on reset and for positions evaluated without any pushed moves
+    root: Accumulator,
+}
+
+impl NnueEvaluator {
+    pub fn new(network: Arc<Network>) -> Self {
+        let root = Accumulator::new(&network);
+
+        Self {
+            network,
+            accumulators: Vec::new(),
+            ply: 0,
+            root,
+        }
+    }
+
+    #[inline]
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    /// Accumulator of the last pushed move, or of the last evaluated position when none are pushed
+    #[inline]
+    pub fn accumulator(&self) -> &Accumulator {
+        if self.ply > 0 {
+            &self.accumulators[self.ply - 1]
+        } else {
+            &self.root
+        }
+    }
+}
+
+impl Evaluator<OxidePosition> for NnueEvaluator {
+    type Score = OxideScore;
+
+    fn evaluate(&mut self, position: &OxidePosition) -> OxideScore {
+        if self.ply == 0 {
+            self.root.refresh_all(&self.network, position);
+        }
+        let accumulator = self.accumulator();
+        let side = position.side_to_move();
+        let (our_weights, their_weights) = self.network.output_weights();
+        let output = clipped_dot(accumulator.values(side), our_weights)
+            + clipped_dot(accumulator.values(side.opposite_side()), their_weights)
+            + self.network.output_bias();
+        let score = (output as i64 * EVALUATION_SCALE as i64 / (ACTIVATION_SCALE * OUTPUT_WEIGHT_SCALE) as i64) as i32;
+
+        OxideScore::new(score.max(-MAX_NETWORK_SCORE).min(MAX_NETWORK_SCORE))
+    }
+
+    fn reset(&mut self, position: &OxidePosition) {
+        self.ply = 0;
+        self.root.refresh_all(&self.network, position);
+    }
+
+    fn push_move(&mut self, position: &OxidePosition, removed: &[(OxideSidedPiece, OxideSquare)], added: &[(OxideSidedPiece, OxideSquare)]) {
+        if self.accumulators.len() == self.ply {
+            self.accumulators.push(Accumulator::new(&self.network));
+        }
+
+        let (parents, children) = self.accumulators.split_at_mut(self.ply);
+        children[0].copy_from(parents.last().unwrap_or(&self.root));
+        children[0].apply(&self.network, position, removed, added);
+        self.ply += 1;
+    }
+
+    fn pop_move(&mut self) {
+        debug_assert!(self.ply > 0, "Popping a move that wasn't pushed");
+        self.ply -= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::engine::Board;
+    use interface::game::ChessMove;
+    use oxide_interface::engine::OxideBoard;
+    use oxide_interface::game::{OxideMove, OxideSide, OxideSquare::*};
+    use crate::nnue::network::test::network;
+
+    fn position(fen: &str) -> OxidePosition {
+        OxidePosition::from_fen(fen).unwrap()
+    }
+
+    fn refreshed(network: &Arc<Network>, position: &OxidePosition) -> Accumulator {
+        let mut accumulator = Accumulator::new(network);
+        accumulator.refresh_all(network, position);
+        accumulator
+    }
+
+    #[test]
+    fn pushed_moves_match_refresh() {
+        for &feature_set in &[FeatureSet::HalfKP, FeatureSet::HalfKA] {
+            let network = Arc::new(network(feature_set));
+            let mut evaluator = NnueEvaluator::new(network.clone());
+            let mut board = OxideBoard::new(position("r3k2r/pppq1ppp/2n5/4p3/4P3/5N2/PPPQ1PPP/R3K2R w KQkq - 0 1"));
+            evaluator.reset(board.position());
+            assert_eq!(evaluator.accumulator(), &refreshed(&network, board.position()));
+            let moves = [
+                // A capture and a recapture
+                OxideMove::new_capture(F3, E5),
+                OxideMove::new_capture(C6, E5),
+                // A castle refreshes the mover's perspective and moves a rook in the other
+                OxideMove::WHITE_QUEEN_CASTLE,
+                OxideMove::new_capture(D7, D2),
+                // A king capture refreshes its own perspective
+                OxideMove::new_capture(C1, D2),
+            ];
+            let mut states = Vec::new();
+            for &chess_move in &moves {
+                let changes = board.piece_changes(chess_move);
+                states.push((chess_move, board.make_move_unchecked(chess_move)));
+                evaluator.push_move(board.position(), changes.removed(), changes.added());
+                assert_eq!(evaluator.accumulator(), &refreshed(&network, board.position()), "Pushing {}", chess_move);
+                assert_eq!(evaluator.evaluate(board.position()), NnueEvaluator::new(network.clone()).evaluate(board.position()));
+            }
+
+            // A null move keeps the accumulator
+            let state = board.make_null_move();
+            evaluator.push_move(board.position(), &[], &[]);
+            assert_eq!(evaluator.accumulator(), &refreshed(&network, board.position()));
+            evaluator.pop_move();
+            board.undo_null_move(state);
+
+            // Popping goes back through every position
+            while let Some((chess_move, state)) = states.pop() {
+                evaluator.pop_move();
+                board.undo_move_unchecked(chess_move, state);
+                if !states.is_empty() {
+                    assert_eq!(evaluator.accumulator(), &refreshed(&network, board.position()), "Popping {}", chess_move);
+                }
+            }
+            let score = evaluator.evaluate(board.position());
+            assert_eq!(evaluator.accumulator(), &refreshed(&network, board.position()));
+            assert_eq!(score, NnueEvaluator::new(network.clone()).evaluate(board.position()));
+        }
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        let network = Arc::new(network(FeatureSet::HalfKA));
+        let mut evaluator = NnueEvaluator::new(network);
+        let white = evaluator.evaluate(&position("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"));
+        let black = evaluator.evaluate(&position("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3"));
+        assert_eq!(white, black);
+        assert!(white.centipawns().abs() <= MAX_NETWORK_SCORE);
+        assert_eq!(evaluator.accumulator().values(OxideSide::White).len(), 32);
+    }
+}
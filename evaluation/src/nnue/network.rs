@@ -0,0 +1,303 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::fs;
+use std::path::Path;
+use interface::game::{Piece, SidedPiece, Side, Square};
+use oxide_interface::engine::OxidePosition;
+use oxide_interface::game::{OxidePiece, OxideSide, OxideSidedPiece, OxideSquare};
+
+/// First bytes of every network file
+pub const NETWORK_MAGIC: [u8; 4] = *b"OXNN";
+/// Version of the network file layout this build reads
+pub const NETWORK_VERSION: u32 = 1;
+/// Largest hidden layer a network file may have
+pub const MAX_HIDDEN_SIZE: usize = 2048;
+/// Hidden layer sizes must be a multiple of this so the SIMD paths never need a remainder loop
+pub const HIDDEN_SIZE_ALIGNMENT: usize = 16;
+/// Value of a fully active hidden neuron, the clipped ReLU's ceiling
+pub const ACTIVATION_SCALE: i32 = 255;
+/// Quantization factor of the output weights
+pub const OUTPUT_WEIGHT_SCALE: i32 = 64;
+/// Centipawns per unit of network output
+pub const EVALUATION_SCALE: i32 = 400;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetworkError {
+    Io(String), // File couldn't be read
+    InvalidMagic, // File doesn't start with the network magic
+    UnsupportedVersion(u32), // File layout is from a different version
+    UnknownFeatureSet(u32), // Feature set id isn't HalfKP or HalfKA
+    InvalidHiddenSize(usize), // Hidden layer is empty, too big or not a multiple of the SIMD width
+    InvalidLength { expected: usize, found: usize }, // File bytes or weights don't match the declared shape
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            NetworkError::Io(error) => write!(f, "Couldn't read network ({})", error),
+            NetworkError::InvalidMagic => write!(f, "Not a network file"),
+            NetworkError::UnsupportedVersion(version) => write!(f, "Unsupported network version {}", version),
+            NetworkError::UnknownFeatureSet(id) => write!(f, "Unknown feature set {}", id),
+            NetworkError::InvalidHiddenSize(size) => write!(f, "Invalid hidden layer size {}", size),
+            NetworkError::InvalidLength { expected, found } => write!(f, "Expected a network of length {} but found {}", expected, found),
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+/// How a position is turned into the network's input features
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FeatureSet {
+    HalfKP, // Every non-king piece's square relative to the perspective's king
+    HalfKA, // Every piece's square, kings included, relative to the perspective's king
+}
+
+impl FeatureSet {
+    /// Id stored in network files
+    pub const fn id(self) -> u32 {
+        match self {
+            FeatureSet::HalfKP => 0,
+            FeatureSet::HalfKA => 1,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Result<Self, NetworkError> {
+        match id {
+            0 => Ok(FeatureSet::HalfKP),
+            1 => Ok(FeatureSet::HalfKA),
+            _ => Err(NetworkError::UnknownFeatureSet(id)),
+        }
+    }
+
+    // Number of piece kinds per king square
+    const fn piece_kinds(self) -> usize {
+        match self {
+            FeatureSet::HalfKP => 10,
+            FeatureSet::HalfKA => 12,
+        }
+    }
+
+    /// Number of input features
+    pub const fn feature_count(self) -> usize {
+        64 * self.piece_kinds() * 64
+    }
+
+    /// Feature for a piece on a square seen from `perspective` with its king on `king`, or `None` if the feature set ignores the piece
+    pub fn feature_index(self, perspective: OxideSide, king: OxideSquare, piece: OxideSidedPiece, square: OxideSquare) -> Option<usize> {
+        let unsided_piece = SidedPiece::<OxidePosition>::unsided_piece(&piece);
+        if self == FeatureSet::HalfKP && unsided_piece == OxidePiece::King {
+            return None;
+        }
+        let piece_type = <OxidePiece as Piece<OxidePosition>>::PIECES.iter().position(|&other| other == unsided_piece)?;
+        let piece_side = SidedPiece::<OxidePosition>::side(&piece);
+        let is_theirs = (piece_side.is_white() != perspective.is_white()) as usize;
+        // Black sees the board flipped so both perspectives share the weights
+        let orient = |square: OxideSquare| {
+            let offset = Square::<OxidePosition>::offset(&square) as usize;
+            if perspective.is_white() { offset } else { offset ^ 56 }
+        };
+        let piece_kind = is_theirs * (self.piece_kinds() / 2) + piece_type;
+
+        Some((orient(king) * self.piece_kinds() + piece_kind) * 64 + orient(square))
+    }
+}
+
+/// Quantized weights of a network with one hidden layer per perspective
+///
+/// Network files are little endian: the magic, version (u32), feature set id (u32), hidden size (u32),
+/// feature weights (i16, feature major), feature biases (i16), output weights (i16, side to move's half first) and output bias (i32)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Network {
+    feature_set: FeatureSet,
+    hidden_size: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+// Reads little endian values from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> &'a [u8] {
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        taken
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take(4).try_into().expect("Took 4 bytes"))
+    }
+
+    fn i32(&mut self) -> i32 {
+        i32::from_le_bytes(self.take(4).try_into().expect("Took 4 bytes"))
+    }
+
+    fn i16s(&mut self, count: usize) -> Vec<i16> {
+        self.take(count * 2).chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+    }
+}
+
+const HEADER_BYTES: usize = 16;
+
+impl Network {
+    /// Create a network from its weights, checking they match the feature set and hidden size
+    pub fn new(feature_set: FeatureSet, hidden_size: usize, feature_weights: Vec<i16>, feature_biases: Vec<i16>, output_weights: Vec<i16>, output_bias: i32) -> Result<Self, NetworkError> {
+        if hidden_size == 0 || hidden_size > MAX_HIDDEN_SIZE || hidden_size % HIDDEN_SIZE_ALIGNMENT != 0 {
+            return Err(NetworkError::InvalidHiddenSize(hidden_size));
+        }
+        let expected = (feature_set.feature_count() + 3) * hidden_size;
+        let found = feature_weights.len() + feature_biases.len() + output_weights.len();
+        if feature_weights.len() != feature_set.feature_count() * hidden_size || feature_biases.len() != hidden_size || output_weights.len() != 2 * hidden_size {
+            return Err(NetworkError::InvalidLength { expected, found });
+        }
+
+        Ok(Self { feature_set, hidden_size, feature_weights, feature_biases, output_weights, output_bias })
+    }
+
+    /// Read a network file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, NetworkError> {
+        let bytes = fs::read(path).map_err(|error| NetworkError::Io(error.to_string()))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse the contents of a network file
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        if bytes.len() < HEADER_BYTES {
+            return Err(NetworkError::InvalidLength { expected: HEADER_BYTES, found: bytes.len() });
+        }
+        let mut reader = Reader { bytes };
+        if reader.take(4) != NETWORK_MAGIC {
+            return Err(NetworkError::InvalidMagic);
+        }
+        let version = reader.u32();
+        if version != NETWORK_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+        let feature_set = FeatureSet::from_id(reader.u32())?;
+        let hidden_size = reader.u32() as usize;
+        if hidden_size == 0 || hidden_size > MAX_HIDDEN_SIZE || hidden_size % HIDDEN_SIZE_ALIGNMENT != 0 {
+            return Err(NetworkError::InvalidHiddenSize(hidden_size));
+        }
+        let expected = HEADER_BYTES + (feature_set.feature_count() + 3) * hidden_size * 2 + 4;
+        if bytes.len() != expected {
+            return Err(NetworkError::InvalidLength { expected, found: bytes.len() });
+        }
+
+        let feature_weights = reader.i16s(feature_set.feature_count() * hidden_size);
+        let feature_biases = reader.i16s(hidden_size);
+        let output_weights = reader.i16s(2 * hidden_size);
+        let output_bias = reader.i32();
+
+        Self::new(feature_set, hidden_size, feature_weights, feature_biases, output_weights, output_bias)
+    }
+
+    /// The network in the file format `from_bytes` reads
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + (self.feature_weights.len() + 3 * self.hidden_size) * 2 + 4);
+        bytes.extend_from_slice(&NETWORK_MAGIC);
+        bytes.extend_from_slice(&NETWORK_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.feature_set.id().to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden_size as u32).to_le_bytes());
+        for weight in self.feature_weights.iter().chain(&self.feature_biases).chain(&self.output_weights) {
+            bytes.extend_from_slice(&weight.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+
+        bytes
+    }
+
+    #[inline]
+    pub fn feature_set(&self) -> FeatureSet {
+        self.feature_set
+    }
+
+    #[inline]
+    pub fn hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
+    /// Hidden layer weights of one input feature
+    #[inline]
+    pub(crate) fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden_size..(feature + 1) * self.hidden_size]
+    }
+
+    #[inline]
+    pub(crate) fn feature_biases(&self) -> &[i16] {
+        &self.feature_biases
+    }
+
+    /// Output weights for the side to move's hidden layer, then the other side's
+    #[inline]
+    pub(crate) fn output_weights(&self) -> (&[i16], &[i16]) {
+        self.output_weights.split_at(self.hidden_size)
+    }
+
+    #[inline]
+    pub(crate) fn output_bias(&self) -> i32 {
+        self.output_bias
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use oxide_interface::game::OxideSquare::*;
+
+    /// A small network with arbitrary but deterministic weights
+    pub(crate) fn network(feature_set: FeatureSet) -> Network {
+        let hidden_size = 32;
+        let mut seed = 0x9E37_79B9_7F4A_7C15u64;
+        let mut next = |range: i64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            ((seed % (2 * range as u64 + 1)) as i64 - range) as i16
+        };
+        let feature_weights = (0..feature_set.feature_count() * hidden_size).map(|_| next(40)).collect();
+        let feature_biases = (0..hidden_size).map(|_| next(100)).collect();
+        let output_weights = (0..2 * hidden_size).map(|_| next(120)).collect();
+
+        Network::new(feature_set, hidden_size, feature_weights, feature_biases, output_weights, 1000).unwrap()
+    }
+
+    #[test]
+    fn feature_index_works() {
+        let index = |feature_set: FeatureSet, perspective, king, piece, square| feature_set.feature_index(perspective, king, piece, square);
+        assert_eq!(index(FeatureSet::HalfKP, OxideSide::White, A1, OxideSidedPiece::WhitePawn, A1), Some(0));
+        assert_eq!(index(FeatureSet::HalfKP, OxideSide::White, E1, OxideSidedPiece::BlackKnight, C3), Some((4 * 10 + 6) * 64 + 18));
+        assert_eq!(index(FeatureSet::HalfKP, OxideSide::White, E1, OxideSidedPiece::WhiteKing, E1), None);
+        // Black's view is white's mirrored with the colours swapped
+        assert_eq!(
+            index(FeatureSet::HalfKA, OxideSide::Black, E8, OxideSidedPiece::WhiteQueen, D4),
+            index(FeatureSet::HalfKA, OxideSide::White, E1, OxideSidedPiece::BlackQueen, D5),
+        );
+        assert_eq!(index(FeatureSet::HalfKA, OxideSide::White, H8, OxideSidedPiece::BlackKing, H8), Some(FeatureSet::HalfKA.feature_count() - 1));
+    }
+
+    #[test]
+    fn network_round_trips() {
+        let network = network(FeatureSet::HalfKP);
+        assert_eq!(Network::from_bytes(&network.to_bytes()), Ok(network));
+    }
+
+    #[test]
+    fn invalid_networks_are_rejected() {
+        let bytes = network(FeatureSet::HalfKA).to_bytes();
+        assert_eq!(Network::from_bytes(b"OXNN"), Err(NetworkError::InvalidLength { expected: 16, found: 4 }));
+        assert_eq!(Network::from_bytes(&[b"NNUE", &bytes[4..]].concat()), Err(NetworkError::InvalidMagic));
+        assert_eq!(Network::from_bytes(&[&bytes[..4], &2u32.to_le_bytes(), &bytes[8..]].concat()), Err(NetworkError::UnsupportedVersion(2)));
+        assert_eq!(Network::from_bytes(&[&bytes[..8], &7u32.to_le_bytes(), &bytes[12..]].concat()), Err(NetworkError::UnknownFeatureSet(7)));
+        assert_eq!(Network::from_bytes(&[&bytes[..12], &24u32.to_le_bytes(), &bytes[16..]].concat()), Err(NetworkError::InvalidHiddenSize(24)));
+        assert!(matches!(Network::from_bytes(&bytes[..bytes.len() - 1]), Err(NetworkError::InvalidLength { .. })));
+        assert!(matches!(Network::load("/nonexistent/network.nnue"), Err(NetworkError::Io(_))));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::nnue::network::ACTIVATION_SCALE;
+
+// Every slice is a hidden layer, so the same length and a multiple of 16 (one AVX2 register of i16)
+
+#[inline]
+fn add_assign_scalar(values: &mut [i16], weights: &[i16]) {
+    values.iter_mut().zip(weights).for_each(|(value, &weight)| *value = value.wrapping_add(weight));
+}
+
+#[inline]
+fn sub_assign_scalar(values: &mut [i16], weights: &[i16]) {
+    values.iter_mut().zip(weights).for_each(|(value, &weight)| *value = value.wrapping_sub(weight));
+}
+
+#[inline]
+fn clipped_dot_scalar(values: &[i16], weights: &[i16]) -> i32 {
+    values.iter().zip(weights)
+        .map(|(&value, &weight)| (value as i32).max(0).min(ACTIVATION_SCALE) * weight as i32)
+        .fold(0i32, |sum, product| sum.wrapping_add(product))
+}
+
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+mod avx2 {
+    use std::arch::x86_64::*;
+    use crate::nnue::network::ACTIVATION_SCALE;
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn add_assign(values: &mut [i16], weights: &[i16]) {
+        for (values, weights) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            let sum = _mm256_add_epi16(_mm256_loadu_si256(values.as_ptr() as *const __m256i), _mm256_loadu_si256(weights.as_ptr() as *const __m256i));
+            _mm256_storeu_si256(values.as_mut_ptr() as *mut __m256i, sum);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn sub_assign(values: &mut [i16], weights: &[i16]) {
+        for (values, weights) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            let difference = _mm256_sub_epi16(_mm256_loadu_si256(values.as_ptr() as *const __m256i), _mm256_loadu_si256(weights.as_ptr() as *const __m256i));
+            _mm256_storeu_si256(values.as_mut_ptr() as *mut __m256i, difference);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn clipped_dot(values: &[i16], weights: &[i16]) -> i32 {
+        let (floor, ceiling) = (_mm256_setzero_si256(), _mm256_set1_epi16(ACTIVATION_SCALE as i16));
+        let mut sums = _mm256_setzero_si256();
+        for (values, weights) in values.chunks_exact(16).zip(weights.chunks_exact(16)) {
+            let activated = _mm256_min_epi16(_mm256_max_epi16(_mm256_loadu_si256(values.as_ptr() as *const __m256i), floor), ceiling);
+            // Multiplies the i16 pairs and adds neighbouring products into i32 lanes
+            sums = _mm256_add_epi32(sums, _mm256_madd_epi16(activated, _mm256_loadu_si256(weights.as_ptr() as *const __m256i)));
+        }
+        let halves = _mm_add_epi32(_mm256_castsi256_si128(sums), _mm256_extracti128_si256(sums, 1));
+        let quarters = _mm_add_epi32(halves, _mm_unpackhi_epi64(halves, halves));
+
+        _mm_cvtsi128_si32(_mm_add_epi32(quarters, _mm_shuffle_epi32(quarters, 1)))
+    }
+}
+
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+#[inline]
+fn has_avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+/// Add a feature's weights to an accumulator
+#[inline]
+pub(crate) fn add_assign(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), weights.len());
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            return unsafe { avx2::add_assign(values, weights) };
+        }
+    }
+
+    add_assign_scalar(values, weights)
+}
+
+/// Subtract a feature's weights from an accumulator
+#[inline]
+pub(crate) fn sub_assign(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), weights.len());
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            return unsafe { avx2::sub_assign(values, weights) };
+        }
+    }
+
+    sub_assign_scalar(values, weights)
+}
+
+/// Dot product of the clipped ReLU of an accumulator with the output weights
+#[inline]
+pub(crate) fn clipped_dot(values: &[i16], weights: &[i16]) -> i32 {
+    debug_assert_eq!(values.len(), weights.len());
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            return unsafe { avx2::clipped_dot(values, weights) };
+        }
+    }
+
+    clipped_dot_scalar(values, weights)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn values() -> (Vec<i16>, Vec<i16>) {
+        let values = (0..64).map(|index| (index * 37 % 601) as i16 - 200).collect();
+        let weights = (0..64).map(|index| (index * 53 % 257) as i16 - 128).collect();
+        (values, weights)
+    }
+
+    #[test]
+    fn clipped_dot_works() {
+        assert_eq!(clipped_dot(&[-5, 10, 300, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1], &[7, 2, -1, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]), 20 - 255 + 1);
+        let (values, weights) = values();
+        assert_eq!(clipped_dot(&values, &weights), clipped_dot_scalar(&values, &weights));
+    }
+
+    #[test]
+    fn add_and_sub_work() {
+        let (values, weights) = values();
+        let mut accumulated = values.clone();
+        add_assign(&mut accumulated, &weights);
+        let mut expected = values.clone();
+        add_assign_scalar(&mut expected, &weights);
+        assert_eq!(accumulated, expected);
+        sub_assign(&mut accumulated, &weights);
+        assert_eq!(accumulated, values);
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::fs;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+use oxide_interface::engine::TaperedScore;
+use crate::parameters::{EvaluationParameters, ParameterVisitor};
+
+/// Largest magnitude of any value in a parameter file, keeping sums of scores within their 16 bit halves
+pub const MAX_PARAMETER_VALUE: i32 = 2000;
+// Longer tables are written with this many entries per line, a square table a rank per line
+const ENTRIES_PER_LINE: usize = 8;
+const MAX_INLINE_ENTRIES: usize = 15;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParameterFileError {
+    Io(String), // Reading or writing the file failed
+    Syntax(usize, String), // File isn't a list of `name = value` lines (line, reason)
+    UnknownKey(String), // Key doesn't name a parameter
+    DuplicateKey(String), // Parameter is set more than once
+    InvalidValue(String, String), // Value has the wrong shape for its parameter (name, expected shape)
+    OutOfRange(String), // Value is outside the range allowed for its parameter
+}
+
+impl Display for ParameterFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            ParameterFileError::Io(reason) => write!(f, "Failed to access parameter file ({})", reason),
+            ParameterFileError::Syntax(line, reason) => write!(f, "Invalid parameter file on line {} ({})", line, reason),
+            ParameterFileError::UnknownKey(name) => write!(f, "Unknown evaluation parameter {}", name),
+            ParameterFileError::DuplicateKey(name) => write!(f, "Evaluation parameter {} is set more than once", name),
+            ParameterFileError::InvalidValue(name, expected) => write!(f, "Expected {} for evaluation parameter {}", expected, name),
+            ParameterFileError::OutOfRange(name) => write!(f, "Value out of range for evaluation parameter {}", name),
+        }
+    }
+}
+
+impl Error for ParameterFileError {}
+
+// The subset of TOML parameter files use, integers and arrays of them
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Value {
+    Integer(i64),
+    Array(Vec<Value>),
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn error<T>(&self, reason: &str) -> Result<T, ParameterFileError> {
+        Err(ParameterFileError::Syntax(self.line, reason.to_string()))
+    }
+
+    // Skip spaces and comments, and line breaks too inside arrays
+    fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(&next) = self.chars.peek() {
+            match next {
+                '#' => while !matches!(self.chars.peek(), None | Some('\n')) {
+                    self.chars.next();
+                },
+                '\n' if newlines => {
+                    self.line += 1;
+                    self.chars.next();
+                },
+                ' ' | '\t' | '\r' => {
+                    self.chars.next();
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String, ParameterFileError> {
+        let mut key = String::new();
+        while let Some(&next) = self.chars.peek().filter(|next| next.is_ascii_alphanumeric() || **next == '_') {
+            key.push(next);
+            self.chars.next();
+        }
+        if key.is_empty() {
+            return self.error("expected a parameter name");
+        }
+
+        Ok(key)
+    }
+
+    fn value(&mut self) -> Result<Value, ParameterFileError> {
+        match self.chars.peek() {
+            Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip_whitespace(true);
+                    if self.chars.peek() == Some(&']') {
+                        self.chars.next();
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_whitespace(true);
+                    match self.chars.next() {
+                        Some(',') => {},
+                        Some(']') => return Ok(Value::Array(values)),
+                        _ => return self.error("expected a comma or the end of the array"),
+                    }
+                }
+            },
+            Some(&next) if next == '-' || next == '+' || next.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(&next) = self.chars.peek().filter(|next| next.is_ascii_digit() || **next == '-' || **next == '+' || **next == '_') {
+                    number.push(next);
+                    self.chars.next();
+                }
+                number.replace('_', "").parse().map(Value::Integer).or_else(|_| self.error("expected an integer"))
+            },
+            _ => self.error("expected an integer or an array"),
+        }
+    }
+
+    // Every `name = value` entry with the line it's on
+    fn entries(mut self) -> Result<HashMap<String, (usize, Value)>, ParameterFileError> {
+        let mut entries = HashMap::new();
+        loop {
+            self.skip_whitespace(true);
+            if self.chars.peek().is_none() {
+                return Ok(entries);
+            }
+
+            let line = self.line;
+            let key = self.key()?;
+            self.skip_whitespace(false);
+            if self.chars.next() != Some('=') {
+                return self.error("expected = after the parameter name");
+            }
+            self.skip_whitespace(false);
+            let value = self.value()?;
+            self.skip_whitespace(false);
+            if !matches!(self.chars.peek(), None | Some('\n')) {
+                return self.error("expected the end of the line");
+            }
+
+            if entries.insert(key.clone(), (line, value)).is_some() {
+                return Err(ParameterFileError::DuplicateKey(key));
+            }
+        }
+    }
+}
+
+fn integer(name: &str, value: &Value) -> Result<i32, ParameterFileError> {
+    match value {
+        Value::Integer(integer) if (-(MAX_PARAMETER_VALUE as i64)..=MAX_PARAMETER_VALUE as i64).contains(integer) => Ok(*integer as i32),
+        Value::Integer(_) => Err(ParameterFileError::OutOfRange(name.to_string())),
+        Value::Array(_) => Err(ParameterFileError::InvalidValue(name.to_string(), "an integer".to_string())),
+    }
+}
+
+fn score(name: &str, value: &Value) -> Result<TaperedScore, ParameterFileError> {
+    match value {
+        Value::Array(pair) if pair.len() == 2 => Ok(TaperedScore::new(integer(name, &pair[0])?, integer(name, &pair[1])?)),
+        _ => Err(ParameterFileError::InvalidValue(name.to_string(), "a [midgame, endgame] pair".to_string())),
+    }
+}
+
+fn array<'a>(name: &str, value: &'a Value, length: usize) -> Result<&'a [Value], ParameterFileError> {
+    match value {
+        Value::Array(values) if values.len() == length => Ok(values),
+        _ => Err(ParameterFileError::InvalidValue(name.to_string(), format!("an array of {} entries", length))),
+    }
+}
+
+// Replaces each parameter set in the file, stopping at the first invalid value
+struct Apply {
+    entries: HashMap<String, (usize, Value)>,
+    result: Result<(), ParameterFileError>,
+}
+
+impl Apply {
+    fn apply<F: FnOnce(&Value) -> Result<(), ParameterFileError>>(&mut self, name: &str, apply: F) {
+        if self.result.is_ok() {
+            if let Some((_, value)) = self.entries.remove(name) {
+                self.result = apply(&value);
+            }
+        }
+    }
+}
+
+impl ParameterVisitor for Apply {
+    fn value(&mut self, name: &'static str, value: &mut i32) {
+        self.apply(name, |entry| {
+            *value = integer(name, entry)?;
+            Ok(())
+        });
+    }
+
+    fn values(&mut self, name: &'static str, values: &mut [i32]) {
+        self.apply(name, |entry| {
+            let entries = array(name, entry, values.len())?;
+            for (value, entry) in values.iter_mut().zip(entries) {
+                *value = integer(name, entry)?;
+            }
+            Ok(())
+        });
+    }
+
+    fn score(&mut self, name: &'static str, value: &mut TaperedScore) {
+        self.apply(name, |entry| {
+            *value = score(name, entry)?;
+            Ok(())
+        });
+    }
+
+    fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]) {
+        self.apply(name, |entry| {
+            let entries = array(name, entry, scores.len())?;
+            for (value, entry) in scores.iter_mut().zip(entries) {
+                *value = score(name, entry)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+// Writes each parameter as a `name = value` line
+struct TomlWriter(String);
+
+impl TomlWriter {
+    fn table(&mut self, name: &str, entries: Vec<String>) {
+        if entries.len() <= MAX_INLINE_ENTRIES {
+            return self.0.push_str(&format!("{} = [{}]\n", name, entries.join(", ")));
+        }
+
+        self.0.push_str(&format!("{} = [\n", name));
+        for line in entries.chunks(ENTRIES_PER_LINE) {
+            self.0.push_str(&format!("    {},\n", line.join(", ")));
+        }
+        self.0.push_str("]\n");
+    }
+}
+
+fn pair(score: &TaperedScore) -> String {
+    format!("[{}, {}]", score.midgame(), score.endgame())
+}
+
+impl ParameterVisitor for TomlWriter {
+    fn value(&mut self, name: &'static str, value: &mut i32) {
+        self.0.push_str(&format!("{} = {}\n", name, value));
+    }
+
+    fn values(&mut self, name: &'static str, values: &mut [i32]) {
+        self.table(name, values.iter().map(i32::to_string).collect());
+    }
+
+    fn score(&mut self, name: &'static str, score: &mut TaperedScore) {
+        self.0.push_str(&format!("{} = {}\n", name, pair(score)));
+    }
+
+    fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]) {
+        self.table(name, scores.iter().map(pair).collect());
+    }
+}
+
+// Smallest and largest value of a parameter, king danger can't be reduced by attacks and the blocked scale is out of 256
+fn bounds(name: &str) -> (i32, i32) {
+    match name {
+        "blocked_passed_pawn_scale" => (0, 256),
+        "king_attack_units" | "safe_check_units" => (0, MAX_PARAMETER_VALUE),
+        _ => (-MAX_PARAMETER_VALUE, MAX_PARAMETER_VALUE),
+    }
+}
+
+// Moves each parameter into its bounds, remembering the first that was outside them
+struct Clamp(Option<&'static str>);
+
+impl Clamp {
+    fn clamp(&mut self, name: &'static str, value: i32) -> i32 {
+        let (low, high) = bounds(name);
+        if value < low || value > high {
+            self.0.get_or_insert(name);
+        }
+
+        value.max(low).min(high)
+    }
+}
+
+impl ParameterVisitor for Clamp {
+    fn value(&mut self, name: &'static str, value: &mut i32) {
+        *value = self.clamp(name, *value);
+    }
+
+    fn values(&mut self, name: &'static str, values: &mut [i32]) {
+        values.iter_mut().for_each(|value| *value = self.clamp(name, *value));
+    }
+
+    fn score(&mut self, name: &'static str, score: &mut TaperedScore) {
+        *score = TaperedScore::new(self.clamp(name, score.midgame()), self.clamp(name, score.endgame()));
+    }
+
+    fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]) {
+        scores.iter_mut().for_each(|score| self.score(name, score));
+    }
+}
+
+impl EvaluationParameters {
+    /// Parse a parameter file, parameters it doesn't set keep their compiled-in values
+    pub fn from_toml(source: &str) -> Result<Self, ParameterFileError> {
+        let entries = Parser::new(source).entries()?;
+        let mut apply = Apply { entries, result: Ok(()) };
+        let mut parameters = Self::default();
+        parameters.visit(&mut apply);
+        apply.result?;
+        // Report the first unknown key in the file
+        if let Some((name, _)) = apply.entries.into_iter().min_by_key(|(_, (line, _))| *line) {
+            return Err(ParameterFileError::UnknownKey(name));
+        }
+        parameters.validate()?;
+
+        Ok(parameters)
+    }
+
+    /// Check every parameter is within the range a parameter file allows, naming the first that isn't
+    pub fn validate(&self) -> Result<(), ParameterFileError> {
+        let mut clamp = Clamp(None);
+        let mut parameters = *self;
+        parameters.visit(&mut clamp);
+
+        match clamp.0 {
+            Some(name) => Err(ParameterFileError::OutOfRange(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Move every parameter into the range a parameter file allows
+    pub fn clamp(&mut self) {
+        self.visit(&mut Clamp(None));
+    }
+
+    /// Every parameter as a parameter file
+    pub fn to_toml(&self) -> String {
+        let mut writer = TomlWriter(String::from("# Oxide evaluation parameters, anything left out keeps its compiled-in value\n"));
+        let mut parameters = *self;
+        parameters.visit(&mut writer);
+
+        writer.0
+    }
+
+    /// Read a parameter file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParameterFileError> {
+        let source = fs::read_to_string(path).map_err(|error| ParameterFileError::Io(error.to_string()))?;
+
+        Self::from_toml(&source)
+    }
+
+    /// Write every parameter to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ParameterFileError> {
+        fs::write(path, self.to_toml()).map_err(|error| ParameterFileError::Io(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn toml_round_trip() {
+        let parameters = EvaluationParameters::default();
+        let toml = parameters.to_toml();
+        assert!(toml.contains("\nblocked_passed_pawn_scale = 160\n"));
+        assert!(toml.contains("\nking_attack_units = [2, 2, 3, 5]\n"));
+        assert!(toml.contains("\nfree_passed_pawn = [5, 25]\n"));
+        assert!(toml.contains("\npiece_values = [[82, 94], [337, 281], [365, 297], [477, 512], [1025, 936], [0, 0]]\n"));
+        assert!(toml.contains("\nknight_squares = [\n    [-167, -58], [-89, -38], [-34, -13], [-49, -28], [61, -31], [-97, -27], [-15, -63], [-107, -99],\n"));
+        assert_eq!(EvaluationParameters::from_toml(&toml), Ok(parameters));
+
+        let shifted = EvaluationParameters::from_values(&parameters.to_values().iter().map(|value| value - 1).collect::<Vec<_>>());
+        assert_eq!(EvaluationParameters::from_toml(&shifted.to_toml()), Ok(shifted));
+    }
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let parameters = EvaluationParameters::from_toml("# Just a couple\nbishop_pair = [30, 60] # more\n\nking_attack_units = [\n  1,\n  2, 3, 4,\n]\n").unwrap();
+        assert_eq!(parameters.bishop_pair, TaperedScore::new(30, 60));
+        assert_eq!(parameters.king_attack_units, [1, 2, 3, 4]);
+        assert_eq!(parameters.rook_open_file, EvaluationParameters::default().rook_open_file);
+        assert_eq!(EvaluationParameters::from_toml(""), Ok(EvaluationParameters::default()));
+    }
+
+    #[test]
+    fn clamp_works() {
+        let mut parameters = EvaluationParameters::default();
+        assert_eq!(parameters.validate(), Ok(()));
+        parameters.blocked_passed_pawn_scale = 300;
+        parameters.king_attack_units[1] = -3;
+        parameters.bishop_pair = TaperedScore::new(-2500, 2100);
+        assert_eq!(parameters.validate(), Err(ParameterFileError::OutOfRange("blocked_passed_pawn_scale".to_string())));
+
+        parameters.clamp();
+        assert_eq!(parameters.validate(), Ok(()));
+        assert_eq!(parameters.blocked_passed_pawn_scale, 256);
+        assert_eq!(parameters.king_attack_units, [2, 0, 3, 5]);
+        assert_eq!(parameters.bishop_pair, TaperedScore::new(-MAX_PARAMETER_VALUE, MAX_PARAMETER_VALUE));
+        assert_eq!(EvaluationParameters::from_toml(&parameters.to_toml()), Ok(parameters));
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let error = |toml: &str| EvaluationParameters::from_toml(toml).unwrap_err();
+        assert_eq!(error("bishop_pair = [30, 60]\nbishop_pear = [1, 2]"), ParameterFileError::UnknownKey("bishop_pear".to_string()));
+        assert_eq!(error("bishop_pair = [30, 60]\nbishop_pair = [1, 2]"), ParameterFileError::DuplicateKey("bishop_pair".to_string()));
+        assert_eq!(error("bishop_pair = 30"), ParameterFileError::InvalidValue("bishop_pair".to_string(), "a [midgame, endgame] pair".to_string()));
+        assert_eq!(error("king_attack_units = [1, 2, 3]"), ParameterFileError::InvalidValue("king_attack_units".to_string(), "an array of 4 entries".to_string()));
+        assert_eq!(error("bishop_pair = [30, 6000]"), ParameterFileError::OutOfRange("bishop_pair".to_string()));
+        // Taking the magnitude of the smallest integer would overflow
+        assert_eq!(error("bishop_pair = [30, -9223372036854775808]"), ParameterFileError::OutOfRange("bishop_pair".to_string()));
+        assert_eq!(error("king_attack_units = [2, -1, 3, 5]"), ParameterFileError::OutOfRange("king_attack_units".to_string()));
+        assert_eq!(error("safe_check_units = [5, 3, -5, 4]"), ParameterFileError::OutOfRange("safe_check_units".to_string()));
+        assert_eq!(error("blocked_passed_pawn_scale = 300"), ParameterFileError::OutOfRange("blocked_passed_pawn_scale".to_string()));
+        assert_eq!(error("\nbishop_pair [30, 60]"), ParameterFileError::Syntax(2, "expected = after the parameter name".to_string()));
+        assert_eq!(error("bishop_pair = [30, 60] 5"), ParameterFileError::Syntax(1, "expected the end of the line".to_string()));
+        assert_eq!(error("bishop_pair = [30,\n 60"), ParameterFileError::Syntax(2, "expected a comma or the end of the array".to_string()));
+        assert_eq!(error("bishop_pair = \"high\""), ParameterFileError::Syntax(1, "expected an integer or an array".to_string()));
+    }
+}
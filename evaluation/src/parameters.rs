@@ -0,0 +1,271 @@
+use oxide_interface::engine::{TaperedScore, MIDGAME_PIECE_VALUES, ENDGAME_PIECE_VALUES, MIDGAME_TABLES, ENDGAME_TABLES};
+
+const fn s(midgame: i32, endgame: i32) -> TaperedScore {
+    TaperedScore::new(midgame, endgame)
+}
+
+// Name of each piece's square table when visited (pawn, knight, bishop, rook, queen, king)
+pub(crate) const PIECE_SQUARE_NAMES: [&str; 6] = ["pawn_squares", "knight_squares", "bishop_squares", "rook_squares", "queen_squares", "king_squares"];
+
+// Piece values the position's incremental piece-square sums are compiled with
+const fn compiled_piece_values() -> [TaperedScore; 6] {
+    let mut values = [TaperedScore::ZERO; 6];
+    let mut piece = 0;
+    while piece < 6 {
+        values[piece] = s(MIDGAME_PIECE_VALUES[piece], ENDGAME_PIECE_VALUES[piece]);
+        piece += 1;
+    }
+
+    values
+}
+
+// Square tables the position's incremental piece-square sums are compiled with
+const fn compiled_piece_squares() -> [[TaperedScore; 64]; 6] {
+    let mut tables = [[TaperedScore::ZERO; 64]; 6];
+    let mut piece = 0;
+    while piece < 6 {
+        let mut offset = 0;
+        while offset < 64 {
+            tables[piece][offset] = s(MIDGAME_TABLES[piece][offset], ENDGAME_TABLES[piece][offset]);
+            offset += 1;
+        }
+        piece += 1;
+    }
+
+    tables
+}
+
+/// Tunable evaluation weights, each a midgame and endgame pair (tables are indexed by rank from the pawn's side)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EvaluationParameters {
+    /// Bonus for a pawn without enemy pawns able to stop it, by rank
+    pub passed_pawn: [TaperedScore; 8],
+    /// Fraction (out of 256) of the passed pawn bonus kept when the square in front of it is occupied
+    pub blocked_passed_pawn_scale: i32,
+    /// Bonus for a passed pawn whose path to promotion is empty
+    pub free_passed_pawn: TaperedScore,
+    /// Bonus for a pawn on a half open file with at least as many friendly pawns able to support it as enemy pawns guarding it, by rank
+    pub candidate_passed_pawn: [TaperedScore; 8],
+    /// Bonus for a pawn defended by or beside a friendly pawn, by rank
+    pub connected_pawn: [TaperedScore; 8],
+    /// Penalty for a pawn without friendly pawns on the neighbouring files
+    pub isolated_pawn: TaperedScore,
+    /// Penalty for each pawn in front of a friendly pawn on the same file
+    pub doubled_pawn: TaperedScore,
+    /// Penalty for a pawn behind its neighbours whose advance is controlled by an enemy pawn
+    pub backward_pawn: TaperedScore,
+    /// Attack units for each piece (knight, bishop, rook, queen) attacking the zone around the enemy king
+    pub king_attack_units: [i32; 4],
+    /// Attack units for each piece type (knight, bishop, rook, queen) able to check the enemy king from a square it doesn't defend
+    pub safe_check_units: [i32; 4],
+    /// Penalty for the square of the attack units against a king, out of 256
+    pub king_danger: TaperedScore,
+    /// Bonus for the nearest friendly pawn on and beside the king's file, by rank (no pawn uses rank 0)
+    pub pawn_shield: [TaperedScore; 8],
+    /// Bonus for the nearest enemy pawn on and beside the king's file, by rank from the king's side (no pawn uses rank 0)
+    pub pawn_storm: [TaperedScore; 8],
+    /// Penalty for each file without pawns on and beside the king's file
+    pub king_open_file: TaperedScore,
+    /// Bonus for a knight by the number of squares it attacks in its mobility area
+    pub knight_mobility: [TaperedScore; 9],
+    /// Bonus for a bishop by the number of squares it attacks in its mobility area
+    pub bishop_mobility: [TaperedScore; 14],
+    /// Bonus for a rook by the number of squares it attacks in its mobility area
+    pub rook_mobility: [TaperedScore; 15],
+    /// Bonus for a queen by the number of squares it attacks in its mobility area
+    pub queen_mobility: [TaperedScore; 28],
+    /// Bonus for a knight on a square in the enemy half defended by a pawn that enemy pawns can never attack
+    pub knight_outpost: TaperedScore,
+    /// Bonus for a bishop on a square in the enemy half defended by a pawn that enemy pawns can never attack
+    pub bishop_outpost: TaperedScore,
+    /// Bonus for a rook on a file without pawns
+    pub rook_open_file: TaperedScore,
+    /// Bonus for a rook on a file with only enemy pawns
+    pub rook_semi_open_file: TaperedScore,
+    /// Bonus for a rook on the seventh rank
+    pub rook_on_seventh: TaperedScore,
+    /// Bonus for having at least two bishops
+    pub bishop_pair: TaperedScore,
+    /// Penalty for a bishop on a7 or h7 (from its side) shut in by an enemy pawn on b6 or g6
+    pub trapped_bishop: TaperedScore,
+    /// Penalty for a rook with little mobility hemmed into the corner by its own king on the first rank
+    pub trapped_rook: TaperedScore,
+    /// Material value of each piece (pawn, knight, bishop, rook, queen, king)
+    pub piece_values: [TaperedScore; 6],
+    /// Bonus for each piece (pawn, knight, bishop, rook, queen, king) by square, from white's point of view with a8 first
+    pub piece_squares: [[TaperedScore; 64]; 6],
+}
+
+impl Default for EvaluationParameters {
+    fn default() -> Self {
+        Self {
+            passed_pawn: [s(0, 0), s(0, 10), s(5, 15), s(10, 25), s(25, 50), s(50, 90), s(90, 150), s(0, 0)],
+            blocked_passed_pawn_scale: 160,
+            free_passed_pawn: s(5, 25),
+            candidate_passed_pawn: [s(0, 0), s(2, 5), s(4, 8), s(8, 15), s(15, 30), s(25, 50), s(0, 0), s(0, 0)],
+            connected_pawn: [s(0, 0), s(4, 2), s(6, 4), s(10, 8), s(20, 18), s(35, 30), s(60, 50), s(0, 0)],
+            isolated_pawn: s(-5, -15),
+            doubled_pawn: s(-10, -25),
+            backward_pawn: s(-8, -12),
+            king_attack_units: [2, 2, 3, 5],
+            safe_check_units: [5, 3, 5, 4],
+            king_danger: s(-64, -8),
+            pawn_shield: [s(-30, -5), s(20, 2), s(12, 1), s(4, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0)],
+            pawn_storm: [s(0, 0), s(-10, 0), s(-25, -5), s(-15, -3), s(-8, 0), s(-3, 0), s(0, 0), s(0, 0)],
+            king_open_file: s(-20, -2),
+            knight_mobility: [s(-16, -16), s(-12, -12), s(-8, -8), s(-4, -4), s(0, 0), s(4, 4), s(8, 8), s(12, 12), s(16, 16)],
+            bishop_mobility: [s(-18, -18), s(-15, -15), s(-12, -12), s(-9, -9), s(-6, -6), s(-3, -3), s(0, 0), s(3, 3), s(6, 6), s(9, 9), s(12, 12), s(15, 15), s(18, 18), s(21, 21)],
+            rook_mobility: [s(-14, -28), s(-12, -24), s(-10, -20), s(-8, -16), s(-6, -12), s(-4, -8), s(-2, -4), s(0, 0), s(2, 4), s(4, 8), s(6, 12), s(8, 16), s(10, 20), s(12, 24), s(14, 28)],
+            queen_mobility: [
+                s(-14, -28), s(-13, -26), s(-12, -24), s(-11, -22), s(-10, -20), s(-9, -18), s(-8, -16),
+                s(-7, -14), s(-6, -12), s(-5, -10), s(-4, -8), s(-3, -6), s(-2, -4), s(-1, -2),
+                s(0, 0), s(1, 2), s(2, 4), s(3, 6), s(4, 8), s(5, 10), s(6, 12),
+                s(7, 14), s(8, 16), s(9, 18), s(10, 20), s(11, 22), s(12, 24), s(13, 26),
+            ],
+            knight_outpost: s(25, 15),
+            bishop_outpost: s(15, 5),
+            rook_open_file: s(25, 10),
+            rook_semi_open_file: s(10, 5),
+            rook_on_seventh: s(10, 25),
+            bishop_pair: s(25, 50),
+            trapped_bishop: s(-80, -80),
+            trapped_rook: s(-40, -10),
+            piece_values: compiled_piece_values(),
+            piece_squares: compiled_piece_squares(),
+        }
+    }
+}
+
+/// Walks every weight of the parameters by field name, for tuning and serialising them
+pub trait ParameterVisitor {
+    fn value(&mut self, name: &'static str, value: &mut i32);
+    fn values(&mut self, name: &'static str, values: &mut [i32]);
+    fn score(&mut self, name: &'static str, score: &mut TaperedScore);
+    fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]);
+}
+
+impl EvaluationParameters {
+    /// Pass every field to the visitor in declaration order
+    pub fn visit<V: ParameterVisitor>(&mut self, visitor: &mut V) {
+        visitor.scores("passed_pawn", &mut self.passed_pawn);
+        visitor.value("blocked_passed_pawn_scale", &mut self.blocked_passed_pawn_scale);
+        visitor.score("free_passed_pawn", &mut self.free_passed_pawn);
+        visitor.scores("candidate_passed_pawn", &mut self.candidate_passed_pawn);
+        visitor.scores("connected_pawn", &mut self.connected_pawn);
+        visitor.score("isolated_pawn", &mut self.isolated_pawn);
+        visitor.score("doubled_pawn", &mut self.doubled_pawn);
+        visitor.score("backward_pawn", &mut self.backward_pawn);
+        visitor.values("king_attack_units", &mut self.king_attack_units);
+        visitor.values("safe_check_units", &mut self.safe_check_units);
+        visitor.score("king_danger", &mut self.king_danger);
+        visitor.scores("pawn_shield", &mut self.pawn_shield);
+        visitor.scores("pawn_storm", &mut self.pawn_storm);
+        visitor.score("king_open_file", &mut self.king_open_file);
+        visitor.scores("knight_mobility", &mut self.knight_mobility);
+        visitor.scores("bishop_mobility", &mut self.bishop_mobility);
+        visitor.scores("rook_mobility", &mut self.rook_mobility);
+        visitor.scores("queen_mobility", &mut self.queen_mobility);
+        visitor.score("knight_outpost", &mut self.knight_outpost);
+        visitor.score("bishop_outpost", &mut self.bishop_outpost);
+        visitor.score("rook_open_file", &mut self.rook_open_file);
+        visitor.score("rook_semi_open_file", &mut self.rook_semi_open_file);
+        visitor.score("rook_on_seventh", &mut self.rook_on_seventh);
+        visitor.score("bishop_pair", &mut self.bishop_pair);
+        visitor.score("trapped_bishop", &mut self.trapped_bishop);
+        visitor.score("trapped_rook", &mut self.trapped_rook);
+        visitor.scores("piece_values", &mut self.piece_values);
+        for (&name, table) in PIECE_SQUARE_NAMES.iter().zip(self.piece_squares.iter_mut()) {
+            visitor.scores(name, table);
+        }
+    }
+
+    /// If the material and square tables are the ones the position's incremental sums use
+    pub fn has_compiled_piece_squares(&self) -> bool {
+        self.piece_values == compiled_piece_values() && self.piece_squares == compiled_piece_squares()
+    }
+
+    /// Every weight flattened in visiting order, with the midgame before the endgame of each score
+    pub fn to_values(&self) -> Vec<i32> {
+        struct Flatten(Vec<i32>);
+
+        impl ParameterVisitor for Flatten {
+            fn value(&mut self, _: &'static str, value: &mut i32) {
+                self.0.push(*value);
+            }
+
+            fn values(&mut self, _: &'static str, values: &mut [i32]) {
+                self.0.extend_from_slice(values);
+            }
+
+            fn score(&mut self, _: &'static str, score: &mut TaperedScore) {
+                self.0.extend_from_slice(&[score.midgame(), score.endgame()]);
+            }
+
+            fn scores(&mut self, _: &'static str, scores: &mut [TaperedScore]) {
+                scores.iter_mut().for_each(|score| self.score("", score));
+            }
+        }
+
+        let mut flatten = Flatten(Vec::new());
+        let mut parameters = *self;
+        parameters.visit(&mut flatten);
+
+        flatten.0
+    }
+
+    /// Parameters with every weight replaced from a flattened list (as made by `to_values`)
+    pub fn from_values(values: &[i32]) -> Self {
+        struct Unflatten<'a>(std::slice::Iter<'a, i32>);
+
+        impl ParameterVisitor for Unflatten<'_> {
+            fn value(&mut self, _: &'static str, value: &mut i32) {
+                *value = *self.0.next().expect("Too few parameter values");
+            }
+
+            fn values(&mut self, _: &'static str, values: &mut [i32]) {
+                values.iter_mut().for_each(|value| self.value("", value));
+            }
+
+            fn score(&mut self, _: &'static str, score: &mut TaperedScore) {
+                let (mut midgame, mut endgame) = (0, 0);
+                self.value("", &mut midgame);
+                self.value("", &mut endgame);
+                *score = TaperedScore::new(midgame, endgame);
+            }
+
+            fn scores(&mut self, _: &'static str, scores: &mut [TaperedScore]) {
+                scores.iter_mut().for_each(|score| self.score("", score));
+            }
+        }
+
+        let mut parameters = Self::default();
+        let mut unflatten = Unflatten(values.iter());
+        parameters.visit(&mut unflatten);
+        debug_assert!(unflatten.0.next().is_none(), "Too many parameter values");
+
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn values_round_trip() {
+        let parameters = EvaluationParameters::default();
+        let values = parameters.to_values();
+        assert_eq!(&values[..4], &[0, 0, 0, 10]);
+        assert_eq!(values[16], 160);
+        assert_eq!(EvaluationParameters::from_values(&values), parameters);
+
+        let shifted = values.iter().map(|value| value + 1).collect::<Vec<_>>();
+        let parameters = EvaluationParameters::from_values(&shifted);
+        assert_eq!(parameters.blocked_passed_pawn_scale, 161);
+        assert_eq!(parameters.trapped_rook, s(-39, -9));
+        assert_eq!(parameters.to_values(), shifted);
+        assert_eq!(parameters.piece_squares[1][0], s(-166, -57));
+        assert!(!parameters.has_compiled_piece_squares());
+        assert!(EvaluationParameters::default().has_compiled_piece_squares());
+    }
+}
@@ -0,0 +1,327 @@
+use attacks::pawn_attacks;
+use interface::game::{BoardMask, PieceArrangement, Shiftable, Side, Square};
+use oxide_interface::engine::{OxidePosition, TaperedScore};
+use oxide_interface::game::{OxideBitboard, OxidePiece, OxideSide, OxideSquare};
+use crate::parameters::EvaluationParameters;
+use crate::trace::{EvaluationTracer, NoTrace};
+
+/// Default number of entries in the pawn hash table
+pub const DEFAULT_PAWN_TABLE_SIZE: usize = 1 << 14;
+
+/// Cached evaluation of a pawn structure
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PawnEntry {
+    key: u64,
+    /// Score of the structure from white's perspective, excluding passed pawns which depend on the other pieces
+    pub score: TaperedScore,
+    /// Passed pawns of each side (white, black)
+    pub passed: [OxideBitboard; 2],
+}
+
+/// Pawn structure evaluations keyed by the position's pawn key, so they're shared between every position with the same pawns
+#[derive(Clone, Debug)]
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnTable {
+    /// Create a table with `size` entries, rounded up to a power of two
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size.max(1).next_power_of_two()],
+        }
+    }
+
+    /// Remove every cached entry
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Get the entry for the position's pawn structure, evaluating and storing it on a miss
+    pub fn probe(&mut self, position: &OxidePosition, parameters: &EvaluationParameters) -> PawnEntry {
+        let key = position.pawn_key();
+        let index = key as usize & (self.entries.len() - 1);
+
+        match self.entries[index] {
+            Some(entry) if entry.key == key => entry,
+            _ => {
+                let entry = evaluate_pawns(position, parameters);
+                self.entries[index] = Some(entry);
+
+                entry
+            }
+        }
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAWN_TABLE_SIZE)
+    }
+}
+
+#[inline]
+fn side_index(side: OxideSide) -> usize {
+    if side.is_white() {
+        0
+    } else {
+        1
+    }
+}
+
+#[inline]
+pub(crate) fn pawns(position: &OxidePosition, side: OxideSide) -> OxideBitboard {
+    position.piece_mask(OxidePiece::Pawn) & position.mask_for_side(side)
+}
+
+// Squares in front of a mask from `side`'s point of view, including the mask
+#[inline]
+pub(crate) fn forward_fill(mask: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    if side.is_white() {
+        mask.north_fill()
+    } else {
+        mask.south_fill()
+    }
+}
+
+#[inline]
+pub(crate) fn forward_shift(mask: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    if side.is_white() {
+        mask.north_shift()
+    } else {
+        mask.south_shift()
+    }
+}
+
+#[inline]
+pub(crate) fn backward_shift(mask: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    forward_shift(mask, side.opposite_side())
+}
+
+// Squares strictly in front of a mask from `side`'s point of view
+#[inline]
+pub(crate) fn front_span(mask: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    forward_fill(forward_shift(mask, side), side)
+}
+
+#[inline]
+pub(crate) fn adjacent_files(mask: OxideBitboard) -> OxideBitboard {
+    let files = mask.file_fill();
+
+    files.east_shift() | files.west_shift()
+}
+
+/// Rank of a square counted from `side`'s first rank
+#[inline]
+pub fn relative_rank(square: OxideSquare, side: OxideSide) -> usize {
+    let rank = Square::<OxidePosition>::y_offset(&square) as usize;
+    if side.is_white() {
+        rank
+    } else {
+        7 - rank
+    }
+}
+
+/// Pawns of `side` that no enemy pawn can stop or capture on the way to promotion
+pub fn passed_pawns(ours: OxideBitboard, theirs: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    let their_span = front_span(theirs, side.opposite_side());
+    let blocked = their_span | their_span.east_shift() | their_span.west_shift();
+    // Only the most advanced of doubled pawns can pass
+    let behind_own = ours & front_span(ours, side.opposite_side());
+
+    ours & !blocked & !behind_own
+}
+
+/// Pawns without friendly pawns on the neighbouring files
+pub fn isolated_pawns(ours: OxideBitboard) -> OxideBitboard {
+    ours & !adjacent_files(ours)
+}
+
+/// Pawns of `side` in front of a friendly pawn on the same file
+pub fn doubled_pawns(ours: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    ours & front_span(ours, side)
+}
+
+/// Pawns of `side` that can't be supported by a friendly pawn and whose advance is controlled by an enemy pawn
+pub fn backward_pawns(ours: OxideBitboard, theirs: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    // Every square our pawns could ever defend by advancing
+    let our_attack_span = forward_fill(pawn_attacks::<OxidePosition>(ours, side), side);
+    let their_attacks = pawn_attacks::<OxidePosition>(theirs, side.opposite_side());
+    let unsupported_stops = forward_shift(ours, side) & their_attacks & !our_attack_span;
+
+    backward_shift(unsupported_stops, side) & !isolated_pawns(ours)
+}
+
+/// Pawns of `side` defended by or beside a friendly pawn
+pub fn connected_pawns(ours: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    let supported = ours & pawn_attacks::<OxidePosition>(ours, side);
+    let phalanx = ours & (ours.east_shift() | ours.west_shift());
+
+    supported | phalanx
+}
+
+/// Pawns of `side` on a half open file with at least as many friendly pawns able to support their advance as enemy pawns guarding it
+pub fn candidate_passed_pawns(ours: OxideBitboard, theirs: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    let half_open = ours & !front_span(theirs, side.opposite_side()) & !passed_pawns(ours, theirs, side);
+
+    half_open.filter(|&square| {
+        let mask = Square::<OxidePosition>::to_mask(&square);
+        let neighbours = adjacent_files(mask);
+        let ranks_ahead = front_span(mask.rank_fill(), side);
+        let sentries = theirs & neighbours & ranks_ahead;
+        let helpers = ours & neighbours & !ranks_ahead;
+
+        helpers.0.count_ones() >= sentries.0.count_ones()
+    }).fold(OxideBitboard::EMPTY, |mask, square| mask | Square::<OxidePosition>::to_mask(&square))
+}
+
+fn ranked_score<T: EvaluationTracer>(mask: OxideBitboard, side: OxideSide, name: &'static str, table: &[TaperedScore; 8], tracer: &mut T) -> TaperedScore {
+    mask.fold(TaperedScore::ZERO, |score, square| {
+        let rank = relative_rank(square, side);
+        tracer.record_score_weight(name, rank, side, 1.0);
+
+        score + table[rank]
+    })
+}
+
+// A weight counted once for each pawn in a mask
+fn counted_score<T: EvaluationTracer>(mask: OxideBitboard, side: OxideSide, name: &'static str, weight: TaperedScore, tracer: &mut T) -> TaperedScore {
+    let count = mask.0.count_ones() as i32;
+    tracer.record_score_weight(name, 0, side, count as f64);
+
+    weight * count
+}
+
+fn evaluate_side<T: EvaluationTracer>(ours: OxideBitboard, theirs: OxideBitboard, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    let mut score = ranked_score(connected_pawns(ours, side), side, "connected_pawn", &parameters.connected_pawn, tracer);
+    score += ranked_score(candidate_passed_pawns(ours, theirs, side), side, "candidate_passed_pawn", &parameters.candidate_passed_pawn, tracer);
+    score += counted_score(isolated_pawns(ours), side, "isolated_pawn", parameters.isolated_pawn, tracer);
+    score += counted_score(doubled_pawns(ours, side), side, "doubled_pawn", parameters.doubled_pawn, tracer);
+    score += counted_score(backward_pawns(ours, theirs, side), side, "backward_pawn", parameters.backward_pawn, tracer);
+
+    score
+}
+
+/// Score of `side`'s pawn structure without the cache, excluding passed pawns
+pub fn pawn_structure_score<T: EvaluationTracer>(position: &OxidePosition, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    evaluate_side(pawns(position, side), pawns(position, side.opposite_side()), side, parameters, tracer)
+}
+
+/// Evaluate the position's pawn structure without the cache
+pub fn evaluate_pawns(position: &OxidePosition, parameters: &EvaluationParameters) -> PawnEntry {
+    let white = pawns(position, OxideSide::White);
+    let black = pawns(position, OxideSide::Black);
+
+    PawnEntry {
+        key: position.pawn_key(),
+        score: evaluate_side(white, black, OxideSide::White, parameters, &mut NoTrace) - evaluate_side(black, white, OxideSide::Black, parameters, &mut NoTrace),
+        passed: [passed_pawns(white, black, OxideSide::White), passed_pawns(black, white, OxideSide::Black)],
+    }
+}
+
+/// Score of `side`'s passed pawns, which is reduced when they're blocked so can't be cached with the structure
+pub fn passed_pawn_score<T: EvaluationTracer>(position: &OxidePosition, entry: &PawnEntry, side: OxideSide, parameters: &EvaluationParameters, tracer: &mut T) -> TaperedScore {
+    let occupied = position.occupied();
+
+    entry.passed[side_index(side)].fold(TaperedScore::ZERO, |score, square| {
+        let mask = Square::<OxidePosition>::to_mask(&square);
+        let rank = relative_rank(square, side);
+        let mut bonus = parameters.passed_pawn[rank];
+        if forward_shift(mask, side) & occupied != OxideBitboard::EMPTY {
+            let scale = parameters.blocked_passed_pawn_scale;
+            tracer.record_score_weight("passed_pawn", rank, side, scale as f64 / 256.0);
+            tracer.record_value_weight("blocked_passed_pawn_scale", 0, side, bonus.midgame() as f64 / 256.0, bonus.endgame() as f64 / 256.0);
+            bonus = bonus.scale(scale, 256);
+        } else {
+            tracer.record_score_weight("passed_pawn", rank, side, 1.0);
+            if front_span(mask, side) & occupied == OxideBitboard::EMPTY {
+                tracer.record_score_weight("free_passed_pawn", 0, side, 1.0);
+                bonus += parameters.free_passed_pawn;
+            }
+        }
+
+        score + bonus
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn mask(squares: &[OxideSquare]) -> OxideBitboard {
+        squares.iter().fold(OxideBitboard::EMPTY, |mask, square| mask | Square::<OxidePosition>::to_mask(square))
+    }
+
+    fn side_pawns(fen: &str) -> (OxideBitboard, OxideBitboard) {
+        let position = OxidePosition::from_fen(fen).unwrap();
+        (pawns(&position, OxideSide::White), pawns(&position, OxideSide::Black))
+    }
+
+    #[test]
+    fn passed_pawns_works() {
+        let (white, black) = side_pawns("4k3/8/1p6/8/P2P2P1/6P1/8/4K3 w - - 0 1");
+        assert_eq!(passed_pawns(white, black, OxideSide::White), mask(&[D4, G4]));
+        assert_eq!(passed_pawns(black, white, OxideSide::Black), OxideBitboard::EMPTY);
+        let (white, black) = side_pawns("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+        assert_eq!(passed_pawns(white, black, OxideSide::White), OxideBitboard::EMPTY);
+        assert_eq!(passed_pawns(black, white, OxideSide::Black), OxideBitboard::EMPTY);
+    }
+
+    #[test]
+    fn isolated_and_doubled_pawns_work() {
+        let (white, _) = side_pawns("4k3/8/8/8/2P5/2P4P/P4PP1/4K3 w - - 0 1");
+        assert_eq!(isolated_pawns(white), mask(&[A2, C3, C4]));
+        assert_eq!(doubled_pawns(white, OxideSide::White), mask(&[C4]));
+        assert_eq!(doubled_pawns(white, OxideSide::Black), mask(&[C3]));
+    }
+
+    #[test]
+    fn backward_pawns_works() {
+        let (white, black) = side_pawns("4k3/8/8/8/3p4/1P6/2P5/4K3 w - - 0 1");
+        assert_eq!(backward_pawns(white, black, OxideSide::White), mask(&[C2]));
+        let (white, black) = side_pawns("4k3/8/8/8/3p4/8/1PP5/4K3 w - - 0 1");
+        assert_eq!(backward_pawns(white, black, OxideSide::White), OxideBitboard::EMPTY);
+    }
+
+    #[test]
+    fn connected_pawns_works() {
+        let (white, _) = side_pawns("4k3/8/8/8/3PP3/2P5/7P/4K3 w - - 0 1");
+        assert_eq!(connected_pawns(white, OxideSide::White), mask(&[D4, E4]));
+    }
+
+    #[test]
+    fn candidate_passed_pawns_works() {
+        let (white, black) = side_pawns("4k3/8/8/2p5/3P4/4P3/8/4K3 w - - 0 1");
+        assert_eq!(candidate_passed_pawns(white, black, OxideSide::White), mask(&[D4]));
+        let (white, black) = side_pawns("4k3/8/4p3/2p5/3P4/8/8/4K3 w - - 0 1");
+        assert_eq!(candidate_passed_pawns(white, black, OxideSide::White), OxideBitboard::EMPTY);
+    }
+
+    #[test]
+    fn pawn_table_works() {
+        let parameters = EvaluationParameters::default();
+        let mut table = PawnTable::new(16);
+        let position = OxidePosition::from_fen("4k3/pp4pp/8/3P4/8/8/P5PP/4K3 w - - 0 1").unwrap();
+        let entry = table.probe(&position, &parameters);
+        assert_eq!(entry, evaluate_pawns(&position, &parameters));
+        assert_eq!(table.probe(&position, &parameters), entry);
+        assert_eq!(entry.passed, [mask(&[D5]), OxideBitboard::EMPTY]);
+        table.clear();
+        assert!(table.entries.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn blocked_passed_pawns_score_less() {
+        let parameters = EvaluationParameters::default();
+        let score = |fen: &str| {
+            let position = OxidePosition::from_fen(fen).unwrap();
+            passed_pawn_score(&position, &evaluate_pawns(&position, &parameters), OxideSide::White, &parameters, &mut NoTrace)
+        };
+        let free = score("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1");
+        let blocked = score("4k3/8/3n4/3P4/8/8/8/4K3 w - - 0 1");
+        assert_eq!(free, parameters.passed_pawn[4] + parameters.free_passed_pawn);
+        assert!(blocked.endgame() < parameters.passed_pawn[4].endgame());
+    }
+}
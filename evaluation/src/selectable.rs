@@ -0,0 +1,143 @@
+use std::sync::Arc;
+use interface::engine::Evaluator;
+use oxide_interface::engine::{OxidePosition, OxideScore};
+use oxide_interface::game::{OxideSidedPiece, OxideSquare};
+use crate::evaluator::HandcraftedEvaluator;
+use crate::nnue::{Network, NnueEvaluator};
+
+/// Name of the option loading a network file
+pub const EVAL_FILE_OPTION: &str = "EvalFile";
+/// Name of the option choosing between the network and the handcrafted evaluation
+pub const USE_NNUE_OPTION: &str = "Use NNUE";
+
+/// The handcrafted evaluation or a network, chosen at runtime through its options
+#[derive(Clone, Debug, Default)]
+pub struct SelectableEvaluator {
+    handcrafted: HandcraftedEvaluator,
+    nnue: Option<NnueEvaluator>,
+    use_nnue: bool,
+}
+
+impl SelectableEvaluator {
+    pub fn new(handcrafted: HandcraftedEvaluator) -> Self {
+        Self {
+            handcrafted,
+            nnue: None,
+            use_nnue: false,
+        }
+    }
+
+    #[inline]
+    pub fn handcrafted(&mut self) -> &mut HandcraftedEvaluator {
+        &mut self.handcrafted
+    }
+
+    /// Evaluate with a network from now on
+    pub fn set_network(&mut self, network: Arc<Network>) {
+        self.nnue = Some(NnueEvaluator::new(network));
+        self.use_nnue = true;
+    }
+
+    /// Switch between the loaded network and the handcrafted evaluation, failing if there's no network to switch to
+    pub fn set_use_nnue(&mut self, use_nnue: bool) -> Result<(), String> {
+        if use_nnue && self.nnue.is_none() {
+            return Err(format!("No network loaded, set {} first", EVAL_FILE_OPTION));
+        }
+        self.use_nnue = use_nnue;
+
+        Ok(())
+    }
+
+    /// If positions are evaluated by the network
+    #[inline]
+    pub fn is_using_nnue(&self) -> bool {
+        self.use_nnue && self.nnue.is_some()
+    }
+}
+
+impl Evaluator<OxidePosition> for SelectableEvaluator {
+    type Score = OxideScore;
+
+    fn evaluate(&mut self, position: &OxidePosition) -> OxideScore {
+        match &mut self.nnue {
+            Some(nnue) if self.use_nnue => nnue.evaluate(position),
+            _ => self.handcrafted.evaluate(position),
+        }
+    }
+
+    fn reset(&mut self, position: &OxidePosition) {
+        if let Some(nnue) = &mut self.nnue {
+            if self.use_nnue {
+                nnue.reset(position);
+            }
+        }
+    }
+
+    fn push_move(&mut self, position: &OxidePosition, removed: &[(OxideSidedPiece, OxideSquare)], added: &[(OxideSidedPiece, OxideSquare)]) {
+        if let Some(nnue) = &mut self.nnue {
+            if self.use_nnue {
+                nnue.push_move(position, removed, added);
+            }
+        }
+    }
+
+    fn pop_move(&mut self) {
+        if let Some(nnue) = &mut self.nnue {
+            if self.use_nnue {
+                nnue.pop_move();
+            }
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        if name.eq_ignore_ascii_case(EVAL_FILE_OPTION) {
+            let network = Network::load(value).map_err(|error| error.to_string())?;
+            self.set_network(Arc::new(network));
+            Ok(true)
+        } else if name.eq_ignore_ascii_case(USE_NNUE_OPTION) {
+            match value.to_ascii_lowercase().as_str() {
+                "true" => self.set_use_nnue(true)?,
+                "false" => self.set_use_nnue(false)?,
+                _ => return Err(format!("Expected true or false but found {}", value)),
+            }
+            Ok(true)
+        } else {
+            self.handcrafted.set_option(name, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs;
+    use interface::game::Position;
+    use crate::nnue::FeatureSet;
+    use crate::nnue::network::test::network;
+
+    #[test]
+    fn selecting_evaluators_works() {
+        let position = OxidePosition::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let network = network(FeatureSet::HalfKP);
+        let expected_nnue = NnueEvaluator::new(Arc::new(network.clone())).evaluate(&position);
+        let expected_handcrafted = HandcraftedEvaluator::default().evaluate(&position);
+        let mut evaluator = SelectableEvaluator::default();
+        assert_eq!(evaluator.evaluate(&position), expected_handcrafted);
+        assert!(evaluator.set_option("Use NNUE", "true").is_err());
+
+        let path = temp_dir().join(format!("oxide-selectable-{}.nnue", std::process::id()));
+        fs::write(&path, network.to_bytes()).unwrap();
+        assert_eq!(evaluator.set_option("EvalFile", path.to_str().unwrap()), Ok(true));
+        fs::remove_file(&path).unwrap();
+        assert!(evaluator.is_using_nnue());
+        assert_eq!(evaluator.evaluate(&position), expected_nnue);
+
+        assert_eq!(evaluator.set_option("use nnue", "false"), Ok(true));
+        assert_eq!(evaluator.evaluate(&position), expected_handcrafted);
+        assert!(evaluator.set_option("Use NNUE", "maybe").is_err());
+        assert!(evaluator.set_option("EvalFile", "/nonexistent/network.nnue").is_err());
+        assert_eq!(evaluator.set_option("Threads", "1"), Ok(false));
+        assert_eq!(evaluator.set_option("EvalParams", ""), Ok(true));
+    }
+}
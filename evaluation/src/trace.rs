@@ -0,0 +1,221 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use interface::game::Side;
+use oxide_interface::engine::{TaperedScore, MAX_PHASE};
+use oxide_interface::game::OxideSide;
+
+/// A group of evaluation terms reported together
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EvaluationTerm {
+    Material, // Piece values
+    PieceSquares, // Piece-square table bonuses
+    Pawns, // Pawn structure other than passed pawns
+    PassedPawns, // Passed pawns
+    KingSafety, // King attacks, checks and shelter
+    Mobility, // Squares the pieces attack
+    Activity, // Outposts, rook files, bishop pair and trapped pieces
+}
+
+impl EvaluationTerm {
+    /// Every term in the order they're reported
+    pub const TERMS: [EvaluationTerm; 7] = [
+        EvaluationTerm::Material,
+        EvaluationTerm::PieceSquares,
+        EvaluationTerm::Pawns,
+        EvaluationTerm::PassedPawns,
+        EvaluationTerm::KingSafety,
+        EvaluationTerm::Mobility,
+        EvaluationTerm::Activity,
+    ];
+
+    /// Name shown in the trace table
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvaluationTerm::Material => "Material",
+            EvaluationTerm::PieceSquares => "PSQT",
+            EvaluationTerm::Pawns => "Pawns",
+            EvaluationTerm::PassedPawns => "Passed pawns",
+            EvaluationTerm::KingSafety => "King safety",
+            EvaluationTerm::Mobility => "Mobility",
+            EvaluationTerm::Activity => "Activity",
+        }
+    }
+
+    /// Key used in the JSON trace
+    pub fn key(&self) -> &'static str {
+        match self {
+            EvaluationTerm::Material => "material",
+            EvaluationTerm::PieceSquares => "piece_squares",
+            EvaluationTerm::Pawns => "pawns",
+            EvaluationTerm::PassedPawns => "passed_pawns",
+            EvaluationTerm::KingSafety => "king_safety",
+            EvaluationTerm::Mobility => "mobility",
+            EvaluationTerm::Activity => "activity",
+        }
+    }
+}
+
+/// Receives each term of an evaluation as it's computed
+pub trait EvaluationTracer {
+    /// If terms are recorded, when not the evaluator skips any work only needed for the trace
+    const ENABLED: bool;
+
+    /// Record `side`'s contribution to a term from its own perspective
+    fn record(&mut self, term: EvaluationTerm, side: OxideSide, score: TaperedScore);
+
+    /// Record the game phase the terms are tapered by
+    fn record_phase(&mut self, phase: i32);
+
+    /// Record `side` using a score weight (`name` as passed to a `ParameterVisitor`, `index` into it for tables) `count` times
+    /// from its own perspective, fractionally where the weight is scaled
+    fn record_score_weight(&mut self, _name: &'static str, _index: usize, _side: OxideSide, _count: f64) {}
+
+    /// Record how fast `side`'s score changes with an integer weight that scales other weights, for the midgame and endgame
+    fn record_value_weight(&mut self, _name: &'static str, _index: usize, _side: OxideSide, _midgame: f64, _endgame: f64) {}
+}
+
+/// Tracer that records nothing, compiling away when evaluating normally
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoTrace;
+
+impl EvaluationTracer for NoTrace {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn record(&mut self, _: EvaluationTerm, _: OxideSide, _: TaperedScore) {}
+
+    #[inline(always)]
+    fn record_phase(&mut self, _: i32) {}
+}
+
+/// Every term of an evaluation for each side and the phase, for finding out why a position scored how it did
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EvaluationTrace {
+    terms: [[TaperedScore; 2]; 7],
+    phase: i32,
+}
+
+#[inline]
+fn side_index(side: OxideSide) -> usize {
+    if side.is_white() {
+        0
+    } else {
+        1
+    }
+}
+
+impl EvaluationTrace {
+    /// `side`'s contribution to a term from its own perspective
+    #[inline]
+    pub fn term(&self, term: EvaluationTerm, side: OxideSide) -> TaperedScore {
+        self.terms[term as usize][side_index(side)]
+    }
+
+    /// Game phase the terms are tapered by
+    #[inline]
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// A term from white's perspective
+    #[inline]
+    pub fn net(&self, term: EvaluationTerm) -> TaperedScore {
+        self.term(term, OxideSide::White) - self.term(term, OxideSide::Black)
+    }
+
+    /// Sum of every term from white's perspective
+    pub fn total(&self) -> TaperedScore {
+        EvaluationTerm::TERMS.iter().fold(TaperedScore::ZERO, |total, &term| total + self.net(term))
+    }
+
+    /// Tapered score from white's perspective
+    #[inline]
+    pub fn score(&self) -> i32 {
+        self.total().taper(self.phase)
+    }
+
+    /// The trace as a JSON object for tooling
+    pub fn to_json(&self) -> String {
+        let pair = |score: TaperedScore| format!("{{\"mg\":{},\"eg\":{}}}", score.midgame(), score.endgame());
+        let terms = EvaluationTerm::TERMS.iter()
+            .map(|&term| format!(
+                "\"{}\":{{\"white\":{},\"black\":{},\"total\":{}}}",
+                term.key(), pair(self.term(term, OxideSide::White)), pair(self.term(term, OxideSide::Black)), pair(self.net(term)),
+            ))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("{{\"phase\":{},\"terms\":{{{}}},\"total\":{},\"score\":{}}}", self.phase, terms, pair(self.total()), self.score())
+    }
+}
+
+impl EvaluationTracer for EvaluationTrace {
+    const ENABLED: bool = true;
+
+    #[inline]
+    fn record(&mut self, term: EvaluationTerm, side: OxideSide, score: TaperedScore) {
+        self.terms[term as usize][side_index(side)] += score;
+    }
+
+    #[inline]
+    fn record_phase(&mut self, phase: i32) {
+        self.phase = phase;
+    }
+}
+
+impl Display for EvaluationTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        const SEPARATOR: &str = "-------------+-------------+-------------+------------";
+        let pair = |score: TaperedScore| format!("{:>5} {:>5}", score.midgame(), score.endgame());
+
+        writeln!(f, "{:>12} | {:^11} | {:^11} | {:^11}", "Term", "White", "Black", "Total")?;
+        writeln!(f, "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}", "", "MG", "EG", "MG", "EG", "MG", "EG")?;
+        writeln!(f, "{}", SEPARATOR)?;
+        for &term in EvaluationTerm::TERMS.iter() {
+            writeln!(f, "{:>12} | {} | {} | {}", term.name(), pair(self.term(term, OxideSide::White)), pair(self.term(term, OxideSide::Black)), pair(self.net(term)))?;
+        }
+        writeln!(f, "{}", SEPARATOR)?;
+        writeln!(f, "{:>12} | {:>11} | {:>11} | {}", "Total", "", "", pair(self.total()))?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase.max(0).min(MAX_PHASE), MAX_PHASE)?;
+        write!(f, "Evaluation: {:+.2} (white side)", self.score() as f64 / 100.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trace() -> EvaluationTrace {
+        let mut trace = EvaluationTrace::default();
+        trace.record_phase(12);
+        trace.record(EvaluationTerm::Material, OxideSide::White, TaperedScore::new(100, 200));
+        trace.record(EvaluationTerm::Mobility, OxideSide::White, TaperedScore::new(10, 20));
+        trace.record(EvaluationTerm::Mobility, OxideSide::Black, TaperedScore::new(30, -10));
+        trace
+    }
+
+    #[test]
+    fn trace_works() {
+        let trace = trace();
+        assert_eq!(trace.net(EvaluationTerm::Mobility), TaperedScore::new(-20, 30));
+        assert_eq!(trace.total(), TaperedScore::new(80, 230));
+        assert_eq!(trace.score(), 155);
+        assert_eq!(trace.term(EvaluationTerm::KingSafety, OxideSide::Black), TaperedScore::ZERO);
+    }
+
+    #[test]
+    fn to_json_works() {
+        let json = trace().to_json();
+        assert!(json.starts_with("{\"phase\":12,\"terms\":{\"material\":{\"white\":{\"mg\":100,\"eg\":200},\"black\":{\"mg\":0,\"eg\":0},\"total\":{\"mg\":100,\"eg\":200}},"));
+        assert!(json.contains("\"mobility\":{\"white\":{\"mg\":10,\"eg\":20},\"black\":{\"mg\":30,\"eg\":-10},\"total\":{\"mg\":-20,\"eg\":30}}"));
+        assert!(json.ends_with("\"total\":{\"mg\":80,\"eg\":230},\"score\":155}"));
+    }
+
+    #[test]
+    fn display_works() {
+        let table = trace().to_string();
+        assert!(table.contains("    Mobility |    10    20 |    30   -10 |   -20    30\n"));
+        assert!(table.contains("       Total |             |             |    80   230\n"));
+        assert!(table.ends_with("Phase: 12/24\nEvaluation: +1.55 (white side)"));
+    }
+}
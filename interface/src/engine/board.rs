@@ -61,6 +61,11 @@ pub trait Board<P: Position> {
     /// Undefined behavior (could panic or continue in undefined state) with illegal move
     fn undo_move_unchecked(&mut self, chess_move: Self::Move, previous_state: Self::BoardState);
 
+    /// Pass the turn to the other side without moving a piece and return the required state to undo
+    fn make_null_move(&mut self) -> Self::BoardState;
+    /// Undo a previously made null move given the state from before it
+    fn undo_null_move(&mut self, previous_state: Self::BoardState);
+
     /// If the side to move is in check
     fn in_check(&self) -> bool;
     /// If a given move is a discovery check
@@ -0,0 +1,26 @@
+use crate::game::Position;
+use crate::engine::PositionalScore;
+
+/// Static evaluation of a position used by the search at its leaves
+pub trait Evaluator<P: Position> {
+    /// Score representation returned by the evaluation
+    type Score: PositionalScore;
+
+    /// Score a position from the perspective of the side to move (positive is good for the side to move)
+    fn evaluate(&mut self, position: &P) -> Self::Score;
+
+    /// A search starts from `position`, the first move pushed afterwards is made from it
+    fn reset(&mut self, _position: &P) {}
+
+    /// A move was made reaching `position`, taking the `removed` pieces off their squares and putting the `added` ones on theirs
+    /// (a null move changes no pieces), for evaluations kept up to date incrementally
+    fn push_move(&mut self, _position: &P, _removed: &[(P::SidedPiece, P::Square)], _added: &[(P::SidedPiece, P::Square)]) {}
+
+    /// The last pushed move was undone
+    fn pop_move(&mut self) {}
+
+    /// Set an option of the evaluation by its UCI name, returning if the evaluator has the option (a value it can't use is an error describing why)
+    fn set_option(&mut self, _name: &str, _value: &str) -> Result<bool, String> {
+        Ok(false)
+    }
+}
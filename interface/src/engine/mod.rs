@@ -1,8 +1,10 @@
 pub use score::PositionalScore;
 pub use zobrist::ZobristHasher;
 pub use board::{Board, BoardState, CachedBoardState, IdempotentBoardState};
+pub use evaluator::Evaluator;
 
 mod score;
 mod zobrist;
 mod board;
+mod evaluator;
 
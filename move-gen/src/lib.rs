@@ -5,9 +5,26 @@ use smallvec::SmallVec;
 use interface::engine::{Board, CachedBoardState};
 use attacks::{pseudo_attacks, pawn_pushes, pawn_east_attacks, pawn_west_attacks, king_attacks};
 
+mod outcome;
+
+pub use outcome::{BoardOutcome, OxideOutcome};
+
 // TODO: Tune this value (be just above average for number of moves so that most move generation calls don't need any reallocation on the heap)
 const BASE_MOVES_CAPACITY: usize = 50;
 
+// Which subset of pseudo-legal moves to generate
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum GenerationType {
+    // Captures and promotions
+    Captures,
+    // Non-capturing, non-promoting moves including castles
+    Quiets,
+    // Every move when not in check
+    NonEvasions,
+    // Moves that might get out of check
+    Evasions,
+}
+
 #[inline]
 fn new_quiet_or_capture<P: Position, B: Board<P>>(occupied: P::BoardMask, from_square: P::Square, to_square: P::Square) -> B::Move {
     if occupied & to_square.to_mask() != P::BoardMask::EMPTY {
@@ -150,7 +167,7 @@ fn extend_pawn_captures<P: Position, B: Board<P>, const N: usize>(move_list: &mu
 
 
 #[inline]
-fn generate_pawn_moves<P: Position, B: Board<P>, const N: usize>(board: &B, move_list: &mut SmallVec<[B::Move; N]>, side_moving: P::Side, target_mask: P::BoardMask, checks_only: bool) {
+fn generate_pawn_moves<P: Position, B: Board<P>, const N: usize>(board: &B, move_list: &mut SmallVec<[B::Move; N]>, side_moving: P::Side, target_mask: P::BoardMask, generation_type: GenerationType, checks_only: bool) {
     let opposite_side = side_moving.opposite_side();
     let enemy_mask = board.position().mask_for_side(opposite_side);
     let (promoting_from_rank, en_passant_rank, en_passant_attack_rank) = if side_moving.is_white() {
@@ -162,39 +179,57 @@ fn generate_pawn_moves<P: Position, B: Board<P>, const N: usize>(board: &B, move
     let promotable_pawns = pawn_mask & promoting_from_rank;
     let non_promoting_pawns = pawn_mask ^ promotable_pawns;
     let en_passant_attackers = non_promoting_pawns & en_passant_attack_rank;
-    let en_passant_mask = board.position().en_passant_square().map_or(P::BoardMask::EMPTY, |s| s.to_mask());
     let empty_mask = board.position().empty();
     let target_mask = if checks_only {
         target_mask & board.state().piece_check_mask(P::Piece::PAWN)
     } else {
         target_mask
     };
+    // Promotions are generated with captures, so a push promotion can land on any empty square unless evading
+    let push_promotion_mask = if generation_type == GenerationType::Evasions {
+        target_mask
+    } else {
+        P::BoardMask::FULL
+    };
+    let en_passant_mask = board.position().en_passant_square().map_or(P::BoardMask::EMPTY, |s| {
+        let captured_pawn_mask = pawn_pushes::<P>(s.to_mask(), opposite_side);
+        // Allowed when capturing the pawn (or landing on the en-passant square) is part of the target
+        if (captured_pawn_mask | s.to_mask()) & target_mask != P::BoardMask::EMPTY {
+            s.to_mask()
+        } else {
+            P::BoardMask::EMPTY
+        }
+    });
+
+    if generation_type != GenerationType::Quiets {
+        // Add promotions
+        let west_promoting_attacks = pawn_west_attacks::<P>(promotable_pawns, side_moving) & enemy_mask & target_mask;
+        extend_pawn_promotion::<P, B, N>(move_list, side_moving, west_promoting_attacks, true, true);
+        let east_promoting_attacks = pawn_east_attacks::<P>(promotable_pawns, side_moving) & enemy_mask & target_mask;
+        extend_pawn_promotion::<P, B, N>(move_list, side_moving, east_promoting_attacks, true, false);
+        let push_promotions = pawn_pushes::<P>(promotable_pawns, side_moving) & empty_mask & push_promotion_mask;
+        extend_pawn_promotion::<P, B, N>(move_list, side_moving, push_promotions, false, false);
+
+        // Add normal pawn captures
+        let west_attacks = pawn_west_attacks::<P>(non_promoting_pawns, side_moving) & enemy_mask & target_mask;
+        extend_pawn_captures::<P, B, N>(move_list, side_moving, west_attacks, false, true);
+        let east_attacks = pawn_east_attacks::<P>(non_promoting_pawns, side_moving) & enemy_mask & target_mask;
+        extend_pawn_captures::<P, B, N>(move_list, side_moving, east_attacks, false, false);
+
+        // Add en-passant captures
+        let west_en_passant_captures = pawn_west_attacks::<P>(en_passant_attackers, side_moving) & en_passant_mask;
+        extend_pawn_captures::<P, B, N>(move_list, side_moving, west_en_passant_captures, true, true);
+        let east_en_passant_captures = pawn_east_attacks::<P>(en_passant_attackers, side_moving) & en_passant_mask;
+        extend_pawn_captures::<P, B, N>(move_list, side_moving, east_en_passant_captures, true, false);
+    }
 
-    // Add promotions
-    let west_promoting_attacks = pawn_west_attacks::<P>(promotable_pawns, side_moving) & enemy_mask & target_mask;
-    extend_pawn_promotion::<P, B, N>(move_list, side_moving, west_promoting_attacks, true, true);
-    let east_promoting_attacks = pawn_east_attacks::<P>(promotable_pawns, side_moving) & enemy_mask & target_mask;
-    extend_pawn_promotion::<P, B, N>(move_list, side_moving, east_promoting_attacks, true, false);
-    let push_promotions = pawn_pushes::<P>(promotable_pawns, side_moving) & empty_mask & target_mask;
-    extend_pawn_promotion::<P, B, N>(move_list, side_moving, push_promotions, false, false);
-
-    // Add pushes
-    let pawn_pushers = pawn_pushes::<P>(non_promoting_pawns, side_moving) & empty_mask;
-    extend_pawn_pushes::<P, B, N>(move_list, side_moving, pawn_pushers, false);
-    let double_pawn_pushers = pawn_pushes::<P>(pawn_pushers & en_passant_rank, side_moving) & empty_mask;
-    extend_pawn_pushes::<P, B, N>(move_list, side_moving, double_pawn_pushers, true);
-
-    // Add normal pawn captures
-    let west_attacks = pawn_west_attacks::<P>(non_promoting_pawns, side_moving) & enemy_mask;
-    extend_pawn_captures::<P, B, N>(move_list, side_moving, west_attacks, false, true);
-    let east_attacks = pawn_east_attacks::<P>(non_promoting_pawns, side_moving) & enemy_mask;
-    extend_pawn_captures::<P, B, N>(move_list, side_moving, east_attacks, false, false);
-
-    // Add en-passant captures
-    let west_en_passant_captures = pawn_west_attacks::<P>(en_passant_attackers, side_moving) & enemy_mask & en_passant_mask;
-    extend_pawn_captures::<P, B, N>(move_list, side_moving, west_en_passant_captures, true, true);
-    let east_en_passant_captures = pawn_east_attacks::<P>(en_passant_attackers, side_moving) & enemy_mask & en_passant_mask;
-    extend_pawn_captures::<P, B, N>(move_list, side_moving, east_en_passant_captures, true, false);
+    if generation_type != GenerationType::Captures {
+        // Add pushes
+        let pawn_pushers = pawn_pushes::<P>(non_promoting_pawns, side_moving) & empty_mask;
+        extend_pawn_pushes::<P, B, N>(move_list, side_moving, pawn_pushers & target_mask, false);
+        let double_pawn_pushers = pawn_pushes::<P>(pawn_pushers & en_passant_rank, side_moving) & empty_mask & target_mask;
+        extend_pawn_pushes::<P, B, N>(move_list, side_moving, double_pawn_pushers, true);
+    }
 }
 
 #[inline]
@@ -232,17 +267,23 @@ fn generate_castles<P: Position, B: Board<P>, const N: usize>(board: &B, move_li
 }
 
 #[inline]
-fn generate_all<P: Position, B: Board<P>, const N: usize>(board: &B, move_list: &mut SmallVec<[B::Move; N]>, side_moving: P::Side, target_mask: P::BoardMask, evasion: bool, include_castles: bool, checks_only: bool) {
-    generate_pawn_moves(board, move_list, side_moving, target_mask, checks_only);
+fn generate_all<P: Position, B: Board<P>, const N: usize>(board: &B, move_list: &mut SmallVec<[B::Move; N]>, side_moving: P::Side, target_mask: P::BoardMask, generation_type: GenerationType, checks_only: bool) {
+    generate_pawn_moves(board, move_list, side_moving, target_mask, generation_type, checks_only);
     generate_piece_moves(board, move_list, P::Piece::KNIGHT, side_moving, target_mask, checks_only);
     generate_piece_moves(board, move_list, P::Piece::BISHOP, side_moving, target_mask, checks_only);
     generate_piece_moves(board, move_list, P::Piece::ROOK, side_moving, target_mask, checks_only);
     generate_piece_moves(board, move_list, P::Piece::QUEEN, side_moving, target_mask, checks_only);
 
-    if evasion && !checks_only {
-        generate_king_moves(board, move_list, side_moving, target_mask);
+    if !checks_only {
+        // The king isn't limited to blocking or capturing the checker when evading
+        let king_target_mask = if generation_type == GenerationType::Evasions {
+            !board.position().mask_for_side(side_moving)
+        } else {
+            target_mask
+        };
+        generate_king_moves(board, move_list, side_moving, king_target_mask);
 
-        if include_castles {
+        if generation_type == GenerationType::Quiets || generation_type == GenerationType::NonEvasions {
             generate_castles(board, move_list, side_moving);
         }
     }
@@ -253,7 +294,8 @@ fn pseudo_legal_filter<P: Position, B: Board<P>>(board: &B, chess_move: &B::Move
     let side_to_move = board.position().side_to_move();
     let is_pinned = board.state().blocking_mask(side_to_move) & board.position().mask_for_side(side_to_move) != P::BoardMask::EMPTY;
 
-    (!is_pinned && chess_move.from() != board.position().king_square(side_to_move) && chess_move.is_en_passant_capture()) || board.is_legal(chess_move)
+    // Without pins only king moves and en-passant captures (removing two pawns from a line) can leave the king attacked
+    (!is_pinned && chess_move.from() != board.position().king_square(side_to_move) && !chess_move.is_en_passant_capture()) || board.is_legal(chess_move)
 }
 
 #[inline]
@@ -271,25 +313,30 @@ pub fn legal_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<Item=B:
     list.into_iter()
 }
 
+/// Generate captures and promotions (not for use in check, see `evasion_moves`)
 #[inline]
 pub fn capture_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<Item=B::Move> {
-    let mut list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
-    let side_to_move = board.position().side_to_move();
-    let opposite_side = side_to_move.opposite_side();
-    let target_mask = board.position().mask_for_side(opposite_side);
+    debug_assert!(!board.in_check(), "Attempting to get capture moves for a position while in check");
+    let mut move_list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
+    let side_moving = board.position().side_to_move();
+    let target_mask = board.position().mask_for_side(side_moving.opposite_side());
 
-    todo!();
+    generate_all(board, &mut move_list, side_moving, target_mask, GenerationType::Captures, false);
 
-    list.into_iter()
+    move_list.into_iter()
 }
 
+/// Generate non-capturing and non-promoting moves (not for use in check, see `evasion_moves`)
 #[inline]
 pub fn quiet_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<Item=B::Move> {
-    let mut list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
+    debug_assert!(!board.in_check(), "Attempting to get quiet moves for a position while in check");
+    let mut move_list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
+    let side_moving = board.position().side_to_move();
+    let target_mask = board.position().empty();
 
-    todo!();
+    generate_all(board, &mut move_list, side_moving, target_mask, GenerationType::Quiets, false);
 
-    list.into_iter()
+    move_list.into_iter()
 }
 
 #[inline]
@@ -305,11 +352,22 @@ pub fn quiet_check_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<I
 #[inline]
 pub fn evasion_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<Item=B::Move> {
     debug_assert!(board.in_check(), "Attempting to get evasion moves for a position not in check");
-    let mut list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
+    let mut move_list = SmallVec::<[B::Move; BASE_MOVES_CAPACITY]>::new();
+    let side_moving = board.position().side_to_move();
+    let checkers_mask = board.state().checkers_mask();
 
-    todo!();
+    if checkers_mask.count() > 1 {
+        // Only the king can escape a double check
+        generate_king_moves(board, &mut move_list, side_moving, !board.position().mask_for_side(side_moving));
+    } else {
+        let king_square = board.position().king_square(side_moving);
+        let checker_square = P::Square::from_mask(checkers_mask).expect("Checkers mask should have one checker");
+        let target_mask = P::BoardMask::between_fill(king_square, checker_square) | checkers_mask;
 
-    list.into_iter()
+        generate_all(board, &mut move_list, side_moving, target_mask, GenerationType::Evasions, false);
+    }
+
+    move_list.into_iter()
 }
 
 #[inline]
@@ -319,7 +377,7 @@ pub fn non_evasion_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<I
     let side_moving = board.position().side_to_move();
     let target_mask = !board.position().mask_for_side(side_moving);
 
-    generate_all(board, &mut move_list, side_moving, target_mask, false, true, false);
+    generate_all(board, &mut move_list, side_moving, target_mask, GenerationType::NonEvasions, false);
 
     move_list.into_iter()
 }
@@ -327,8 +385,8 @@ pub fn non_evasion_moves<P: Position, B: Board<P>>(board: &B) -> impl Iterator<I
 #[cfg(test)]
 mod tests {
     use oxide_interface::engine::{OxidePosition, OxideBoard};
-    use oxide_interface::game::{OxideMove, OxideSimpleMove, OxideSquare::*};
-    use crate::legal_moves;
+    use oxide_interface::game::{OxideMove, OxideSimpleMove, OxidePiece, OxideSquare::*};
+    use crate::{legal_moves, capture_moves, quiet_moves, non_evasion_moves};
     use interface::game::{Position, SimpleChessMove, ChessMove};
     use interface::engine::Board;
 
@@ -372,4 +430,87 @@ mod tests {
             OxideMove::new(G1, H3),
         ]);
     }
+
+    fn perft(board: &mut OxideBoard, depth: usize) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+
+        legal_moves(board).collect::<Vec<OxideMove>>().into_iter()
+            .map(|chess_move| {
+                let state = board.make_move_unchecked(chess_move);
+                let nodes = perft(board, depth - 1);
+                board.undo_move_unchecked(chess_move, state);
+                nodes
+            })
+            .sum()
+    }
+
+    #[test]
+    fn perft_works() {
+        // Known node counts, none reaching an under-promotion
+        for &(fen, depth, nodes) in &[
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3, 8_902),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2, 2_039),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+        ] {
+            let mut board = OxideBoard::new(OxidePosition::from_fen(fen).expect("Failed to parse test case FEN"));
+            assert_eq!(perft(&mut board, depth), nodes, "Perft {} of {}", depth, fen);
+        }
+    }
+
+    // Checking moves at the last ply, asserting along the way that every check is predicted before the move is made
+    fn perft_checks(board: &mut OxideBoard, depth: usize) -> usize {
+        legal_moves(board).collect::<Vec<OxideMove>>().into_iter()
+            .map(|chess_move| {
+                let predicted = board.gives_check(&chess_move) || board.is_discovery(&chess_move);
+                let state = board.make_move_unchecked(chess_move);
+                assert_eq!(predicted, board.in_check(), "Check by {} wasn't predicted", chess_move);
+                let checks = if depth == 1 {
+                    board.in_check() as usize
+                } else {
+                    perft_checks(board, depth - 1)
+                };
+                board.undo_move_unchecked(chess_move, state);
+                checks
+            })
+            .sum()
+    }
+
+    #[test]
+    fn perft_checks_works() {
+        for &(fen, depth, checks) in &[
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3, 12),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2, 3),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 1_680),
+        ] {
+            let mut board = OxideBoard::new(OxidePosition::from_fen(fen).expect("Failed to parse test case FEN"));
+            assert_eq!(perft_checks(&mut board, depth), checks, "Checks at perft {} of {}", depth, fen);
+        }
+    }
+
+    #[test]
+    fn captures_and_quiets_partition_non_evasions_works() {
+        let board = OxideBoard::new(OxidePosition::from_fen("r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/P1PBBPPP/R3K2R w KQkq - 0 1").expect("Failed to parse test case FEN"));
+        let captures = capture_moves(&board).collect::<Vec<OxideMove>>();
+        let quiets = quiet_moves(&board).collect::<Vec<OxideMove>>();
+        let all_moves = non_evasion_moves(&board).collect::<Vec<OxideMove>>();
+
+        for capture in &captures {
+            assert!(capture.is_capture() || capture.is_promotion(), "Capture gen had quiet move {}", capture);
+            assert!(all_moves.contains(capture), "Capture gen had extra move {}", capture);
+        }
+        for quiet in &quiets {
+            assert!(!quiet.is_capture() && !quiet.is_promotion(), "Quiet gen had tactical move {}", quiet);
+            assert!(all_moves.contains(quiet), "Quiet gen had extra move {}", quiet);
+        }
+        assert_eq!(captures.len() + quiets.len(), all_moves.len(), "Captures and quiets don't cover every move");
+        // Promotions by push and capture
+        assert!(captures.contains(&OxideMove::new_promotion(B7, B8, OxidePiece::Queen)));
+        assert!(captures.contains(&OxideMove::new_promoting_capture(B7, A8, OxidePiece::Queen)));
+        assert!(captures.contains(&OxideMove::new_capture(E5, F7)));
+        // King moves and castles are quiet
+        assert!(quiets.contains(&OxideMove::new(E1, D1)));
+        assert!(quiets.contains(&OxideMove::WHITE_KING_CASTLE));
+    }
 }
@@ -0,0 +1,180 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use oxide_interface::engine::{OxideBoard, OxidePosition, FIFTY_MOVE_RULE_PLIES};
+use oxide_interface::game::OxideSide;
+use interface::engine::Board;
+use interface::game::{Position, Side};
+use crate::legal_moves;
+
+/// If and how a game ended (repetitions need the game's key history so aren't included)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OxideOutcome {
+    Checkmate { winner: OxideSide }, // Side to move is in check without a legal move
+    Stalemate, // Side to move isn't in check but has no legal move
+    FiftyMoveRule, // Fifty moves without a capture or pawn move
+    InsufficientMaterial, // Neither side can possibly mate
+    Ongoing, // Game isn't over
+}
+
+impl OxideOutcome {
+    /// If the game ended
+    #[inline]
+    pub fn is_over(&self) -> bool {
+        *self != OxideOutcome::Ongoing
+    }
+
+    /// If the game ended drawn
+    #[inline]
+    pub fn is_draw(&self) -> bool {
+        matches!(self, OxideOutcome::Stalemate | OxideOutcome::FiftyMoveRule | OxideOutcome::InsufficientMaterial)
+    }
+
+    /// The side that won if any
+    #[inline]
+    pub fn winner(&self) -> Option<OxideSide> {
+        match self {
+            OxideOutcome::Checkmate { winner } => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// Result as written in a PGN result tag
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            OxideOutcome::Checkmate { winner: OxideSide::White } => "1-0",
+            OxideOutcome::Checkmate { winner: OxideSide::Black } => "0-1",
+            OxideOutcome::Ongoing => "*",
+            _ => "1/2-1/2",
+        }
+    }
+}
+
+impl Display for OxideOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            OxideOutcome::Checkmate { winner: OxideSide::White } => write!(f, "White mates"),
+            OxideOutcome::Checkmate { winner: OxideSide::Black } => write!(f, "Black mates"),
+            OxideOutcome::Stalemate => write!(f, "Draw by stalemate"),
+            OxideOutcome::FiftyMoveRule => write!(f, "Draw by fifty move rule"),
+            OxideOutcome::InsufficientMaterial => write!(f, "Draw by insufficient material"),
+            OxideOutcome::Ongoing => write!(f, "Ongoing"),
+        }
+    }
+}
+
+/// Outcome queries on the board, kept here as they need legal move generation
+pub trait BoardOutcome {
+    /// If the game is over and why
+    fn outcome(&self) -> OxideOutcome;
+
+    /// Fifty moves passed without a capture or pawn move, unless the move that completed them delivered mate
+    fn is_fifty_move_draw(&self) -> bool;
+}
+
+impl BoardOutcome for OxideBoard {
+    fn outcome(&self) -> OxideOutcome {
+        // Mate and stalemate take priority, even when the fifty move rule was reached by the same move
+        if !has_legal_move(self) {
+            return if self.in_check() {
+                OxideOutcome::Checkmate { winner: self.position().side_to_move().opposite_side() }
+            } else {
+                OxideOutcome::Stalemate
+            };
+        }
+
+        if self.is_fifty_move_draw() {
+            OxideOutcome::FiftyMoveRule
+        } else if self.position().has_insufficient_material() {
+            OxideOutcome::InsufficientMaterial
+        } else {
+            OxideOutcome::Ongoing
+        }
+    }
+
+    fn is_fifty_move_draw(&self) -> bool {
+        self.position().halfmove_clock() >= FIFTY_MOVE_RULE_PLIES && (!self.in_check() || has_legal_move(self))
+    }
+}
+
+#[inline]
+fn has_legal_move(board: &OxideBoard) -> bool {
+    legal_moves::<OxidePosition, OxideBoard>(board).next().is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::SimpleChessMove;
+    use oxide_interface::game::OxideMove;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn board(fen: &str) -> OxideBoard {
+        OxideBoard::new(OxidePosition::from_fen(fen).unwrap())
+    }
+
+    fn outcome(fen: &str) -> OxideOutcome {
+        board(fen).outcome()
+    }
+
+    #[test]
+    fn outcome_works() {
+        assert_eq!(outcome("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), OxideOutcome::Ongoing);
+        assert_eq!(outcome("4k3/8/8/8/8/8/8/4K2R w - - 100 80"), OxideOutcome::FiftyMoveRule);
+        assert_eq!(outcome("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"), OxideOutcome::InsufficientMaterial);
+    }
+
+    #[test]
+    fn checkmate_works() {
+        // Back rank mate
+        assert_eq!(outcome("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), OxideOutcome::Checkmate { winner: OxideSide::White });
+        // Fool's mate
+        assert_eq!(outcome("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"), OxideOutcome::Checkmate { winner: OxideSide::Black });
+        // The checker can be captured
+        assert_eq!(outcome("R5k1/5ppp/8/8/8/8/8/r5K1 b - - 0 1"), OxideOutcome::Ongoing);
+        // Or the king can escape
+        assert_eq!(outcome("R5k1/5pp1/8/8/8/8/8/6K1 b - - 0 1"), OxideOutcome::Ongoing);
+    }
+
+    #[test]
+    fn stalemate_works() {
+        assert_eq!(outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), OxideOutcome::Stalemate);
+        // Only the pinned pawn could move
+        assert_eq!(outcome("k1N5/1pK5/8/3B4/8/8/8/8 b - - 0 1"), OxideOutcome::Stalemate);
+        // Mate takes priority over the fifty move rule
+        assert_eq!(outcome("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80"), OxideOutcome::Checkmate { winner: OxideSide::White });
+    }
+
+    #[test]
+    fn is_fifty_move_draw_works() {
+        assert!(!board("4k3/8/8/8/8/8/8/4K2R w - - 99 80").is_fifty_move_draw());
+        assert!(board("4k3/8/8/8/8/8/8/4K2R w - - 100 80").is_fifty_move_draw());
+        assert!(board("4k3/8/8/8/8/8/8/4K2R b - - 120 80").is_fifty_move_draw());
+    }
+
+    #[test]
+    fn mate_on_the_hundredth_ply_works() {
+        let mut mated = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80");
+        mated.make_move_unchecked(OxideMove::new(A1, A8));
+        assert_eq!(mated.position().halfmove_clock(), FIFTY_MOVE_RULE_PLIES);
+        assert!(!mated.is_fifty_move_draw());
+        assert_eq!(mated.outcome(), OxideOutcome::Checkmate { winner: OxideSide::White });
+
+        // The same check with an escape square is drawn
+        let mut checked = board("6k1/5pp1/8/8/8/8/8/R5K1 w - - 99 80");
+        checked.make_move_unchecked(OxideMove::new(A1, A8));
+        assert!(checked.in_check());
+        assert!(checked.is_fifty_move_draw());
+        assert_eq!(checked.outcome(), OxideOutcome::FiftyMoveRule);
+    }
+
+    #[test]
+    fn pgn_result_works() {
+        assert_eq!(OxideOutcome::Checkmate { winner: OxideSide::White }.pgn_result(), "1-0");
+        assert_eq!(OxideOutcome::Checkmate { winner: OxideSide::Black }.pgn_result(), "0-1");
+        assert_eq!(OxideOutcome::Stalemate.pgn_result(), "1/2-1/2");
+        assert_eq!(OxideOutcome::InsufficientMaterial.pgn_result(), "1/2-1/2");
+        assert_eq!(OxideOutcome::Ongoing.pgn_result(), "*");
+        assert!(OxideOutcome::FiftyMoveRule.is_draw());
+        assert!(!OxideOutcome::Ongoing.is_over());
+        assert_eq!(OxideOutcome::Checkmate { winner: OxideSide::Black }.winner(), Some(OxideSide::Black));
+    }
+}
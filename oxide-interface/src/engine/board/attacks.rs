@@ -0,0 +1,130 @@
+use interface::game::{BoardMask, LineMask, PieceArrangement, Piece, Shiftable, Side, Square};
+use crate::engine::OxidePosition;
+use crate::game::{OxideBitboard, OxidePiece, OxideSide, OxideSquare};
+
+// The attacks crate dev-depends on this one, so the few attack sets the board needs to track checks and pins live here
+
+#[inline]
+pub(crate) fn pawn_attacks(pawn_mask: OxideBitboard, side: OxideSide) -> OxideBitboard {
+    if side.is_white() {
+        pawn_mask.north_west_shift() | pawn_mask.north_east_shift()
+    } else {
+        pawn_mask.south_west_shift() | pawn_mask.south_east_shift()
+    }
+}
+
+#[inline]
+pub(crate) fn knight_attacks(knight_mask: OxideBitboard) -> OxideBitboard {
+    (((knight_mask << 15) | (knight_mask >> 17)) & !OxideBitboard::H_FILE)
+        | (((knight_mask >> 15) | (knight_mask << 17)) & !OxideBitboard::A_FILE)
+        | (((knight_mask << 6) | (knight_mask >> 10)) & !(OxideBitboard::G_FILE | OxideBitboard::H_FILE))
+        | (((knight_mask >> 6) | (knight_mask << 10)) & !(OxideBitboard::A_FILE | OxideBitboard::B_FILE))
+}
+
+#[inline]
+pub(crate) fn king_attacks(king_mask: OxideBitboard) -> OxideBitboard {
+    let side_attacks = king_mask.east_shift() | king_mask.west_shift();
+    let horizontal_attacks_unshifted = side_attacks | king_mask;
+
+    side_attacks | horizontal_attacks_unshifted.north_shift() | horizontal_attacks_unshifted.south_shift()
+}
+
+#[inline]
+pub(crate) fn bishop_attacks(from_mask: OxideBitboard, occupied: OxideBitboard) -> OxideBitboard {
+    from_mask.diagonal_ray_attacks(!occupied)
+}
+
+#[inline]
+pub(crate) fn rook_attacks(from_mask: OxideBitboard, occupied: OxideBitboard) -> OxideBitboard {
+    from_mask.cardinal_ray_attacks(!occupied)
+}
+
+#[inline]
+pub(crate) fn sided_piece_mask(position: &OxidePosition, piece: OxidePiece, side: OxideSide) -> OxideBitboard {
+    position.sided_piece_mask(<OxidePiece as Piece<OxidePosition>>::add_side(piece, side))
+}
+
+/// Squares a piece of the side moving attacks from a square (pawn captures only)
+#[inline]
+pub(crate) fn piece_attacks(piece: OxidePiece, side: OxideSide, square: OxideSquare, occupied: OxideBitboard) -> OxideBitboard {
+    let square_mask = square.to_mask();
+    match piece {
+        OxidePiece::Pawn => pawn_attacks(square_mask, side),
+        OxidePiece::Knight => knight_attacks(square_mask),
+        OxidePiece::Bishop => bishop_attacks(square_mask, occupied),
+        OxidePiece::Rook => rook_attacks(square_mask, occupied),
+        OxidePiece::Queen => bishop_attacks(square_mask, occupied) | rook_attacks(square_mask, occupied),
+        OxidePiece::King => king_attacks(square_mask),
+        OxidePiece::Empty => OxideBitboard::EMPTY,
+    }
+}
+
+/// Every piece of either side attacking a square given an occupancy
+pub(crate) fn attackers_to(position: &OxidePosition, square: OxideSquare, occupied: OxideBitboard) -> OxideBitboard {
+    let square_mask = square.to_mask();
+    let diagonal_sliders = position.piece_mask(OxidePiece::Bishop) | position.piece_mask(OxidePiece::Queen);
+    let cardinal_sliders = position.piece_mask(OxidePiece::Rook) | position.piece_mask(OxidePiece::Queen);
+
+    // A pawn attacks a square if an opposing pawn on that square would attack it
+    (pawn_attacks(square_mask, OxideSide::Black) & sided_piece_mask(position, OxidePiece::Pawn, OxideSide::White))
+        | (pawn_attacks(square_mask, OxideSide::White) & sided_piece_mask(position, OxidePiece::Pawn, OxideSide::Black))
+        | (knight_attacks(square_mask) & position.piece_mask(OxidePiece::Knight))
+        | (king_attacks(square_mask) & position.piece_mask(OxidePiece::King))
+        | (bishop_attacks(square_mask, occupied) & diagonal_sliders)
+        | (rook_attacks(square_mask, occupied) & cardinal_sliders)
+}
+
+/// If any piece of `side` attacks a square given an occupancy
+#[inline]
+pub(crate) fn is_attacked_by(position: &OxidePosition, square: OxideSquare, side: OxideSide, occupied: OxideBitboard) -> bool {
+    attackers_to(position, square, occupied) & position.mask_for_side(side) & occupied != OxideBitboard::EMPTY
+}
+
+/// Sliders of `side` attacking the enemy king through exactly one piece (the pinners) and the pieces between them (the blockers)
+pub(crate) fn pins(position: &OxidePosition, side: OxideSide) -> (OxideBitboard, OxideBitboard) {
+    let king_square = position.king_square(side.opposite_side());
+    let king_mask = king_square.to_mask();
+    let occupied = position.occupied();
+    let diagonal_sliders = sided_piece_mask(position, OxidePiece::Bishop, side) | sided_piece_mask(position, OxidePiece::Queen, side);
+    let cardinal_sliders = sided_piece_mask(position, OxidePiece::Rook, side) | sided_piece_mask(position, OxidePiece::Queen, side);
+    // Sliders that would see the king on an empty board
+    let snipers = (bishop_attacks(king_mask, OxideBitboard::EMPTY) & diagonal_sliders) | (rook_attacks(king_mask, OxideBitboard::EMPTY) & cardinal_sliders);
+
+    let mut pinning = OxideBitboard::EMPTY;
+    let mut blocking = OxideBitboard::EMPTY;
+    for sniper_square in snipers {
+        let between = OxideBitboard::between_fill(king_square, sniper_square) & occupied;
+        if between.0.count_ones() == 1 {
+            pinning |= sniper_square.to_mask();
+            blocking |= between;
+        }
+    }
+
+    (pinning, blocking)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+    use crate::game::OxideSquare::*;
+
+    #[test]
+    fn attackers_to_works() {
+        let position = OxidePosition::from_fen("4k3/8/2n5/8/1b1P4/4N3/8/R3K2R w - - 0 1").unwrap();
+        let attackers = attackers_to(&position, D2, position.occupied());
+        assert_eq!(attackers, E1.to_mask() | B4.to_mask());
+        let attackers = attackers_to(&position, E5, position.occupied());
+        assert_eq!(attackers, C6.to_mask() | D4.to_mask());
+        assert!(is_attacked_by(&position, C4, OxideSide::White, position.occupied()));
+        assert!(!is_attacked_by(&position, F1, OxideSide::Black, position.occupied()));
+    }
+
+    #[test]
+    fn pins_works() {
+        let position = OxidePosition::from_fen("4k3/4r3/8/8/1b6/8/3N4/4K3 w - - 0 1").unwrap();
+        // The bishop pins the knight but the rook's path to the king is clear
+        assert_eq!(pins(&position, OxideSide::Black), (B4.to_mask(), D2.to_mask()));
+        assert_eq!(pins(&position, OxideSide::White), (OxideBitboard::EMPTY, OxideBitboard::EMPTY));
+    }
+}
@@ -0,0 +1,123 @@
+use interface::engine::Board;
+use interface::game::{ChessMove, PieceArrangement, Position, Side, SimpleChessMove};
+use crate::engine::OxidePosition;
+use crate::game::{OxideMove, OxidePiece, OxideSidedPiece, OxideSquare};
+use super::{castle_rook_squares, en_passant_pawn_square, sided, OxideBoard};
+
+// A castle moves two pieces, every other move removes at most the mover and a captured piece
+const MAX_CHANGES: usize = 2;
+
+/// Pieces a move takes off the board and puts back on, for evaluations updated from the move instead of the whole position
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OxidePieceChanges {
+    removed: [(OxideSidedPiece, OxideSquare); MAX_CHANGES],
+    removed_count: usize,
+    added: [(OxideSidedPiece, OxideSquare); MAX_CHANGES],
+    added_count: usize,
+}
+
+impl OxidePieceChanges {
+    const NONE: Self = Self {
+        removed: [(OxideSidedPiece::WhitePawn, OxideSquare::A1); MAX_CHANGES],
+        removed_count: 0,
+        added: [(OxideSidedPiece::WhitePawn, OxideSquare::A1); MAX_CHANGES],
+        added_count: 0,
+    };
+
+    #[inline]
+    fn remove(&mut self, piece: OxideSidedPiece, square: OxideSquare) {
+        self.removed[self.removed_count] = (piece, square);
+        self.removed_count += 1;
+    }
+
+    #[inline]
+    fn add(&mut self, piece: OxideSidedPiece, square: OxideSquare) {
+        self.added[self.added_count] = (piece, square);
+        self.added_count += 1;
+    }
+
+    /// Pieces taken off their squares
+    #[inline]
+    pub fn removed(&self) -> &[(OxideSidedPiece, OxideSquare)] {
+        &self.removed[..self.removed_count]
+    }
+
+    /// Pieces put on their squares
+    #[inline]
+    pub fn added(&self) -> &[(OxideSidedPiece, OxideSquare)] {
+        &self.added[..self.added_count]
+    }
+}
+
+impl OxideBoard {
+    /// Pieces a move would remove and add, worked out before it's made
+    pub fn piece_changes(&self, chess_move: OxideMove) -> OxidePieceChanges {
+        let position: &OxidePosition = self.position();
+        let side = position.side_to_move();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let piece = position.piece_on_square(from);
+        let mut changes = OxidePieceChanges::NONE;
+
+        changes.remove(sided(piece, side), from);
+        if chess_move.is_en_passant_capture() {
+            changes.remove(sided(OxidePiece::Pawn, side.opposite_side()), en_passant_pawn_square(to, side));
+        } else if chess_move.is_capture() {
+            changes.remove(sided(position.piece_on_square(to), side.opposite_side()), to);
+        }
+
+        if chess_move.is_promotion() {
+            changes.add(sided(chess_move.promotion(), side), to);
+        } else {
+            changes.add(sided(piece, side), to);
+        }
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            let (rook_from, rook_to) = castle_rook_squares(chess_move);
+            changes.remove(sided(OxidePiece::Rook, side), rook_from);
+            changes.add(sided(OxidePiece::Rook, side), rook_to);
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::SidedPiece;
+    use crate::game::OxideSquare::*;
+
+    fn pieces(position: &OxidePosition) -> Vec<(OxideSidedPiece, OxideSquare)> {
+        <OxideSidedPiece as SidedPiece<OxidePosition>>::PIECES.iter()
+            .flat_map(|&piece| position.sided_piece_mask(piece).map(move |square| (piece, square)))
+            .collect()
+    }
+
+    #[test]
+    fn piece_changes_works() {
+        let cases = [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", OxideMove::WHITE_KING_CASTLE),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", OxideMove::BLACK_QUEEN_CASTLE),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", OxideMove::new_capture(A1, A8)),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", OxideMove::new_en_passant_capture(E5, D6)),
+            ("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", OxideMove::new_double_pawn_push(E2, E4)),
+            ("1n2k3/P7/8/8/8/8/8/4K3 w - - 5 1", OxideMove::new_promoting_capture(A7, B8, OxidePiece::Queen)),
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 5 1", OxideMove::new(G1, F3)),
+        ];
+        for &(fen, chess_move) in &cases {
+            let mut board = OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+            let changes = board.piece_changes(chess_move);
+            let mut changed = pieces(board.position());
+            for removed in changes.removed() {
+                let index = changed.iter().position(|piece| piece == removed).expect("Removed piece isn't on the board");
+                changed.swap_remove(index);
+            }
+            changed.extend_from_slice(changes.added());
+
+            board.make_move_unchecked(chess_move);
+            let expected = pieces(board.position());
+            assert_eq!(changed.len(), expected.len(), "Making {} from {}", chess_move, fen);
+            assert!(expected.iter().all(|piece| changed.contains(piece)), "Making {} from {}", chess_move, fen);
+        }
+    }
+}
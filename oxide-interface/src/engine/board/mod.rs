@@ -1,5 +1,6 @@
-use crate::engine::zobrist::{OxideZobristHasher, BASE_KEY};
-use interface::game::{PieceArrangement, SimpleChessMove, ChessMove, Side, BoardMask, CastleRights, Piece, SidedPiece, Square, Position};
+use crate::engine::zobrist::OxideZobristHasher;
+use interface::types::PlyCount;
+use interface::game::{PieceArrangement, SimpleChessMove, ChessMove, Side, BoardMask, LineMask, CastleRights, Piece, SidedPiece, Square, Position, Shiftable};
 use crate::game::{OxidePiece, OxideBitboard, OxideSquare, OxideCastleRights, OxideSide, OxideSidedPiece, OxideMove, OxideSimpleMove, OxideIllegalMoveError};
 use crate::engine::position::OxidePosition;
 use std::fmt::Debug;
@@ -7,6 +8,15 @@ use std::error::Error;
 use crate::engine::OxideFenParseError;
 use std::hash::{Hash, Hasher};
 use interface::engine::{IdempotentBoardState, CachedBoardState, BoardState, Board};
+use attacks::{attackers_to, bishop_attacks, is_attacked_by, knight_attacks, pawn_attacks, piece_attacks, pins, rook_attacks, sided_piece_mask};
+
+mod attacks;
+mod changes;
+
+pub use changes::OxidePieceChanges;
+
+/// Plies without a capture or pawn move after which the game is drawn
+pub const FIFTY_MOVE_RULE_PLIES: PlyCount = 100;
 
 #[derive(Copy, Clone, Debug)]
 pub struct OxideBoardState {
@@ -95,24 +105,37 @@ impl CachedBoardState<OxidePosition> for OxideBoardState {
 
 impl BoardState<OxidePosition> for OxideBoardState {
     fn new(position: &OxidePosition) -> Self {
-        let state = Self {
-            white_pinning: OxideBitboard::EMPTY,
-            white_blocking: OxideBitboard::EMPTY,
-            black_pinning: OxideBitboard::EMPTY,
-            black_blocking: OxideBitboard::EMPTY,
-            checkers: OxideBitboard::EMPTY,
-            check_piece_masks: [OxideBitboard::EMPTY; 6],
-            zobrist_hasher: OxideZobristHasher(BASE_KEY),
+        let side = position.side_to_move();
+        let enemy_king_square = position.king_square(side.opposite_side());
+        let occupied = position.occupied();
+        let (white_pinning, black_blocking) = pins(position, OxideSide::White);
+        let (black_pinning, white_blocking) = pins(position, OxideSide::Black);
+        let checkers = attackers_to(position, position.king_square(side), occupied) & position.mask_for_side(side.opposite_side());
+        // Squares each piece of the side to move would check the enemy king from
+        let enemy_king_mask = enemy_king_square.to_mask();
+        let bishop_checks = bishop_attacks(enemy_king_mask, occupied);
+        let rook_checks = rook_attacks(enemy_king_mask, occupied);
+
+        Self {
+            white_pinning,
+            white_blocking,
+            black_pinning,
+            black_blocking,
+            checkers,
+            check_piece_masks: [
+                pawn_attacks(enemy_king_mask, side.opposite_side()),
+                knight_attacks(enemy_king_mask),
+                bishop_checks,
+                rook_checks,
+                bishop_checks | rook_checks,
+                OxideBitboard::EMPTY,
+            ],
+            zobrist_hasher: OxideZobristHasher(position.zobrist_key()),
             castle_rights: position.castle_rights(),
             en_passant_square: position.en_passant_square(),
             captured_piece: OxidePiece::Empty,
             halfmove_clock: position.halfmove_clock() as u8
-        };
-
-        // TODO: Call something to add pins and blocks to state
-        // TODO: Call something to add checkers and update check_piece_masks to state
-
-        state
+        }
     }
 }
 
@@ -140,36 +163,391 @@ impl Board<OxidePosition> for OxideBoard {
     }
 
     fn make_move(&mut self, chess_move: Self::Move) -> Result<Self::BoardState, Self::IllegalMoveError> {
-        todo!()
+        self.validate_move(chess_move)?;
+
+        Ok(self.make_move_unchecked(chess_move))
     }
 
     fn make_move_unchecked(&mut self, chess_move: Self::Move) -> Self::BoardState {
-        todo!()
+        let previous_state = self.state;
+        let side = self.position.side_to_move();
+        let enemy_side = side.opposite_side();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let piece = self.position.piece_on_square(from);
+        debug_assert_ne!(piece, OxidePiece::Empty, "Attempting to make a move from an empty square");
+
+        let captured_piece = if chess_move.is_en_passant_capture() {
+            self.position.remove_piece(sided(OxidePiece::Pawn, enemy_side), en_passant_pawn_square(to, side));
+            OxidePiece::Pawn
+        } else if chess_move.is_capture() {
+            let captured_piece = self.position.piece_on_square(to);
+            debug_assert_ne!(captured_piece, OxidePiece::Empty, "Attempting to make a capture onto an empty square");
+            self.position.remove_piece(sided(captured_piece, enemy_side), to);
+            captured_piece
+        } else {
+            OxidePiece::Empty
+        };
+
+        if chess_move.is_promotion() {
+            self.position.remove_piece(sided(OxidePiece::Pawn, side), from);
+            self.position.add_piece(sided(chess_move.promotion(), side), to);
+        } else {
+            self.position.move_piece(sided(piece, side), to, from);
+        }
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            let (rook_from, rook_to) = castle_rook_squares(chess_move);
+            self.position.move_piece(sided(OxidePiece::Rook, side), rook_to, rook_from);
+        }
+
+        self.position.clear_en_passant();
+        if chess_move.is_double_pawn_push() {
+            // Only kept when it can be captured so transpositions share a key
+            let en_passant_square = en_passant_pawn_square(to, side);
+            if pawn_attacks(en_passant_square.to_mask(), side) & sided_piece_mask(&self.position, OxidePiece::Pawn, enemy_side) != OxideBitboard::EMPTY {
+                self.position.set_en_passant(en_passant_square);
+            }
+        }
+        self.position.remove_castle_rights(lost_castle_rights(from) | lost_castle_rights(to));
+        if piece == OxidePiece::Pawn || captured_piece != OxidePiece::Empty {
+            self.position.reset_halfmove_clock();
+        } else {
+            self.position.increment_halfmove_clock();
+        }
+        self.position.switch_sides();
+        self.position.increment_halfmove_count();
+
+        self.state = OxideBoardState {
+            captured_piece,
+            ..OxideBoardState::new(&self.position)
+        };
+
+        previous_state
     }
 
     fn undo_move(&mut self, chess_move: Self::Move, previous_state: Self::BoardState) -> Result<(), Self::UndoMoveError> {
-        todo!()
+        self.validate_undo(chess_move)?;
+        self.undo_move_unchecked(chess_move, previous_state);
+
+        Ok(())
     }
 
     fn undo_move_unchecked(&mut self, chess_move: Self::Move, previous_state: Self::BoardState) {
-        todo!()
+        let captured_piece = self.state.captured_piece;
+        self.position.switch_sides();
+        self.position.decrement_halfmove_count();
+        let side = self.position.side_to_move();
+        let enemy_side = side.opposite_side();
+        let from = chess_move.from();
+        let to = chess_move.to();
+
+        if chess_move.is_promotion() {
+            self.position.remove_piece(sided(chess_move.promotion(), side), to);
+            self.position.add_piece(sided(OxidePiece::Pawn, side), from);
+        } else {
+            let piece = self.position.piece_on_square(to);
+            self.position.move_piece(sided(piece, side), from, to);
+        }
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            let (rook_from, rook_to) = castle_rook_squares(chess_move);
+            self.position.move_piece(sided(OxidePiece::Rook, side), rook_from, rook_to);
+        }
+
+        if chess_move.is_en_passant_capture() {
+            self.position.add_piece(sided(OxidePiece::Pawn, enemy_side), en_passant_pawn_square(to, side));
+        } else if captured_piece != OxidePiece::Empty {
+            self.position.add_piece(sided(captured_piece, enemy_side), to);
+        }
+
+        self.position.clear_en_passant();
+        if let Some(en_passant_square) = previous_state.en_passant_square {
+            self.position.set_en_passant(en_passant_square);
+        }
+        self.position.set_castle_rights(previous_state.castle_rights);
+        self.position.set_halfmove_clock(previous_state.halfmove_clock);
+        self.state = previous_state;
+        debug_assert_eq!(self.position.zobrist_key(), previous_state.zobrist_hasher.finish(), "Undoing {} didn't restore the position", chess_move);
+    }
+
+    fn make_null_move(&mut self) -> Self::BoardState {
+        debug_assert!(!self.in_check(), "Attempting to make a null move while in check");
+        let previous_state = self.state;
+
+        self.position.clear_en_passant();
+        self.position.switch_sides();
+        self.position.increment_halfmove_clock();
+        self.position.increment_halfmove_count();
+        self.state = OxideBoardState::new(&self.position);
+
+        previous_state
+    }
+
+    fn undo_null_move(&mut self, previous_state: Self::BoardState) {
+        self.position.switch_sides();
+        self.position.decrement_halfmove_count();
+        if let Some(en_passant_square) = previous_state.en_passant_square {
+            self.position.set_en_passant(en_passant_square);
+        }
+        self.position.set_halfmove_clock(previous_state.halfmove_clock);
+        self.state = previous_state;
     }
+
     #[inline]
     fn in_check(&self) -> bool {
         self.state.checkers_mask() != OxideBitboard::EMPTY
     }
 
     fn is_discovery(&self, chess_move: &Self::Move) -> bool {
-        todo!()
+        let position = &self.position;
+        let side = position.side_to_move();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let enemy_king_square = position.king_square(side.opposite_side());
+
+        if chess_move.is_en_passant_capture() {
+            // The captured pawn can also be the piece uncovering a line to the king
+            let occupied = (position.occupied() ^ from.to_mask() ^ en_passant_pawn_square(to, side).to_mask()) | to.to_mask();
+            let enemy_king_mask = enemy_king_square.to_mask();
+            let diagonal_sliders = sided_piece_mask(position, OxidePiece::Bishop, side) | sided_piece_mask(position, OxidePiece::Queen, side);
+            let cardinal_sliders = sided_piece_mask(position, OxidePiece::Rook, side) | sided_piece_mask(position, OxidePiece::Queen, side);
+            return (bishop_attacks(enemy_king_mask, occupied) & diagonal_sliders) | (rook_attacks(enemy_king_mask, occupied) & cardinal_sliders) != OxideBitboard::EMPTY;
+        }
+
+        // Moving a piece off a line between a slider and the enemy king, unless it stays on that line
+        self.state.blocking_mask(side.opposite_side()) & from.to_mask() != OxideBitboard::EMPTY && !OxideBitboard::aligned(from, to, enemy_king_square)
     }
 
     fn gives_check(&self, chess_move: &Self::Move) -> bool {
-        todo!()
+        let position = &self.position;
+        let side = position.side_to_move();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let enemy_king_mask = position.king_square(side.opposite_side()).to_mask();
+
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            // Only the rook can check, along lines the king just left
+            let (rook_from, rook_to) = castle_rook_squares(*chess_move);
+            let occupied = (position.occupied() ^ from.to_mask() ^ rook_from.to_mask()) | to.to_mask() | rook_to.to_mask();
+            return rook_attacks(rook_to.to_mask(), occupied) & enemy_king_mask != OxideBitboard::EMPTY;
+        }
+        if chess_move.is_promotion() {
+            // The promoted piece may attack through the square the pawn left
+            let occupied = position.occupied() ^ from.to_mask();
+            return piece_attacks(chess_move.promotion(), side, to, occupied) & enemy_king_mask != OxideBitboard::EMPTY;
+        }
+
+        self.state.piece_check_mask(position.piece_on_square(from)) & to.to_mask() != OxideBitboard::EMPTY
     }
 
     fn is_legal(&self, chess_move: &Self::Move) -> bool {
-        // todo!()
-        true
+        let position = &self.position;
+        let side = position.side_to_move();
+        let enemy_side = side.opposite_side();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let king_square = position.king_square(side);
+        let occupied = position.occupied();
+
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            // The king can't castle out of, through or into check
+            return !self.in_check() && (OxideBitboard::between_fill(from, to) | to.to_mask())
+                .into_iter()
+                .all(|square| !is_attacked_by(position, square, enemy_side, occupied));
+        }
+        if from == king_square {
+            // Without the king in the way a slider's attack continues past it
+            return !is_attacked_by(position, to, enemy_side, occupied ^ from.to_mask());
+        }
+        if chess_move.is_en_passant_capture() {
+            // Two pawns leave the king's lines at once, so check the resulting occupancy directly
+            let occupied = (occupied ^ from.to_mask() ^ en_passant_pawn_square(to, side).to_mask()) | to.to_mask();
+            return !is_attacked_by(position, king_square, enemy_side, occupied);
+        }
+
+        if self.in_check() {
+            // Only the king escapes a double check, otherwise the checker has to be captured or blocked
+            let checkers = self.state.checkers;
+            let checker_square = match OxideSquare::from_mask(checkers) {
+                Some(checker_square) if checkers.0.count_ones() == 1 => checker_square,
+                _ => return false,
+            };
+            if (OxideBitboard::between_fill(king_square, checker_square) | checkers) & to.to_mask() == OxideBitboard::EMPTY {
+                return false;
+            }
+        }
+
+        // A pinned piece can only move along the pin
+        self.state.blocking_mask(side) & from.to_mask() == OxideBitboard::EMPTY || OxideBitboard::aligned(from, to, king_square)
+    }
+}
+
+#[inline]
+fn sided(piece: OxidePiece, side: OxideSide) -> OxideSidedPiece {
+    <OxidePiece as Piece<OxidePosition>>::add_side(piece, side)
+}
+
+// The square a pawn captured en-passant stands on, or skipped over with a double push, given where the moving pawn lands
+#[inline]
+fn en_passant_pawn_square(to: OxideSquare, side: OxideSide) -> OxideSquare {
+    if side.is_white() {
+        to.south_shift()
+    } else {
+        to.north_shift()
+    }
+}
+
+// Where the rook moves from and to when castling
+#[inline]
+fn castle_rook_squares(chess_move: OxideMove) -> (OxideSquare, OxideSquare) {
+    match (chess_move.is_king_castle(), chess_move.from() == OxideSquare::E1) {
+        (true, true) => (OxideSquare::H1, OxideSquare::F1),
+        (false, true) => (OxideSquare::A1, OxideSquare::D1),
+        (true, false) => (OxideSquare::H8, OxideSquare::F8),
+        (false, false) => (OxideSquare::A8, OxideSquare::D8),
+    }
+}
+
+// Castle rights lost when a piece moves from or to a square
+#[inline]
+fn lost_castle_rights(square: OxideSquare) -> OxideCastleRights {
+    match square {
+        OxideSquare::E1 => OxideCastleRights::WHITE_ALL,
+        OxideSquare::H1 => OxideCastleRights::WHITE_KING,
+        OxideSquare::A1 => OxideCastleRights::WHITE_QUEEN,
+        OxideSquare::E8 => OxideCastleRights::BLACK_ALL,
+        OxideSquare::H8 => OxideCastleRights::BLACK_KING,
+        OxideSquare::A8 => OxideCastleRights::BLACK_QUEEN,
+        _ => OxideCastleRights::NONE,
+    }
+}
+
+impl OxideBoard {
+    // If a move could be played from the position at all, which the unchecked path takes for granted
+    fn validate_move(&self, chess_move: OxideMove) -> Result<(), OxideIllegalMoveError> {
+        let position = &self.position;
+        let side = position.side_to_move();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let from_mask = from.to_mask();
+        let to_mask = to.to_mask();
+        let occupied = position.occupied();
+        let friendly = position.mask_for_side(side);
+        let piece = position.piece_on_square(from);
+
+        if piece == OxidePiece::Empty {
+            return Err(OxideIllegalMoveError::MovingFromEmptySquare);
+        }
+        if friendly & from_mask == OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::MovingPieceForWrongSide);
+        }
+        if friendly & to_mask != OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::CapturingOwnPiece);
+        }
+        if chess_move.is_en_passant_capture() {
+            if piece != OxidePiece::Pawn || position.en_passant_square() != Some(to) {
+                return Err(OxideIllegalMoveError::NonExistentEnPassantCapture);
+            }
+        } else if chess_move.is_capture() && occupied & to_mask == OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::NonCapturingCapture);
+        } else if !chess_move.is_capture() && occupied & to_mask != OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::CapturingNonCapture);
+        }
+
+        if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+            let (castle_rights, castle_move) = match (chess_move.is_king_castle(), side.is_white()) {
+                (true, true) => (OxideCastleRights::WHITE_KING, OxideMove::WHITE_KING_CASTLE),
+                (false, true) => (OxideCastleRights::WHITE_QUEEN, OxideMove::WHITE_QUEEN_CASTLE),
+                (true, false) => (OxideCastleRights::BLACK_KING, OxideMove::BLACK_KING_CASTLE),
+                (false, false) => (OxideCastleRights::BLACK_QUEEN, OxideMove::BLACK_QUEEN_CASTLE),
+            };
+            if chess_move != castle_move || piece != OxidePiece::King || !position.castle_rights().contains(castle_rights) {
+                return Err(OxideIllegalMoveError::CastlingWithoutPermission);
+            }
+            if castle_rights.castle_path() & occupied != OxideBitboard::EMPTY {
+                return Err(OxideIllegalMoveError::InvalidCardinalMovement);
+            }
+            if !self.is_legal(&chess_move) {
+                return Err(OxideIllegalMoveError::CastlingThroughAttack);
+            }
+
+            return Ok(());
+        }
+
+        if piece == OxidePiece::Pawn {
+            let forward = |mask: OxideBitboard| if side.is_white() { mask.north_shift() } else { mask.south_shift() };
+            let (start_rank, last_rank) = if side.is_white() {
+                (OxideBitboard::RANK_2, OxideBitboard::RANK_8)
+            } else {
+                (OxideBitboard::RANK_7, OxideBitboard::RANK_1)
+            };
+
+            if chess_move.is_capture() {
+                if pawn_attacks(from_mask, side) & to_mask == OxideBitboard::EMPTY {
+                    return Err(OxideIllegalMoveError::VerticalPawnCapture);
+                }
+            } else if to_mask.file_fill() & from_mask == OxideBitboard::EMPTY {
+                return Err(OxideIllegalMoveError::HorizontalPawnPush);
+            } else if chess_move.is_double_pawn_push() {
+                // The landing square was checked above, the skipped one has to be empty too
+                let skipped_mask = forward(from_mask);
+                if from_mask & start_rank == OxideBitboard::EMPTY || skipped_mask & occupied != OxideBitboard::EMPTY || forward(skipped_mask) != to_mask {
+                    return Err(OxideIllegalMoveError::InvalidPawnPush);
+                }
+            } else if forward(from_mask) != to_mask {
+                return Err(OxideIllegalMoveError::InvalidPawnPush);
+            }
+            // A pawn reaching the last rank has to promote and can't promote anywhere else
+            if chess_move.is_promotion() != (to_mask & last_rank != OxideBitboard::EMPTY) {
+                return Err(OxideIllegalMoveError::InvalidPawnPush);
+            }
+        } else if chess_move.is_double_pawn_push() || chess_move.is_promotion() {
+            // Only pawns push twice or promote
+            return Err(OxideIllegalMoveError::InvalidPawnPush);
+        } else if piece_attacks(piece, side, from, occupied) & to_mask == OxideBitboard::EMPTY {
+            return Err(match piece {
+                OxidePiece::Knight => OxideIllegalMoveError::InvalidKnightJump,
+                OxidePiece::Bishop => OxideIllegalMoveError::InvalidDiagonalMovement,
+                OxidePiece::Rook => OxideIllegalMoveError::InvalidCardinalMovement,
+                // Queens and kings go either way, so go by the line the square is on
+                _ if bishop_attacks(from_mask, OxideBitboard::EMPTY) & to_mask != OxideBitboard::EMPTY => OxideIllegalMoveError::InvalidDiagonalMovement,
+                _ => OxideIllegalMoveError::InvalidCardinalMovement,
+            });
+        }
+
+        if !self.is_legal(&chess_move) {
+            return Err(OxideIllegalMoveError::SelfCheck);
+        }
+
+        Ok(())
+    }
+
+    // If a move could have been the last one made, so undoing it puts the pieces back where they came from
+    fn validate_undo(&self, chess_move: OxideMove) -> Result<(), OxideIllegalMoveError> {
+        let position = &self.position;
+        let side = position.side_to_move().opposite_side();
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let piece = position.piece_on_square(to);
+
+        if piece == OxidePiece::Empty {
+            return Err(OxideIllegalMoveError::MovingFromEmptySquare);
+        }
+        if position.mask_for_side(side) & to.to_mask() == OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::MovingPieceForWrongSide);
+        }
+        // The piece moves back onto the square it left, which nothing can have taken since
+        if position.occupied() & from.to_mask() != OxideBitboard::EMPTY {
+            return Err(OxideIllegalMoveError::CapturingOwnPiece);
+        }
+        if chess_move.is_promotion() && piece != chess_move.promotion() {
+            return Err(OxideIllegalMoveError::InvalidPawnPush);
+        }
+        // The captured piece comes back from the state, so it has to have been left by a capture
+        if chess_move.is_capture() && !chess_move.is_en_passant_capture() && self.state.captured_piece == OxidePiece::Empty {
+            return Err(OxideIllegalMoveError::NonCapturingCapture);
+        }
+
+        Ok(())
     }
 }
 
@@ -282,4 +660,215 @@ impl Board<OxideSide, OxidePiece, OxideSide, OxideBitboard, OxideSquare, OxideCa
         todo!()
     }
 }
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::OxideSquare::*;
+
+    #[test]
+    fn null_move_works() {
+        let mut position = OxidePosition::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3").unwrap();
+        position.set_en_passant(E3);
+        let mut board = OxideBoard::new(position);
+        let original_key = board.position().zobrist_key();
+        let previous_state = board.make_null_move();
+        assert_eq!(board.position().side_to_move(), OxideSide::White);
+        assert_eq!(board.position().en_passant_square(), None);
+        assert_eq!(board.state().en_passant_square(), None);
+        assert_eq!(board.position().halfmove_clock(), 1);
+        assert_ne!(board.position().zobrist_key(), original_key);
+        board.undo_null_move(previous_state);
+        assert_eq!(board.position().side_to_move(), OxideSide::Black);
+        assert_eq!(board.position().en_passant_square(), Some(E3));
+        assert_eq!(board.position().halfmove_clock(), 0);
+        assert_eq!(board.position().zobrist_key(), original_key);
+    }
+
+    #[test]
+    fn state_tracks_checks_and_pins() {
+        let board = OxideBoard::new(OxidePosition::from_fen("4k3/8/8/8/1b6/8/3N4/R3K3 b - - 0 1").unwrap());
+        assert!(!board.in_check());
+        assert_eq!(board.state().blocking_mask(OxideSide::White), D2.to_mask());
+        assert_eq!(board.state().pinning_mask(OxideSide::Black), B4.to_mask());
+        // Squares black checks the white king from
+        assert_ne!(board.state().piece_check_mask(OxidePiece::Rook) & E2.to_mask(), OxideBitboard::EMPTY);
+        assert_ne!(board.state().piece_check_mask(OxidePiece::Knight) & F3.to_mask(), OxideBitboard::EMPTY);
+        assert_eq!(board.state().piece_check_mask(OxidePiece::Pawn), D2.to_mask() | F2.to_mask());
+
+        let board = OxideBoard::new(OxidePosition::from_fen("4k3/8/8/8/1b6/8/8/4K3 w - - 0 1").unwrap());
+        assert!(board.in_check());
+        assert_eq!(board.state().checkers_mask(), B4.to_mask());
+    }
+
+    #[test]
+    fn make_and_undo_move_works() {
+        let cases = [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", OxideMove::WHITE_KING_CASTLE, "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", OxideMove::BLACK_QUEEN_CASTLE, "2kr3r/8/8/8/8/8/8/R3K2R w KQ - 1 2"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", OxideMove::new_capture(A1, A8), "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", OxideMove::new_en_passant_capture(E5, D6), "4k3/8/3P4/8/8/8/8/4K3 b - - 0 1"),
+            ("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", OxideMove::new_double_pawn_push(E2, E4), "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+            // Nothing can capture en-passant so the square isn't kept
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 3 1", OxideMove::new_double_pawn_push(E2, E4), "4k3/8/8/8/4P3/8/8/4K3 b - - 0 1"),
+            ("1n2k3/P7/8/8/8/8/8/4K3 w - - 5 1", OxideMove::new_promoting_capture(A7, B8, OxidePiece::Queen), "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1"),
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 5 1", OxideMove::new(G1, F3), "4k3/8/8/8/8/5N2/8/4K3 b - - 6 1"),
+        ];
+        for &(fen, chess_move, expected_fen) in &cases {
+            let mut board = OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+            let expected = OxidePosition::from_fen(expected_fen).unwrap();
+            let previous_state = board.make_move_unchecked(chess_move);
+            assert_eq!(board.position().to_fen(), expected.to_fen(), "Making {} from {}", chess_move, fen);
+            assert_eq!(board.position().zobrist_key(), expected.zobrist_key(), "Making {} from {}", chess_move, fen);
+            assert_eq!(board.position().pawn_key(), expected.pawn_key(), "Making {} from {}", chess_move, fen);
+            assert_eq!(board.position().piece_squares(), expected.piece_squares(), "Making {} from {}", chess_move, fen);
+
+            board.undo_move_unchecked(chess_move, previous_state);
+            let original = OxidePosition::from_fen(fen).unwrap();
+            assert_eq!(board.position().to_fen(), original.to_fen(), "Undoing {} from {}", chess_move, fen);
+            assert_eq!(board.position().zobrist_key(), original.zobrist_key());
+        }
+    }
+
+    #[test]
+    fn make_move_updates_checks() {
+        let mut board = OxideBoard::new(OxidePosition::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap());
+        let previous_state = board.make_move_unchecked(OxideMove::new(H1, H8));
+        assert!(board.in_check());
+        assert_eq!(board.state().checkers_mask(), H8.to_mask());
+        assert_eq!(board.state().captured_piece(), OxidePiece::Empty);
+        board.undo_move_unchecked(OxideMove::new(H1, H8), previous_state);
+        assert!(!board.in_check());
+        assert_eq!(board.position().castle_rights(), OxideCastleRights::WHITE_KING);
+    }
+
+    #[test]
+    fn is_legal_works() {
+        let board = |fen: &str| OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+        // Pinned knight
+        let pinned = board("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1");
+        assert!(!pinned.is_legal(&OxideMove::new(D2, F3)));
+        assert!(pinned.is_legal(&OxideMove::new(E1, F1)));
+        // Pinned rook moving along the pin
+        let pinned = board("4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1");
+        assert!(pinned.is_legal(&OxideMove::new(E2, E5)));
+        assert!(pinned.is_legal(&OxideMove::new_capture(E2, E8)));
+        assert!(!pinned.is_legal(&OxideMove::new(E2, D2)));
+        // The king can't step into an attack or stay on a checking slider's line
+        let checked = board("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+        assert!(!checked.is_legal(&OxideMove::new(E1, F1)));
+        assert!(!checked.is_legal(&OxideMove::new(E1, D1)));
+        assert!(checked.is_legal(&OxideMove::new(E1, E2)));
+        // Evasions have to block or capture the checker
+        let checked = board("4k3/8/8/8/8/8/8/r3K1N1 w - - 0 1");
+        assert!(!checked.is_legal(&OxideMove::new(G1, F3)));
+        let checked = board("4k3/8/8/8/8/1N6/8/r3K3 w - - 0 1");
+        assert!(checked.is_legal(&OxideMove::new_capture(B3, A1)));
+        assert!(checked.is_legal(&OxideMove::new(B3, C1)));
+        assert!(!checked.is_legal(&OxideMove::new(B3, D4)));
+        // Double check
+        let double_checked = board("4k3/8/8/8/8/3n4/8/r3K2R w - - 0 1");
+        assert!(!double_checked.is_legal(&OxideMove::new(H1, H8)));
+        assert!(double_checked.is_legal(&OxideMove::new(E1, E2)));
+        // En-passant capture exposing the king along the rank
+        let en_passant = board("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1");
+        assert!(!en_passant.is_legal(&OxideMove::new_en_passant_capture(B5, C6)));
+        let en_passant = board("8/8/8/1Pp4r/K7/8/8/4k3 w - c6 0 1");
+        assert!(en_passant.is_legal(&OxideMove::new_en_passant_capture(B5, C6)));
+        // Castling through or out of check
+        let castling = board("4k3/8/8/8/8/8/6r1/R3K2R w KQ - 0 1");
+        assert!(!castling.is_legal(&OxideMove::WHITE_KING_CASTLE));
+        assert!(castling.is_legal(&OxideMove::WHITE_QUEEN_CASTLE));
+        let castling = board("4k3/8/8/8/8/8/8/R3K2r w Q - 0 1");
+        assert!(!castling.is_legal(&OxideMove::WHITE_QUEEN_CASTLE));
+    }
+
+    #[test]
+    fn gives_check_works() {
+        let board = |fen: &str| OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+        let checks = board("4k3/8/8/8/8/8/8/R3K1N1 w Q - 0 1");
+        assert!(checks.gives_check(&OxideMove::new(A1, A8)));
+        assert!(!checks.gives_check(&OxideMove::new(A1, A7)));
+        assert!(!checks.gives_check(&OxideMove::new(G1, F3)));
+        // The rook checks once the king is out of its way
+        let castle = board("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        assert!(castle.gives_check(&OxideMove::WHITE_QUEEN_CASTLE));
+        // A promoted piece attacks back through the square the pawn left
+        let promotion = board("8/3P4/8/8/8/8/3k4/K7 w - - 0 1");
+        assert!(promotion.gives_check(&OxideMove::new_promotion(D7, D8, OxidePiece::Queen)));
+        assert!(!promotion.gives_check(&OxideMove::new_promotion(D7, D8, OxidePiece::Knight)));
+
+        // Moving off the bishop's diagonal discovers a check, moving along it doesn't
+        let discovery = board("7k/8/8/8/3N4/8/1B6/K7 w - - 0 1");
+        assert!(discovery.is_discovery(&OxideMove::new(D4, F3)));
+        assert!(!discovery.is_discovery(&OxideMove::new(B2, C3)));
+        // Capturing en-passant can clear a rank of both pawns
+        let en_passant = board("8/8/8/R2pP2k/8/8/8/K7 w - d6 0 1");
+        assert!(en_passant.is_discovery(&OxideMove::new_en_passant_capture(E5, D6)));
+        assert!(!en_passant.gives_check(&OxideMove::new_en_passant_capture(E5, D6)));
+    }
+
+    #[test]
+    fn make_move_rejects_illegal_moves() {
+        use OxideIllegalMoveError::*;
+        let cases = [
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", OxideMove::new(F1, F2), MovingFromEmptySquare),
+            ("4k1n1/8/8/8/8/8/8/4K1N1 w - - 0 1", OxideMove::new(G8, F6), MovingPieceForWrongSide),
+            ("4k3/8/8/8/8/8/4P3/4K1N1 w - - 0 1", OxideMove::new_capture(G1, E2), CapturingOwnPiece),
+            ("4k3/8/8/8/8/5p2/8/4K1N1 w - - 0 1", OxideMove::new_capture(G1, H3), NonCapturingCapture),
+            ("4k3/8/8/8/8/5p2/8/4K1N1 w - - 0 1", OxideMove::new(G1, F3), CapturingNonCapture),
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", OxideMove::new(G1, G3), InvalidKnightJump),
+            // Blocked by its own pawn
+            ("4k3/8/8/8/8/8/3P4/2B1K3 w - - 0 1", OxideMove::new(C1, E3), InvalidDiagonalMovement),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", OxideMove::new(A1, B2), InvalidCardinalMovement),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", OxideMove::new(E2, F3), HorizontalPawnPush),
+            ("4k3/8/8/8/8/4p3/4P3/4K3 w - - 0 1", OxideMove::new_capture(E2, E3), VerticalPawnCapture),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", OxideMove::new(E2, E4), InvalidPawnPush),
+            ("4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1", OxideMove::new_double_pawn_push(E2, E4), InvalidPawnPush),
+            ("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", OxideMove::new(A7, A8), InvalidPawnPush),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1", OxideMove::new_en_passant_capture(E5, D6), NonExistentEnPassantCapture),
+            ("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1", OxideMove::WHITE_KING_CASTLE, CastlingWithoutPermission),
+            ("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1", OxideMove::WHITE_QUEEN_CASTLE, InvalidCardinalMovement),
+            ("4k3/8/8/8/8/8/6r1/R3K2R w KQ - 0 1", OxideMove::WHITE_KING_CASTLE, CastlingThroughAttack),
+            ("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1", OxideMove::new(D2, F3), SelfCheck),
+        ];
+        for &(fen, chess_move, error) in &cases {
+            let mut board = OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+            assert_eq!(board.make_move(chess_move).err(), Some(error), "Making {} from {}", chess_move, fen);
+            assert_eq!(board.position().to_fen(), OxidePosition::from_fen(fen).unwrap().to_fen(), "Making {} from {}", chess_move, fen);
+        }
+    }
+
+    #[test]
+    fn make_and_undo_checked_move_works() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K1N1 w - - 0 1";
+        let mut board = OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+        let previous_state = board.make_move(OxideMove::new_double_pawn_push(E2, E4)).unwrap();
+        assert_eq!(board.position().to_fen(), OxidePosition::from_fen("4k3/8/8/8/4P3/8/8/4K1N1 b - - 0 1").unwrap().to_fen());
+        assert_eq!(board.undo_move(OxideMove::new(D2, D4), previous_state), Err(OxideIllegalMoveError::MovingFromEmptySquare));
+        assert_eq!(board.undo_move(OxideMove::new(E7, E8), previous_state), Err(OxideIllegalMoveError::MovingPieceForWrongSide));
+        assert_eq!(board.undo_move(OxideMove::new(G1, E4), previous_state), Err(OxideIllegalMoveError::CapturingOwnPiece));
+        assert_eq!(board.undo_move(OxideMove::new_double_pawn_push(E2, E4), previous_state), Ok(()));
+        assert_eq!(board.position().to_fen(), OxidePosition::from_fen(fen).unwrap().to_fen());
+    }
+
+    #[test]
+    fn null_move_updates_checks() {
+        let mut board = OxideBoard::new(OxidePosition::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap());
+        let previous_state = board.make_null_move();
+        assert!(!board.in_check());
+        // Black to move, so the masks are of squares black checks white's king from
+        assert_eq!(board.state().piece_check_mask(OxidePiece::Pawn), D2.to_mask() | F2.to_mask());
+        board.undo_null_move(previous_state);
+
+        let mut board = OxideBoard::new(OxidePosition::from_fen("r3k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap());
+        board.make_null_move();
+        assert!(!board.in_check());
+        // Passing with a rook already on the king's file
+        let mut board = OxideBoard::new(OxidePosition::from_fen("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1").unwrap());
+        board.make_null_move();
+        assert!(board.in_check());
+        assert_eq!(board.state().checkers_mask(), E2.to_mask());
+    }
+}
@@ -0,0 +1,143 @@
+use interface::game::Position;
+use interface::types::PlyCount;
+use crate::engine::OxidePosition;
+
+// A position that was reached and how many plies before it a repetition is possible
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct HistoryEntry {
+    key: u64,
+    reversible_plies: PlyCount,
+}
+
+/// Stack of the zobrist keys of every position reached in a game (or search line) for repetition detection
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OxideKeyHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl OxideKeyHistory {
+    /// A history starting from a position
+    pub fn new(position: &OxidePosition) -> Self {
+        let mut history = Self::default();
+        history.push(position);
+
+        history
+    }
+
+    /// Record the position reached after making a move
+    #[inline]
+    pub fn push(&mut self, position: &OxidePosition) {
+        // Positions before a null move can't repeat through it even though the halfmove clock keeps counting
+        let reversible_plies = self.entries.last()
+            .map_or(position.halfmove_clock(), |previous| position.halfmove_clock().min(previous.reversible_plies + 1));
+        self.entries.push(HistoryEntry {
+            key: position.zobrist_key(),
+            reversible_plies,
+        });
+    }
+
+    /// Record the position reached after a null move
+    #[inline]
+    pub fn push_null(&mut self, position: &OxidePosition) {
+        self.entries.push(HistoryEntry {
+            key: position.zobrist_key(),
+            reversible_plies: 0,
+        });
+    }
+
+    /// Forget the latest position when undoing a move
+    #[inline]
+    pub fn pop(&mut self) {
+        self.entries.pop();
+    }
+
+    /// Number of positions recorded
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// If no positions were recorded
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Key of the latest position
+    #[inline]
+    pub fn last_key(&self) -> Option<u64> {
+        self.entries.last().map(|entry| entry.key)
+    }
+
+    /// If the latest position occurred at least `count` times (including itself), only scanning back to the last irreversible move
+    pub fn is_repetition(&self, count: usize) -> bool {
+        let current = match self.entries.last() {
+            Some(current) => current,
+            None => return false,
+        };
+
+        // Only positions with the same side to move can repeat, so every other entry is skipped
+        let earlier = &self.entries[..self.entries.len() - 1];
+        let occurrences = 1 + earlier.iter()
+            .rev()
+            .take(current.reversible_plies as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|entry| entry.key == current.key)
+            .count();
+
+        occurrences >= count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(fen: &str) -> OxidePosition {
+        OxidePosition::from_fen(fen).unwrap()
+    }
+
+    // A rook and king shuffle back to the starting squares after four plies
+    fn shuffle(history: &mut OxideKeyHistory, first_halfmove_clock: PlyCount) {
+        history.push(&position(&format!("4k3/8/8/8/8/8/8/4K1R1 b - - {} 1", first_halfmove_clock)));
+        history.push(&position(&format!("3k4/8/8/8/8/8/8/4K1R1 w - - {} 2", first_halfmove_clock + 1)));
+        history.push(&position(&format!("3k4/8/8/8/8/8/8/4K2R b - - {} 2", first_halfmove_clock + 2)));
+        history.push(&position(&format!("4k3/8/8/8/8/8/8/4K2R w - - {} 3", first_halfmove_clock + 3)));
+    }
+
+    #[test]
+    fn is_repetition_works() {
+        let mut history = OxideKeyHistory::new(&position("4k3/8/8/8/8/8/8/4K2R w - - 0 1"));
+        assert!(!history.is_repetition(2));
+        shuffle(&mut history, 1);
+        assert!(history.is_repetition(2));
+        assert!(!history.is_repetition(3));
+        shuffle(&mut history, 5);
+        assert!(history.is_repetition(3));
+        history.pop();
+        assert!(history.is_repetition(2));
+        assert!(!history.is_repetition(3));
+    }
+
+    #[test]
+    fn irreversible_move_ends_repetition_scan() {
+        let mut history = OxideKeyHistory::new(&position("4k3/8/8/8/8/8/8/4K2R w - - 0 1"));
+        // The same keys, but the clock says an irreversible move happened in between
+        history.push(&position("4k3/8/8/8/8/8/8/4K1R1 b - - 1 1"));
+        history.push(&position("3k4/8/8/8/8/8/8/4K1R1 w - - 0 2"));
+        history.push(&position("3k4/8/8/8/8/8/8/4K2R b - - 1 2"));
+        history.push(&position("4k3/8/8/8/8/8/8/4K2R w - - 2 3"));
+        assert!(!history.is_repetition(2));
+    }
+
+    #[test]
+    fn null_move_ends_repetition_scan() {
+        let mut history = OxideKeyHistory::new(&position("4k3/8/8/8/8/8/8/4K2R w - - 0 1"));
+        history.push(&position("4k3/8/8/8/8/8/8/4K1R1 b - - 1 1"));
+        history.push_null(&position("4k3/8/8/8/8/8/8/4K1R1 w - - 2 1"));
+        history.push(&position("4k3/8/8/8/8/8/8/4K2R b - - 3 1"));
+        history.push(&position("4k3/8/8/8/8/8/8/4K2R w - - 4 1"));
+        assert!(!history.is_repetition(2));
+    }
+}
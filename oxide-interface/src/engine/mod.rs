@@ -3,7 +3,13 @@ mod score;
 mod zobrist;
 mod board;
 mod position;
+mod history;
+mod tapered;
+mod psqt;
 
 pub use score::OxideScore;
 pub use position::{OxideFenParseError, OxidePieceArrangement, OxidePosition};
-pub use board::{OxideBoard, OxideBoardState};
\ No newline at end of file
+pub use board::{OxideBoard, OxideBoardState, OxidePieceChanges, FIFTY_MOVE_RULE_PLIES};
+pub use history::OxideKeyHistory;
+pub use tapered::{TaperedScore, MAX_PHASE};
+pub use psqt::{piece_square_value, phase_weight, MIDGAME_PIECE_VALUES, ENDGAME_PIECE_VALUES, PHASE_WEIGHTS, MIDGAME_TABLES, ENDGAME_TABLES};
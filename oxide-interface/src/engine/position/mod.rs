@@ -2,25 +2,76 @@ mod piece_arrangement;
 
 
 pub use piece_arrangement::OxidePieceArrangement;
+use crate::engine::TaperedScore;
 use crate::engine::zobrist::{OxideZobristHasher, piece_key, castle_key, en_passant_key, SIDE_KEY, BASE_KEY};
 use crate::game::{OxideBitboard, OxideSide, OxidePiece, OxideSidedPiece, OxideSquare, OxideCastleRights, OxideSquare::A8};
-use interface::game::{Position, PieceArrangement, Square, Side, CastleRights, Piece};
+use interface::game::{Position, PieceArrangement, Square, Side, CastleRights, Piece, BoardMask};
 use interface::types::PlyCount;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter, Result as FormatResult};
 use std::hash::{Hasher, Hash};
 
+// Squares the same colour as a1
+const DARK_SQUARES: OxideBitboard = OxideBitboard(0xAA55_AA55_AA55_AA55);
+
 #[derive(Copy, Clone)]
 pub struct OxidePosition {
     arrangement: OxidePieceArrangement,
     side: OxideSide,
     zobrist_hasher: OxideZobristHasher,
+    // Only hashes the pawns, for caching pawn structure evaluation
+    pawn_hasher: OxideZobristHasher,
     castle_rights: OxideCastleRights,
     en_passant_square: Option<OxideSquare>,
     halfmove_clock: u8,
     halfmove_count: PlyCount,
 }
 
+impl OxidePosition {
+    /// The incrementally maintained zobrist key for the position (cheaper than re-hashing)
+    #[inline]
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_hasher.finish()
+    }
+    /// Neither side can mate with any sequence of legal moves (KK, KBK, KNK or only bishops all on the same colour)
+    pub fn has_insufficient_material(&self) -> bool {
+        let non_king_pieces = self.occupied() & !self.piece_mask(OxidePiece::King);
+        let bishops = self.piece_mask(OxidePiece::Bishop);
+        let knights = self.piece_mask(OxidePiece::Knight);
+
+        if non_king_pieces.0.count_ones() <= 1 {
+            non_king_pieces & !(bishops | knights) == OxideBitboard::EMPTY
+        } else {
+            non_king_pieces == bishops && (bishops & DARK_SQUARES == OxideBitboard::EMPTY || bishops & !DARK_SQUARES == OxideBitboard::EMPTY)
+        }
+    }
+    /// Zobrist key of only the pawns, maintained next to the main key
+    #[inline]
+    pub fn pawn_key(&self) -> u64 {
+        self.pawn_hasher.finish()
+    }
+    /// Material and piece-square sum from white's perspective and the game phase, maintained as pieces move
+    #[inline]
+    pub fn piece_squares(&self) -> (TaperedScore, i32) {
+        self.arrangement.piece_squares()
+    }
+    /// Restore the halfmove clock from a previous state when undoing
+    #[inline]
+    pub(crate) fn set_halfmove_clock(&mut self, halfmove_clock: u8) {
+        self.halfmove_clock = halfmove_clock;
+    }
+    /// Count a ply played in the game (the fullmove count follows from it)
+    #[inline]
+    pub(crate) fn increment_halfmove_count(&mut self) {
+        self.halfmove_count += 1;
+    }
+    /// Uncount the last ply played when undoing
+    #[inline]
+    pub(crate) fn decrement_halfmove_count(&mut self) {
+        self.halfmove_count -= 1;
+    }
+}
+
 impl Debug for OxidePosition {
     fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
         let mut ranks: [String; 8] = [
@@ -69,6 +120,11 @@ impl Hash for OxidePosition {
     }
 }
 
+#[inline]
+fn is_pawn(piece: OxideSidedPiece) -> bool {
+    piece == OxideSidedPiece::WhitePawn || piece == OxideSidedPiece::BlackPawn
+}
+
 impl PieceArrangement<OxidePosition> for OxidePosition {
     type Side = OxideSide;
     type Piece = OxidePiece;
@@ -79,6 +135,7 @@ impl PieceArrangement<OxidePosition> for OxidePosition {
         arrangement: OxidePieceArrangement::EMPTY,
         side: OxideSide::White,
         zobrist_hasher: OxideZobristHasher(BASE_KEY),
+        pawn_hasher: OxideZobristHasher(BASE_KEY),
         castle_rights: CastleRights::NONE,
         en_passant_square: None,
         halfmove_clock: 0,
@@ -121,17 +178,27 @@ impl PieceArrangement<OxidePosition> for OxidePosition {
     fn add_piece(&mut self, piece: OxideSidedPiece, to_square: OxideSquare) {
         self.arrangement.add_piece(piece, to_square);
         self.zobrist_hasher.write_u64(piece_key(piece, to_square));
+        if is_pawn(piece) {
+            self.pawn_hasher.write_u64(piece_key(piece, to_square));
+        }
     }
     #[inline]
     fn remove_piece(&mut self, piece: OxideSidedPiece, from_square: OxideSquare) {
         self.arrangement.remove_piece(piece, from_square);
         self.zobrist_hasher.write_u64(piece_key(piece, from_square));
+        if is_pawn(piece) {
+            self.pawn_hasher.write_u64(piece_key(piece, from_square));
+        }
     }
     #[inline]
-    fn move_piece(&mut self, piece: OxideSidedPiece, from_square: OxideSquare, to_square: OxideSquare) {
-        self.arrangement.move_piece(piece, from_square, to_square);
+    fn move_piece(&mut self, piece: OxideSidedPiece, to_square: OxideSquare, from_square: OxideSquare) {
+        self.arrangement.move_piece(piece, to_square, from_square);
         self.zobrist_hasher.write_u64(piece_key(piece, from_square));
         self.zobrist_hasher.write_u64(piece_key(piece, to_square));
+        if is_pawn(piece) {
+            self.pawn_hasher.write_u64(piece_key(piece, from_square));
+            self.pawn_hasher.write_u64(piece_key(piece, to_square));
+        }
     }
 }
 
@@ -254,8 +321,7 @@ impl Position for OxidePosition {
         builder.push(' ');
         builder.push_str(self.halfmove_clock().to_string().as_str());
         builder.push(' ');
-        // Convert halfmove to fullmove by dividing by 2, add 1 since we are 0 indexed
-        builder.push_str((self.fullmove_count() / 2 + 1).to_string().as_str());
+        builder.push_str(self.fullmove_count().to_string().as_str());
 
         builder
     }
@@ -293,7 +359,7 @@ impl Position for OxidePosition {
     }
     #[inline]
     fn remove_castle_rights(&mut self, castle_rights: OxideCastleRights) {
-        let removed_rights = !self.castle_rights & castle_rights;
+        let removed_rights = self.castle_rights & castle_rights;
         self.castle_rights.remove(removed_rights);
         self.zobrist_hasher.write_u64(castle_key(removed_rights));
     }
@@ -328,6 +394,7 @@ impl Position for OxidePosition {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::game::OxideSquare::*;
 
     #[test]
     fn from_default_fen_works() {
@@ -351,4 +418,61 @@ mod test {
         assert_eq!(position.sided_piece_mask(OxideSidedPiece::WhiteKing), OxideBitboard(0x10u64));
         assert_eq!(position.sided_piece_mask(OxideSidedPiece::BlackKing), OxideBitboard(0x1000000000000000u64));
     }
+
+    #[test]
+    fn fullmove_count_round_trips() {
+        for fen in &["4k3/8/8/8/8/8/8/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/4K3 b - - 0 1", "4k3/8/8/8/8/8/8/4K3 w - - 7 42", "4k3/8/8/8/8/8/8/4K3 b - - 7 42"] {
+            assert_eq!(OxidePosition::from_fen(fen).unwrap().to_fen(), *fen);
+        }
+        assert_eq!(OxidePosition::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 7 42").unwrap().fullmove_count(), 42);
+    }
+
+    #[test]
+    fn piece_squares_are_incremental() {
+        let mut position = OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let (start_piece_squares, start_phase) = position.piece_squares();
+        assert_eq!(start_piece_squares, TaperedScore::ZERO);
+        assert_eq!(start_phase, 24);
+
+        // 1. e4 d5 2. exd5
+        position.move_piece(OxideSidedPiece::WhitePawn, E4, E2);
+        position.move_piece(OxideSidedPiece::BlackPawn, D5, D7);
+        position.remove_piece(OxideSidedPiece::BlackPawn, D5);
+        position.move_piece(OxideSidedPiece::WhitePawn, D5, E4);
+        let expected = OxidePosition::from_fen("rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+        assert_eq!(position.piece_squares(), expected.piece_squares());
+
+        position.remove_piece(OxideSidedPiece::BlackQueen, D8);
+        position.add_piece(OxideSidedPiece::BlackKnight, D8);
+        assert_eq!(position.piece_squares().1, 21);
+        assert_eq!(position.piece_squares(), position.arrangement.recount_piece_squares());
+    }
+
+    #[test]
+    fn pawn_key_only_hashes_pawns() {
+        let mut position = OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let (key, pawn_key) = (position.zobrist_key(), position.pawn_key());
+        position.move_piece(OxideSidedPiece::WhiteKnight, F3, G1);
+        assert_ne!(position.zobrist_key(), key);
+        assert_eq!(position.pawn_key(), pawn_key);
+        position.move_piece(OxideSidedPiece::WhitePawn, E4, E2);
+        assert_ne!(position.pawn_key(), pawn_key);
+        let expected = OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 1").unwrap();
+        assert_eq!(position.pawn_key(), expected.pawn_key());
+    }
+
+    #[test]
+    fn has_insufficient_material_works() {
+        let insufficient = |fen: &str| OxidePosition::from_fen(fen).unwrap().has_insufficient_material();
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1"));
+        // Bishops all on dark squares
+        assert!(insufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/4K2R w - - 0 1"));
+        assert!(!insufficient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    }
 }
\ No newline at end of file
@@ -2,7 +2,7 @@ use interface::game::{SidedPiece, Piece, Square, BoardMask, Side, PieceArrangeme
 use crate::game::{OxideBitboard, OxidePiece, OxideSidedPiece, OxideSquare, OxideSide};
 use crate::engine::zobrist::piece_key;
 use std::hash::{Hasher, Hash};
-use crate::engine::OxidePosition;
+use crate::engine::{OxidePosition, TaperedScore, piece_square_value, phase_weight};
 
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -15,6 +15,33 @@ pub struct OxidePieceArrangement {
     knights: OxideBitboard,
     white: OxideBitboard,
     black: OxideBitboard,
+    // Material and piece-square sum from white's perspective, kept up to date as pieces are added, removed or moved
+    piece_squares: TaperedScore,
+    // Non-pawn material weight for tapering between the midgame and endgame
+    phase: i32,
+}
+
+impl OxidePieceArrangement {
+    /// Incrementally maintained material and piece-square sum (from white's perspective) and game phase
+    #[inline]
+    pub fn piece_squares(&self) -> (TaperedScore, i32) {
+        debug_assert_eq!((self.piece_squares, self.phase), self.recount_piece_squares(), "Incremental piece-square sums differ from a full recount");
+        (self.piece_squares, self.phase)
+    }
+
+    /// Sum the material, piece-square values and game phase of every piece from scratch
+    pub fn recount_piece_squares(&self) -> (TaperedScore, i32) {
+        let mut piece_squares = TaperedScore::ZERO;
+        let mut phase = 0;
+        for &sided_piece in &<OxideSidedPiece as SidedPiece<OxidePosition>>::PIECES {
+            for square in self.sided_piece_mask(sided_piece) {
+                piece_squares += piece_square_value(sided_piece, square);
+                phase += phase_weight(<OxideSidedPiece as SidedPiece<OxidePosition>>::unsided_piece(&sided_piece));
+            }
+        }
+
+        (piece_squares, phase)
+    }
 }
 
 impl Hash for OxidePieceArrangement {
@@ -49,7 +76,9 @@ impl PieceArrangement<OxidePosition> for OxidePieceArrangement {
         rooks: OxideBitboard(0),
         knights: OxideBitboard(0),
         white: OxideBitboard(0),
-        black: OxideBitboard(0)
+        black: OxideBitboard(0),
+        piece_squares: TaperedScore::ZERO,
+        phase: 0,
     };
 
     #[inline]
@@ -154,6 +183,8 @@ impl PieceArrangement<OxidePosition> for OxidePieceArrangement {
         } else {
             self.black |= mask;
         }
+        self.piece_squares += piece_square_value(sided_piece, to_square);
+        self.phase += phase_weight(piece);
     }
     #[inline]
     fn remove_piece(&mut self, sided_piece: OxideSidedPiece, from_square: OxideSquare) {
@@ -176,6 +207,8 @@ impl PieceArrangement<OxidePosition> for OxidePieceArrangement {
         } else {
             self.black &= mask;
         }
+        self.piece_squares -= piece_square_value(sided_piece, from_square);
+        self.phase -= phase_weight(piece);
     }
     #[inline]
     fn move_piece(&mut self, sided_piece: OxideSidedPiece, to_square: OxideSquare, from_square: OxideSquare) {
@@ -199,5 +232,6 @@ impl PieceArrangement<OxidePosition> for OxidePieceArrangement {
         } else {
             self.black ^= mask;
         }
+        self.piece_squares += piece_square_value(sided_piece, to_square) - piece_square_value(sided_piece, from_square);
     }
 }
\ No newline at end of file
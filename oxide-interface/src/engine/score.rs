@@ -1,10 +1,65 @@
 use interface::engine::PositionalScore;
+use std::fmt::{Display, Formatter, Result as FormatResult};
 use std::ops::{Neg, Div, Mul, Add, Sub};
 use interface::types::PlyCount;
 
+// Furthest mate that can be represented, scores within this many plies of mate are mate scores
+const MAX_MATE_PLIES: PlyCount = 256;
+const MATE_VALUE: i32 = 32_000;
+const INFINITE_VALUE: i32 = MATE_VALUE + 1;
+
+/// Score from the side to move's perspective in centipawns, or the plies until either side mates
 #[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Default, Debug)]
 pub struct OxideScore(i32);
 
+impl OxideScore {
+    /// Bound beyond any score a search can return (every score lies within `-INFINITE..=INFINITE`)
+    pub const INFINITE: Self = Self(INFINITE_VALUE);
+
+    #[inline]
+    fn bounded(value: i32) -> Self {
+        Self(value.max(-INFINITE_VALUE).min(INFINITE_VALUE))
+    }
+
+    /// Multiply two scores, None if the product is outside the score range
+    #[inline]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0)
+            .filter(|value| value.abs() <= INFINITE_VALUE)
+            .map(Self)
+    }
+
+    /// Divide two scores, None when dividing by zero
+    #[inline]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.0.checked_div(rhs.0).map(Self)
+    }
+
+    /// Convert a mate score counted from the root to one counted from a node `ply` plies deep (for storing in a table shared between nodes)
+    #[inline]
+    pub fn to_node_relative(self, ply: PlyCount) -> Self {
+        if self.mate_in().is_some() {
+            Self(self.0 + ply as i32)
+        } else if self.mated_in().is_some() {
+            Self(self.0 - ply as i32)
+        } else {
+            self
+        }
+    }
+
+    /// Convert a mate score counted from a node `ply` plies deep back to one counted from the root
+    #[inline]
+    pub fn to_root_relative(self, ply: PlyCount) -> Self {
+        if self.mate_in().is_some() {
+            Self(self.0 - ply as i32)
+        } else if self.mated_in().is_some() {
+            Self(self.0 + ply as i32)
+        } else {
+            self
+        }
+    }
+}
+
 impl Neg for OxideScore {
     type Output = Self;
 
@@ -17,7 +72,7 @@ impl Mul for OxideScore {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Self(self.0.mul(rhs.0))
+        self.checked_mul(rhs).expect("Score multiplication overflowed")
     }
 }
 
@@ -25,7 +80,7 @@ impl Div for OxideScore {
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self::Output {
-        Self(self.0.div(rhs.0))
+        self.checked_div(rhs).expect("Score division by zero")
     }
 }
 
@@ -34,7 +89,7 @@ impl Add for OxideScore {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0.add(rhs.0))
+        Self::bounded(self.0.add(rhs.0))
     }
 }
 
@@ -43,34 +98,172 @@ impl Sub for OxideScore {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self(self.0.sub(rhs.0))
+        Self::bounded(self.0.sub(rhs.0))
     }
 }
 
 impl PositionalScore for OxideScore {
-    const MATE_SCORE: Self = Self(i32::MAX);
+    const MATE_SCORE: Self = Self(MATE_VALUE);
+    const DRAW_SCORE: Self = Self(0);
+
     fn new(centipawns: i32) -> Self {
-        Self(centipawns)
+        Self::bounded(centipawns)
     }
 
-    fn new_mate(plies_to_mate: u16) -> Self {
-        Self(OxideScore::MATE_SCORE.0 - plies_to_mate as i32)
+    fn new_mate(plies_to_mate: PlyCount) -> Self {
+        Self(MATE_VALUE - plies_to_mate.min(MAX_MATE_PLIES) as i32)
+    }
+
+    fn new_mated(plies_to_mated: PlyCount) -> Self {
+        -Self::new_mate(plies_to_mated)
     }
 
     fn is_mate(&self) -> bool {
-        const MAX_MATE_PLIES: PlyCount = 50;
-        Self::MATE_SCORE - Self(MAX_MATE_PLIES as i32) >= *self
+        self.mate_in().is_some() || self.mated_in().is_some()
     }
 
-    fn mate_in(&self) -> Option<u16> {
-        if self.is_mate() {
-            Some((Self::MATE_SCORE - *self).0 as u16)
+    fn mate_in(&self) -> Option<PlyCount> {
+        let plies = MATE_VALUE - self.0;
+        if (0..=MAX_MATE_PLIES as i32).contains(&plies) {
+            Some(plies as PlyCount)
         } else {
             None
         }
     }
 
+    fn mated_in(&self) -> Option<PlyCount> {
+        (-*self).mate_in()
+    }
+
     fn centipawns(&self) -> i32 {
         self.0
     }
-}
\ No newline at end of file
+}
+
+impl Display for OxideScore {
+    /// UCI form, mates are in full moves and negative when the side to move is getting mated
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        if let Some(plies) = self.mate_in() {
+            write!(f, "mate {}", (plies as i32 + 1) / 2)
+        } else if let Some(plies) = self.mated_in() {
+            write!(f, "mate {}", -(plies as i32) / 2)
+        } else {
+            write!(f, "cp {}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new_is_bounded() {
+        assert_eq!(OxideScore::new(35).centipawns(), 35);
+        assert_eq!(OxideScore::new(-35).centipawns(), -35);
+        assert_eq!(OxideScore::new(i32::MAX), OxideScore::INFINITE);
+        assert_eq!(OxideScore::new(i32::MIN), -OxideScore::INFINITE);
+        assert!(OxideScore::INFINITE > OxideScore::MATE_SCORE);
+        assert!(-OxideScore::INFINITE < OxideScore::new_mated(0));
+    }
+
+    #[test]
+    fn arithmetic_is_bounded() {
+        assert_eq!(OxideScore::new(30) + OxideScore::new(5), OxideScore::new(35));
+        assert_eq!(OxideScore::new(30) - OxideScore::new(65), OxideScore::new(-35));
+        assert_eq!(OxideScore::INFINITE + OxideScore::new(1), OxideScore::INFINITE);
+        assert_eq!(-OxideScore::INFINITE - OxideScore::new(1), -OxideScore::INFINITE);
+        assert_eq!(-OxideScore::INFINITE + OxideScore::new(1) - OxideScore::new(1), -OxideScore::INFINITE);
+        assert_eq!(-(-OxideScore::INFINITE), OxideScore::INFINITE);
+        assert_eq!(-OxideScore::MATE_SCORE, OxideScore::new_mated(0));
+    }
+
+    #[test]
+    fn checked_mul_works() {
+        assert_eq!(OxideScore::new(7).checked_mul(OxideScore::new(-5)), Some(OxideScore::new(-35)));
+        assert_eq!(OxideScore::new(200).checked_mul(OxideScore::new(200)), None);
+        assert_eq!(OxideScore::INFINITE.checked_mul(OxideScore::INFINITE), None);
+        assert_eq!(OxideScore::new(7) * OxideScore::new(5), OxideScore::new(35));
+    }
+
+    #[test]
+    fn checked_div_works() {
+        assert_eq!(OxideScore::new(70).checked_div(OxideScore::new(2)), Some(OxideScore::new(35)));
+        assert_eq!(OxideScore::new(-70).checked_div(OxideScore::new(2)), Some(OxideScore::new(-35)));
+        assert_eq!(OxideScore::new(70).checked_div(OxideScore::new(0)), None);
+        assert_eq!(OxideScore::new(70) / OxideScore::new(2), OxideScore::new(35));
+    }
+
+    #[test]
+    #[should_panic]
+    fn div_by_zero_panics() {
+        let _ = OxideScore::new(70) / OxideScore::new(0);
+    }
+
+    #[test]
+    fn mate_scores_are_symmetric() {
+        for plies in 0..=MAX_MATE_PLIES {
+            let mate = OxideScore::new_mate(plies);
+            let mated = OxideScore::new_mated(plies);
+            assert_eq!(mated, -mate);
+            assert!(mate.is_mate());
+            assert!(mated.is_mate());
+            assert_eq!(mate.mate_in(), Some(plies));
+            assert_eq!(mate.mated_in(), None);
+            assert_eq!(mated.mated_in(), Some(plies));
+            assert_eq!(mated.mate_in(), None);
+        }
+    }
+
+    #[test]
+    fn mate_ordering_works() {
+        // Sooner mates are better, later mateds are better
+        assert!(OxideScore::new_mate(1) > OxideScore::new_mate(3));
+        assert!(OxideScore::new_mated(1) < OxideScore::new_mated(3));
+        assert!(OxideScore::new_mate(MAX_MATE_PLIES) > OxideScore::new(20_000));
+        assert!(OxideScore::new_mated(MAX_MATE_PLIES) < OxideScore::new(-20_000));
+    }
+
+    #[test]
+    fn centipawns_are_not_mate() {
+        for centipawns in [-20_000, -900, -35, 0, 35, 900, 20_000].iter().copied() {
+            let score = OxideScore::new(centipawns);
+            assert!(!score.is_mate());
+            assert_eq!(score.mate_in(), None);
+            assert_eq!(score.mated_in(), None);
+        }
+        assert!(!OxideScore::INFINITE.is_mate());
+        assert!(!OxideScore::DRAW_SCORE.is_mate());
+        assert_eq!(OxideScore::DRAW_SCORE.centipawns(), 0);
+    }
+
+    #[test]
+    fn ply_adjustment_works() {
+        // Mate in 5 from the root is mate in 2 from a node 3 plies deep
+        assert_eq!(OxideScore::new_mate(5).to_node_relative(3), OxideScore::new_mate(2));
+        assert_eq!(OxideScore::new_mate(2).to_root_relative(3), OxideScore::new_mate(5));
+        assert_eq!(OxideScore::new_mated(6).to_node_relative(4), OxideScore::new_mated(2));
+        assert_eq!(OxideScore::new_mated(2).to_root_relative(4), OxideScore::new_mated(6));
+        assert_eq!(OxideScore::new(35).to_node_relative(4), OxideScore::new(35));
+        assert_eq!(OxideScore::new(-35).to_root_relative(4), OxideScore::new(-35));
+        for plies in 0..64 {
+            for ply in 0..64 {
+                let mate = OxideScore::new_mate(plies + ply);
+                assert_eq!(mate.to_node_relative(ply).to_root_relative(ply), mate);
+                assert_eq!((-mate).to_node_relative(ply).to_root_relative(ply), -mate);
+            }
+        }
+    }
+
+    #[test]
+    fn display_works() {
+        assert_eq!(OxideScore::new(35).to_string(), "cp 35");
+        assert_eq!(OxideScore::new(-35).to_string(), "cp -35");
+        assert_eq!(OxideScore::DRAW_SCORE.to_string(), "cp 0");
+        assert_eq!(OxideScore::new_mate(1).to_string(), "mate 1");
+        assert_eq!(OxideScore::new_mate(2).to_string(), "mate 1");
+        assert_eq!(OxideScore::new_mate(5).to_string(), "mate 3");
+        assert_eq!(OxideScore::new_mated(2).to_string(), "mate -1");
+        assert_eq!(OxideScore::new_mated(6).to_string(), "mate -3");
+    }
+}
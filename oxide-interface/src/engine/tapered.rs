@@ -0,0 +1,131 @@
+use std::ops::{Add, AddAssign, Sub, SubAssign, Neg, Mul};
+use std::fmt::{Debug, Formatter, Result as FormatResult};
+
+/// Largest game phase, reached with every non-pawn piece of the starting position on the board
+pub const MAX_PHASE: i32 = 24;
+
+/// A midgame and an endgame score packed into one integer so both are added in a single operation
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TaperedScore(i32);
+
+impl TaperedScore {
+    pub const ZERO: Self = Self(0);
+
+    #[inline]
+    pub const fn new(midgame: i32, endgame: i32) -> Self {
+        Self(((endgame as u32) << 16).wrapping_add(midgame as u32) as i32)
+    }
+
+    /// Score when every piece is still on the board
+    #[inline]
+    pub const fn midgame(self) -> i32 {
+        self.0 as i16 as i32
+    }
+
+    /// Score when only kings and pawns are left
+    #[inline]
+    pub const fn endgame(self) -> i32 {
+        // Rounding compensates for the borrow the midgame takes from the endgame when negative
+        ((self.0 as u32).wrapping_add(0x8000) >> 16) as i16 as i32
+    }
+
+    /// Multiply both scores by a fraction, rounding toward zero
+    #[inline]
+    pub const fn scale(self, numerator: i32, denominator: i32) -> Self {
+        Self::new(self.midgame() * numerator / denominator, self.endgame() * numerator / denominator)
+    }
+
+    /// Interpolate between the endgame (phase 0) and midgame (`MAX_PHASE`) scores
+    #[inline]
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.max(0).min(MAX_PHASE);
+
+        (self.midgame() * phase + self.endgame() * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Debug for TaperedScore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        write!(f, "TaperedScore({}, {})", self.midgame(), self.endgame())
+    }
+}
+
+impl Add for TaperedScore {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl AddAssign for TaperedScore {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for TaperedScore {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl SubAssign for TaperedScore {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for TaperedScore {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+impl Mul<i32> for TaperedScore {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self(self.0.wrapping_mul(rhs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn packing_works() {
+        for &(midgame, endgame) in &[(0, 0), (35, -12), (-35, 12), (-900, -950), (20000, -20000)] {
+            let score = TaperedScore::new(midgame, endgame);
+            assert_eq!((score.midgame(), score.endgame()), (midgame, endgame));
+        }
+    }
+
+    #[test]
+    fn arithmetic_works() {
+        let a = TaperedScore::new(10, -20);
+        let b = TaperedScore::new(-30, 5);
+        assert_eq!(a + b, TaperedScore::new(-20, -15));
+        assert_eq!(a - b, TaperedScore::new(40, -25));
+        assert_eq!(-a, TaperedScore::new(-10, 20));
+        assert_eq!(b * 3, TaperedScore::new(-90, 15));
+        assert_eq!(b.scale(1, 2), TaperedScore::new(-15, 2));
+    }
+
+    #[test]
+    fn taper_works() {
+        let score = TaperedScore::new(100, 200);
+        assert_eq!(score.taper(MAX_PHASE), 100);
+        assert_eq!(score.taper(0), 200);
+        assert_eq!(score.taper(MAX_PHASE / 2), 150);
+        // Promotions can push the phase past the starting position's
+        assert_eq!(score.taper(MAX_PHASE + 4), 100);
+    }
+}
@@ -20,6 +20,7 @@ pub enum OxideIllegalMoveError {
     MovingPieceForWrongSide, // Moving a piece for the side that isn't currently supposed to move
     CapturingOwnPiece, // Move onto square with the same side
     NonCapturingCapture, // Piece being moved was expecting to capture but targeted an empty square
+    CapturingNonCapture, // Move wasn't classified as a capture but targeted an enemy piece
     MovingFromEmptySquare, // Piece being moved doesn't exist
     CastlingWithoutPermission, // Castle without permissions
     CastlingThroughAttack, // Castle path is attacked
@@ -40,6 +41,7 @@ impl Display for OxideIllegalMoveError {
             OxideIllegalMoveError::MovingPieceForWrongSide => write!(f, "Attempting to move piece for the wrong side"),
             OxideIllegalMoveError::CapturingOwnPiece => write!(f, "Attempting to capture one's own piece"),
             OxideIllegalMoveError::NonCapturingCapture => write!(f, "Move was classified as a capture but didn't capture"),
+            OxideIllegalMoveError::CapturingNonCapture => write!(f, "Move wasn't classified as a capture but captured"),
             OxideIllegalMoveError::MovingFromEmptySquare => write!(f, "Missing piece on from square"),
             OxideIllegalMoveError::CastlingWithoutPermission => write!(f, "Castling without permission"),
             OxideIllegalMoveError::CastlingThroughAttack => write!(f, "Castling path travels through an attacked square"),
@@ -85,6 +87,30 @@ impl Display for OxideMove {
     }
 }
 
+impl OxideMove {
+    /// Pack a move into 16 bits (6 bits origin, 6 bits destination, 4 bits move type) for compact storage
+    #[inline]
+    pub fn to_packed(self) -> u16 {
+        let OxideSimpleMove { from, to } = self.simple_move;
+        from.offset() as u16 | (to.offset() as u16) << 6 | (self.move_type as u16) << 12
+    }
+    /// Unpack a move previously packed with `to_packed` (None if the bits don't describe a move)
+    #[inline]
+    pub fn from_packed(packed: u16) -> Option<Self> {
+        let from = OxideSquare::from_offset((packed & 0x3F) as u8)?;
+        let to = OxideSquare::from_offset((packed >> 6 & 0x3F) as u8)?;
+        let move_type = OxideMoveType::from_bits((packed >> 12) as u8)?;
+        if from == to {
+            return None;
+        }
+
+        Some(Self {
+            simple_move: OxideSimpleMove { from, to },
+            move_type,
+        })
+    }
+}
+
 impl SimpleChessMove<OxidePosition> for OxideMove {
     #[inline]
     fn new(from: OxideSquare, to: OxideSquare) -> Self {
@@ -23,3 +23,27 @@ pub enum OxideMoveType {
     RookPromotingCapture,
     QueenPromotingCapture = 15,
 }
+
+impl OxideMoveType {
+    /// Get a move type from its discriminant (None for the unused discriminants 6 and 7 or anything above 15)
+    #[inline]
+    pub(crate) fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(OxideMoveType::Quiet),
+            1 => Some(OxideMoveType::DoublePawnPush),
+            2 => Some(OxideMoveType::KingSideCastle),
+            3 => Some(OxideMoveType::QueenSideCastle),
+            4 => Some(OxideMoveType::Capture),
+            5 => Some(OxideMoveType::EnPassantCapture),
+            8 => Some(OxideMoveType::KnightPromotion),
+            9 => Some(OxideMoveType::BishopPromotion),
+            10 => Some(OxideMoveType::RookPromotion),
+            11 => Some(OxideMoveType::QueenPromotion),
+            12 => Some(OxideMoveType::KnightPromotingCapture),
+            13 => Some(OxideMoveType::BishopPromotingCapture),
+            14 => Some(OxideMoveType::RookPromotingCapture),
+            15 => Some(OxideMoveType::QueenPromotingCapture),
+            _ => None,
+        }
+    }
+}
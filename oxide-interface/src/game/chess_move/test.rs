@@ -303,4 +303,29 @@ mod test {
         assert_eq!(OxideMove::WHITE_QUEEN_CASTLE.to(), C1);
         assert_eq!(OxideMove::BLACK_QUEEN_CASTLE.to(), C8);
     }
-}
+
+    #[test]
+    fn packing_round_trips() {
+        let moves = [
+            OxideMove::new(A2, A3),
+            OxideMove::new_double_pawn_push(E2, E4),
+            OxideMove::new_capture(D7, E6),
+            OxideMove::new_en_passant_capture(B5, A6),
+            OxideMove::new_promotion(H2, H1, OxidePiece::Knight),
+            OxideMove::new_promoting_capture(E7, F8, OxidePiece::Queen),
+            OxideMove::WHITE_KING_CASTLE,
+            OxideMove::BLACK_QUEEN_CASTLE,
+        ];
+        for &chess_move in &moves {
+            assert_eq!(OxideMove::from_packed(chess_move.to_packed()), Some(chess_move));
+        }
+    }
+
+    #[test]
+    fn unpacking_invalid_bits_fails() {
+        // Same origin and destination
+        assert_eq!(OxideMove::from_packed(0), None);
+        // Unused move type discriminant
+        assert_eq!(OxideMove::from_packed(OxideMove::new(A2, A3).to_packed() | 6 << 12), None);
+    }
+}
\ No newline at end of file
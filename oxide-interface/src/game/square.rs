@@ -54,7 +54,7 @@ impl TryFrom<&str> for OxideSquare {
         const FILES: [char; 8] = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'];
         const RANKS: [char; 8] = ['1', '2', '3', '4', '5', '6', '7', '8'];
         let mut chars = value.chars().take(2);
-        let file = chars.next().ok_or(OxideFenParseError::InvalidEnPassantSquare)?;
+        let file = chars.next().ok_or(OxideFenParseError::InvalidEnPassantSquare)?.to_ascii_uppercase();
         let rank = chars.next().ok_or(OxideFenParseError::InvalidEnPassantSquare)?;
         let x_offset = FILES.iter().position(|&f| f == file).ok_or(OxideFenParseError::InvalidEnPassantSquare)?;
         let y_offset = RANKS.iter().position(|&r| r == rank).ok_or(OxideFenParseError::InvalidEnPassantSquare)?;
@@ -0,0 +1,85 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::time::{Duration, Instant};
+use oxide_interface::engine::{OxideBoard, OxidePosition, OxideScore};
+use interface::engine::{Board, Evaluator};
+use interface::game::Position;
+use crate::smp::ThreadPool;
+use crate::limits::SearchLimits;
+use crate::types::Depth;
+
+/// Depth each bench position is searched to by default
+pub const DEFAULT_BENCH_DEPTH: Depth = 8;
+
+/// Openings, middlegames and endgames searched by the bench command to track node count regressions
+pub const BENCH_POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP2BPPP/R2Q1RK1 w - - 0 10",
+    "r1bq1rk1/pp3ppp/2nbpn2/3p4/2PP4/1PN1PN2/P4PPP/R1BQKB1R w KQ - 1 8",
+    "2rq1rk1/pb1nbppp/1p2pn2/2pp4/2PP4/1P2PN2/PBQNBPPP/R4RK1 w - - 0 12",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/8/4k3/8/2p5/8/B2K4/8 w - - 0 1",
+    "8/5pk1/6p1/8/8/6P1/5PK1/8 w - - 0 1",
+    "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+];
+
+/// Total work done by a bench run
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BenchResult {
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    /// Search speed over the whole run
+    pub fn nodes_per_second(&self) -> u64 {
+        let elapsed_micros = self.elapsed.as_micros().max(1);
+
+        (self.nodes as u128 * 1_000_000 / elapsed_micros) as u64
+    }
+}
+
+impl Display for BenchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        write!(f, "{} nodes {} nps", self.nodes, self.nodes_per_second())
+    }
+}
+
+/// Search every bench position to a fixed depth from an empty table (node counts are only deterministic with a single thread)
+pub fn bench<E: Evaluator<OxidePosition, Score = OxideScore> + Clone + Send + 'static>(pool: &mut ThreadPool<E>, depth: Depth) -> BenchResult {
+    let start = Instant::now();
+    let nodes = BENCH_POSITIONS.iter()
+        .map(|fen| {
+            let position = OxidePosition::from_fen(fen).expect("Bench positions should be valid");
+            pool.clear_hash();
+            pool.search(&OxideBoard::new(position), &SearchLimits::depth(depth)).nodes
+        })
+        .sum();
+
+    BenchResult {
+        nodes,
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bench_positions_parse() {
+        for fen in BENCH_POSITIONS {
+            assert!(OxidePosition::from_fen(fen).is_ok(), "Failed to parse bench position {}", fen);
+        }
+    }
+
+    #[test]
+    fn nodes_per_second_works() {
+        let result = BenchResult { nodes: 3_000, elapsed: Duration::from_millis(1_500) };
+        assert_eq!(result.nodes_per_second(), 2_000);
+        assert_eq!(result.to_string(), "3000 nodes 2000 nps");
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::error::Error;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::thread::{self, JoinHandle};
+use oxide_interface::engine::{OxideBoard, OxidePosition, OxideScore, OxideFenParseError, OxideKeyHistory};
+use interface::engine::{Board, Evaluator};
+use interface::game::Position;
+use crate::options::{SearchOptions, SearchOptionError};
+use crate::limits::SearchLimits;
+use crate::result::SearchResult;
+use crate::info::SearchListener;
+use crate::notation::parse_move;
+use crate::smp::ThreadPool;
+
+/// The standard starting position
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EngineError {
+    InvalidFen(OxideFenParseError), // Position couldn't be parsed
+    IllegalMove(String), // Move isn't legal (or isn't a move) in the position it's played from
+    InvalidOption(SearchOptionError), // Option was rejected by the search
+    Searching, // Request can't be handled until the running search finishes
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            EngineError::InvalidFen(error) => write!(f, "Invalid FEN ({:?})", error),
+            EngineError::IllegalMove(text) => write!(f, "Illegal move {}", text),
+            EngineError::InvalidOption(error) => write!(f, "{}", error),
+            EngineError::Searching => write!(f, "Engine is searching"),
+        }
+    }
+}
+
+impl Error for EngineError {}
+
+// Work handed to the thread that owns the thread pool, processed in order
+enum Command {
+    Search(OxideBoard, OxideKeyHistory, SearchLimits),
+    SetOption(String, String, Sender<Result<(), SearchOptionError>>),
+    ClearHash,
+    SetListener(Option<Arc<dyn SearchListener>>),
+}
+
+// What callers waiting on a search can see
+#[derive(Default)]
+struct SearchState {
+    searching: bool,
+    result: Option<SearchResult>,
+}
+
+/// Embeddable engine that searches on a background thread, every method can be called from any thread
+pub struct Engine {
+    // Position the next search starts from, with the game's positions leading to it
+    game: Mutex<(OxideBoard, OxideKeyHistory)>,
+    commands: Mutex<Sender<Command>>,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+    state: Arc<(Mutex<SearchState>, Condvar)>,
+    controller: Option<JoinHandle<()>>,
+}
+
+impl Engine {
+    /// Start an engine's threads (kept for every search until the engine is dropped), set up at the starting position
+    pub fn new<E: Evaluator<OxidePosition, Score = OxideScore> + Clone + Send + 'static>(options: SearchOptions, evaluator: E) -> Self {
+        let mut pool = ThreadPool::new(options, evaluator);
+        let stop = pool.stop_flag();
+        let pondering = pool.ponder_flag();
+        let state = Arc::new((Mutex::new(SearchState::default()), Condvar::new()));
+        let (commands, command_receiver) = channel();
+
+        let controller_state = state.clone();
+        let controller = thread::spawn(move || {
+            for command in command_receiver {
+                match command {
+                    Command::Search(board, history, limits) => {
+                        pool.set_history(history);
+                        let result = pool.search(&board, &limits);
+                        let (lock, finished) = &*controller_state;
+                        let mut state = lock.lock().expect("Search state lock poisoned");
+                        state.searching = false;
+                        state.result = Some(result);
+                        finished.notify_all();
+                    },
+                    Command::SetOption(name, value, reply) => {
+                        let _ = reply.send(pool.set_option(&name, &value));
+                    },
+                    Command::ClearHash => pool.clear_hash(),
+                    Command::SetListener(listener) => pool.set_listener(listener),
+                }
+            }
+        });
+
+        let position = OxidePosition::from_fen(START_FEN).expect("Start position should be valid");
+        Self {
+            game: Mutex::new((OxideBoard::new(position), OxideKeyHistory::new(&position))),
+            commands: Mutex::new(commands),
+            stop,
+            pondering,
+            state,
+            controller: Some(controller),
+        }
+    }
+
+    /// The position the next search starts from
+    pub fn board(&self) -> OxideBoard {
+        self.game.lock().expect("Game lock poisoned").0
+    }
+
+    /// Set the position the next search starts from, a FEN followed by moves in long algebraic notation
+    pub fn set_position(&self, fen: &str, moves: &[&str]) -> Result<(), EngineError> {
+        let position = OxidePosition::from_fen(fen).map_err(EngineError::InvalidFen)?;
+        let mut board = OxideBoard::new(position);
+        let mut history = OxideKeyHistory::new(&position);
+        for &text in moves {
+            let chess_move = parse_move(&board, text).ok_or_else(|| EngineError::IllegalMove(text.to_string()))?;
+            board.make_move_unchecked(chess_move);
+            history.push(board.position());
+        }
+
+        *self.game.lock().expect("Game lock poisoned") = (board, history);
+        Ok(())
+    }
+
+    /// Set a search option by its UCI name
+    pub fn set_option(&self, name: &str, value: &str) -> Result<(), EngineError> {
+        let (reply, result) = channel();
+        self.send_when_idle(Command::SetOption(name.to_string(), value.to_string(), reply))?;
+
+        result.recv()
+            .expect("Engine thread stopped")
+            .map_err(EngineError::InvalidOption)
+    }
+
+    /// Empty the transposition table
+    pub fn clear_hash(&self) -> Result<(), EngineError> {
+        self.send_when_idle(Command::ClearHash)
+    }
+
+    /// Receive progress events from future searches (or stop receiving them with None)
+    pub fn set_listener(&self, listener: Option<Arc<dyn SearchListener>>) -> Result<(), EngineError> {
+        self.send_when_idle(Command::SetListener(listener))
+    }
+
+    /// Start searching the current position in the background
+    pub fn go(&self, limits: SearchLimits) -> Result<(), EngineError> {
+        self.start(limits, false)
+    }
+
+    /// Start searching in the background while the opponent thinks, time limits only apply after `ponderhit`
+    pub fn ponder(&self, limits: SearchLimits) -> Result<(), EngineError> {
+        self.start(limits, true)
+    }
+
+    /// The opponent played the expected move, the pondering search continues as a normal search
+    pub fn ponderhit(&self) {
+        let state = self.state.0.lock().expect("Search state lock poisoned");
+        if state.searching {
+            self.pondering.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Stop the running search as soon as possible, its result is available through `wait`
+    pub fn stop(&self) {
+        let state = self.state.0.lock().expect("Search state lock poisoned");
+        if state.searching {
+            self.pondering.store(false, Ordering::Relaxed);
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// If a search is running
+    pub fn is_searching(&self) -> bool {
+        self.state.0.lock().expect("Search state lock poisoned").searching
+    }
+
+    /// Block until the running search finishes, returning the latest search's result (None if nothing was searched yet)
+    pub fn wait(&self) -> Option<SearchResult> {
+        let (lock, finished) = &*self.state;
+        let mut state = lock.lock().expect("Search state lock poisoned");
+        while state.searching {
+            state = finished.wait(state).expect("Search state lock poisoned");
+        }
+
+        state.result.clone()
+    }
+
+    fn start(&self, limits: SearchLimits, ponder: bool) -> Result<(), EngineError> {
+        let (board, history) = self.game.lock().expect("Game lock poisoned").clone();
+        let mut state = self.state.0.lock().expect("Search state lock poisoned");
+        if state.searching {
+            return Err(EngineError::Searching);
+        }
+
+        // Flags are only changed with the state locked so a stop or ponderhit can't be lost before the search starts
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(ponder, Ordering::Relaxed);
+        state.searching = true;
+        self.send(Command::Search(board, history, limits));
+        Ok(())
+    }
+
+    fn send_when_idle(&self, command: Command) -> Result<(), EngineError> {
+        if self.is_searching() {
+            return Err(EngineError::Searching);
+        }
+
+        self.send(command);
+        Ok(())
+    }
+
+    fn send(&self, command: Command) {
+        self.commands.lock()
+            .expect("Command lock poisoned")
+            .send(command)
+            .expect("Engine thread stopped");
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop();
+        // Replacing the sender closes the command channel, ending the engine thread once it finishes its queue
+        if let Ok(commands) = self.commands.get_mut() {
+            *commands = channel().0;
+        }
+        if let Some(controller) = self.controller.take() {
+            let _ = controller.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use oxide_interface::game::OxideMove;
+    use move_gen::legal_moves;
+
+    // Long enough for a search that should keep running to have finished if it wrongly didn't
+    const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+    #[derive(Copy, Clone)]
+    struct DrawEvaluator;
+
+    impl Evaluator<OxidePosition> for DrawEvaluator {
+        type Score = OxideScore;
+
+        fn evaluate(&mut self, _: &OxidePosition) -> OxideScore {
+            OxideScore::default()
+        }
+
+        fn set_option(&mut self, name: &str, value: &str) -> Result<bool, String> {
+            match (name, value) {
+                ("Draw Score", "0") => Ok(true),
+                ("Draw Score", _) => Err("Draws are always 0".to_string()),
+                _ => Ok(false),
+            }
+        }
+    }
+
+    fn engine() -> Engine {
+        Engine::new(SearchOptions { hash_megabytes: 1, ..SearchOptions::default() }, DrawEvaluator)
+    }
+
+    #[test]
+    fn set_position_works() {
+        let engine = engine();
+        assert_eq!(engine.set_position("4k3/8/8/8/8/8/8/4K2R w K - 0 1", &[]), Ok(()));
+        assert_eq!(engine.board().position().castle_rights(), OxidePosition::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap().castle_rights());
+        assert_eq!(engine.set_position(START_FEN, &["e2e5"]), Err(EngineError::IllegalMove("e2e5".to_string())));
+        assert!(matches!(engine.set_position("", &[]), Err(EngineError::InvalidFen(_))));
+    }
+
+    #[test]
+    fn set_option_works() {
+        let engine = engine();
+        assert_eq!(engine.set_option("Threads", "2"), Ok(()));
+        assert_eq!(engine.set_option("Hash", "2"), Ok(()));
+        assert_eq!(engine.set_option("Contempt", "2"), Err(EngineError::InvalidOption(SearchOptionError::UnknownOption("Contempt".to_string()))));
+        // Options the search doesn't have are passed to the evaluator
+        assert_eq!(engine.set_option("Draw Score", "0"), Ok(()));
+        let rejected = SearchOptionError::InvalidEvaluatorValue("Draw Score".to_string(), "Draws are always 0".to_string());
+        assert_eq!(engine.set_option("Draw Score", "1"), Err(EngineError::InvalidOption(rejected)));
+        assert_eq!(engine.clear_hash(), Ok(()));
+    }
+
+    #[test]
+    fn engine_is_thread_safe() {
+        fn assert_thread_safe<T: Send + Sync>() {}
+        assert_thread_safe::<Engine>();
+    }
+
+    #[test]
+    fn idle_engine_works() {
+        let engine = engine();
+        assert!(!engine.is_searching());
+        assert!(engine.wait().is_none());
+        // Stopping or a ponderhit without a search does nothing
+        engine.stop();
+        engine.ponderhit();
+        assert!(!engine.stop.load(Ordering::Relaxed));
+    }
+
+    fn is_legal(engine: &Engine, chess_move: Option<OxideMove>) -> bool {
+        let board = engine.board();
+        chess_move.map_or(false, |chess_move| legal_moves::<OxidePosition, OxideBoard>(&board).any(|legal_move| legal_move == chess_move))
+    }
+
+    #[test]
+    fn set_position_with_moves_works() {
+        let engine = engine();
+        assert_eq!(engine.set_position(START_FEN, &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"]), Ok(()));
+        assert_eq!(engine.board().position().to_fen(), "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4");
+        // En-passant and promotion
+        assert_eq!(engine.set_position("4k3/1P6/8/8/3p4/8/4P3/4K3 w - - 0 1", &["e2e4", "d4e3", "b7b8q"]), Ok(()));
+        assert_eq!(engine.board().position().to_fen(), "1Q2k3/8/8/8/8/4p3/8/4K3 b - - 0 2");
+        // A failed position leaves the previous one
+        assert_eq!(engine.set_position(START_FEN, &["e2e4", "e2e4"]), Err(EngineError::IllegalMove("e2e4".to_string())));
+        assert_eq!(engine.board().position().to_fen(), "1Q2k3/8/8/8/8/4p3/8/4K3 b - - 0 2");
+    }
+
+    #[test]
+    fn set_position_keeps_the_game_history() {
+        let engine = engine();
+        let history = || engine.game.lock().unwrap().1.clone();
+        assert_eq!(engine.set_position(START_FEN, &["g1f3", "g8f6", "f3g1"]), Ok(()));
+        assert!(!history().is_repetition(2));
+        // Back at the start position, so the search sees going there again as a draw
+        assert_eq!(engine.set_position(START_FEN, &["g1f3", "g8f6", "f3g1", "f6g8"]), Ok(()));
+        assert!(history().is_repetition(2));
+        assert_eq!(history().last_key(), Some(engine.board().position().zobrist_key()));
+    }
+
+    #[test]
+    fn go_and_wait_works() {
+        let engine = engine();
+        engine.set_position(START_FEN, &["e2e4"]).unwrap();
+        engine.go(SearchLimits::depth(3)).unwrap();
+        let result = engine.wait().unwrap();
+        assert!(!engine.is_searching());
+        assert_eq!(result.depth, 3);
+        assert!(is_legal(&engine, result.best_move));
+        // The result stays available until the next search
+        assert_eq!(engine.wait(), Some(result));
+    }
+
+    #[test]
+    fn stop_during_search_works() {
+        let engine = engine();
+        engine.go(SearchLimits { infinite: true, ..SearchLimits::default() }).unwrap();
+        thread::sleep(SETTLE_TIME);
+        assert!(engine.is_searching());
+        // Only stopping is allowed while searching
+        assert_eq!(engine.go(SearchLimits::depth(1)), Err(EngineError::Searching));
+        assert_eq!(engine.set_option("Hash", "2"), Err(EngineError::Searching));
+        assert_eq!(engine.clear_hash(), Err(EngineError::Searching));
+        engine.stop();
+        let result = engine.wait().unwrap();
+        assert!(!engine.is_searching());
+        assert!(is_legal(&engine, result.best_move));
+        // A stopped search doesn't stop the next one
+        engine.go(SearchLimits::depth(2)).unwrap();
+        assert_eq!(engine.wait().unwrap().depth, 2);
+    }
+
+    #[test]
+    fn ponder_and_ponderhit_works() {
+        let engine = engine();
+        engine.ponder(SearchLimits::depth(2)).unwrap();
+        // Pondering keeps going after the last depth until the opponent moves
+        thread::sleep(SETTLE_TIME);
+        assert!(engine.is_searching());
+        engine.ponderhit();
+        let result = engine.wait().unwrap();
+        assert_eq!(result.depth, 2);
+        assert!(is_legal(&engine, result.best_move));
+
+        // Or the opponent plays something else and the ponder search is stopped
+        engine.ponder(SearchLimits::depth(2)).unwrap();
+        thread::sleep(SETTLE_TIME);
+        assert!(engine.is_searching());
+        engine.stop();
+        assert!(engine.wait().is_some());
+        assert!(!engine.is_searching());
+    }
+
+    #[test]
+    fn helpers_are_reused_between_searches() {
+        let engine = engine();
+        engine.set_option("Threads", "3").unwrap();
+        for depth in 2..=4 {
+            engine.go(SearchLimits::depth(depth)).unwrap();
+            let result = engine.wait().unwrap();
+            assert!(result.depth >= depth);
+            assert!(is_legal(&engine, result.best_move));
+        }
+        // Shrinking and growing the pool between searches keeps it working
+        engine.set_option("Threads", "1").unwrap();
+        engine.set_option("Threads", "2").unwrap();
+        engine.go(SearchLimits { infinite: true, ..SearchLimits::default() }).unwrap();
+        engine.stop();
+        assert!(is_legal(&engine, engine.wait().unwrap().best_move));
+    }
+}
@@ -0,0 +1,182 @@
+use oxide_interface::game::{OxideMove, OxideSide, OxideSidedPiece, OxideSquare};
+use interface::game::{SimpleChessMove, Square};
+use crate::types::{Depth, MAX_PLY};
+
+const MAX_HISTORY: i32 = 1 << 14;
+const MAX_HISTORY_BONUS: i32 = 1200;
+const KILLER_SLOTS: usize = 2;
+const SIDED_PIECES: usize = 12;
+const SQUARES: usize = 64;
+const PIECE_TO: usize = SIDED_PIECES * SQUARES;
+
+/// The piece that moved and where it went, which is what continuation statistics are keyed on
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PieceTo {
+    pub piece: OxideSidedPiece,
+    pub to: OxideSquare,
+}
+
+impl PieceTo {
+    #[inline]
+    fn index(self) -> usize {
+        debug_assert_ne!(self.piece, OxideSidedPiece::Empty, "Attempting to index move statistics with an empty piece");
+        self.piece as usize * SQUARES + self.to.offset() as usize
+    }
+}
+
+#[inline]
+fn history_bonus(depth: Depth) -> i32 {
+    (32 * depth * depth).min(MAX_HISTORY_BONUS)
+}
+
+// Gravity update: scale the change down as the entry approaches the limit so old statistics decay
+#[inline]
+fn apply_gravity(entry: &mut i32, bonus: i32) {
+    *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+}
+
+/// Per-thread move ordering statistics (kept separate per thread so helpers diverge)
+#[derive(Clone)]
+pub struct Heuristics {
+    // Butterfly history indexed by side then from/to square offsets
+    history: Box<[[[i32; SQUARES]; SQUARES]; 2]>,
+    // Quiet moves that caused a beta cutoff by ply, most recent first
+    killers: Box<[[Option<OxideMove>; KILLER_SLOTS]; MAX_PLY]>,
+    // Refutations indexed by the previous move's piece and destination
+    countermoves: Box<[Option<OxideMove>]>,
+    // Indexed by a previous move's piece and destination then the current move's piece and destination
+    continuation_history: Box<[[i32; PIECE_TO]]>,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self {
+            history: Box::new([[[0; SQUARES]; SQUARES]; 2]),
+            killers: Box::new([[None; KILLER_SLOTS]; MAX_PLY]),
+            countermoves: vec![None; PIECE_TO].into_boxed_slice(),
+            // Allocated on the heap directly, it's far too large for a helper thread's stack
+            continuation_history: vec![[0; PIECE_TO]; PIECE_TO].into_boxed_slice(),
+        }
+    }
+}
+
+impl Heuristics {
+    /// Butterfly history score for a quiet move
+    #[inline]
+    pub fn history(&self, side: OxideSide, chess_move: OxideMove) -> i32 {
+        self.history[side as usize][chess_move.from().offset() as usize][chess_move.to().offset() as usize]
+    }
+
+    /// Killer moves for a ply, most recent first
+    #[inline]
+    pub fn killers(&self, ply: usize) -> [Option<OxideMove>; KILLER_SLOTS] {
+        self.killers[ply]
+    }
+
+    /// If a move caused a beta cutoff at the same ply in a sibling node
+    #[inline]
+    pub fn is_killer(&self, ply: usize, chess_move: OxideMove) -> bool {
+        self.killers[ply].contains(&Some(chess_move))
+    }
+
+    /// The quiet move that last refuted the previous move
+    #[inline]
+    pub fn countermove(&self, previous: Option<PieceTo>) -> Option<OxideMove> {
+        previous.and_then(|previous| self.countermoves[previous.index()])
+    }
+
+    /// How well a move has done after a given earlier move
+    #[inline]
+    pub fn continuation_history(&self, previous: Option<PieceTo>, current: PieceTo) -> i32 {
+        previous.map_or(0, |previous| self.continuation_history[previous.index()][current.index()])
+    }
+
+    /// Combined ordering score for a quiet move given the moves one and two plies earlier
+    #[inline]
+    pub fn quiet_score(&self, side: OxideSide, chess_move: OxideMove, current: PieceTo, previous: [Option<PieceTo>; 2]) -> i32 {
+        self.history(side, chess_move)
+            + self.continuation_history(previous[0], current)
+            + self.continuation_history(previous[1], current)
+    }
+
+    fn update_quiet(&mut self, side: OxideSide, chess_move: OxideMove, current: PieceTo, previous: [Option<PieceTo>; 2], bonus: i32) {
+        apply_gravity(&mut self.history[side as usize][chess_move.from().offset() as usize][chess_move.to().offset() as usize], bonus);
+        for previous in previous.iter().flatten() {
+            apply_gravity(&mut self.continuation_history[previous.index()][current.index()], bonus);
+        }
+    }
+
+    /// Reward a quiet move that caused a beta cutoff and penalize the quiet moves searched before it
+    pub fn update_quiet_cutoff(&mut self, side: OxideSide, best: (OxideMove, PieceTo), searched_quiets: &[(OxideMove, PieceTo)], previous: [Option<PieceTo>; 2], depth: Depth, ply: usize) {
+        let bonus = history_bonus(depth);
+        let (best_move, best_piece_to) = best;
+        self.update_quiet(side, best_move, best_piece_to, previous, bonus);
+        for &(chess_move, piece_to) in searched_quiets.iter().filter(|&&(m, _)| m != best_move) {
+            self.update_quiet(side, chess_move, piece_to, previous, -bonus);
+        }
+
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(best_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(best_move);
+        }
+
+        if let Some(previous) = previous[0] {
+            self.countermoves[previous.index()] = Some(best_move);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oxide_interface::game::OxideSquare::*;
+
+    const PREVIOUS: PieceTo = PieceTo { piece: OxideSidedPiece::BlackPawn, to: E5 };
+
+    fn knight_out() -> (OxideMove, PieceTo) {
+        (OxideMove::new(G1, F3), PieceTo { piece: OxideSidedPiece::WhiteKnight, to: F3 })
+    }
+
+    fn bishop_out() -> (OxideMove, PieceTo) {
+        (OxideMove::new(F1, C4), PieceTo { piece: OxideSidedPiece::WhiteBishop, to: C4 })
+    }
+
+    #[test]
+    fn update_quiet_cutoff_works() {
+        let (knight_out, bishop_out) = (knight_out(), bishop_out());
+        let mut heuristics = Heuristics::default();
+        heuristics.update_quiet_cutoff(OxideSide::White, knight_out, &[bishop_out, knight_out], [Some(PREVIOUS), None], 4, 3);
+        assert!(heuristics.history(OxideSide::White, knight_out.0) > 0);
+        assert!(heuristics.history(OxideSide::White, bishop_out.0) < 0);
+        assert_eq!(heuristics.history(OxideSide::Black, knight_out.0), 0);
+        assert!(heuristics.continuation_history(Some(PREVIOUS), knight_out.1) > 0);
+        assert!(heuristics.continuation_history(Some(PREVIOUS), bishop_out.1) < 0);
+        assert_eq!(heuristics.continuation_history(None, knight_out.1), 0);
+        assert_eq!(heuristics.countermove(Some(PREVIOUS)), Some(knight_out.0));
+        assert!(heuristics.is_killer(3, knight_out.0));
+        assert!(!heuristics.is_killer(2, knight_out.0));
+    }
+
+    #[test]
+    fn killers_shift_works() {
+        let (knight_out, bishop_out) = (knight_out(), bishop_out());
+        let mut heuristics = Heuristics::default();
+        heuristics.update_quiet_cutoff(OxideSide::White, knight_out, &[], [None, None], 2, 0);
+        heuristics.update_quiet_cutoff(OxideSide::White, knight_out, &[], [None, None], 2, 0);
+        assert_eq!(heuristics.killers(0), [Some(knight_out.0), None]);
+        heuristics.update_quiet_cutoff(OxideSide::White, bishop_out, &[], [None, None], 2, 0);
+        assert_eq!(heuristics.killers(0), [Some(bishop_out.0), Some(knight_out.0)]);
+    }
+
+    #[test]
+    fn history_gravity_is_bounded() {
+        let knight_out = knight_out();
+        let mut heuristics = Heuristics::default();
+        for _ in 0..10_000 {
+            heuristics.update_quiet_cutoff(OxideSide::White, knight_out, &[], [None, None], 40, 0);
+        }
+        let history = heuristics.history(OxideSide::White, knight_out.0);
+        assert!(history > 0 && history <= MAX_HISTORY);
+    }
+}
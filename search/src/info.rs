@@ -0,0 +1,119 @@
+use std::sync::Mutex;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use oxide_interface::engine::OxideScore;
+use oxide_interface::game::OxideMove;
+use crate::transposition::Bound;
+use crate::types::Depth;
+
+/// A completed iteration's result for one MultiPV line, or a bound on it when it failed outside its aspiration window
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IterationInfo {
+    /// Iteration depth
+    pub depth: Depth,
+    /// Deepest ply any line of the iteration reached
+    pub sel_depth: usize,
+    /// Which line this is, starting at 1 for the principal variation
+    pub multi_pv: usize,
+    /// Score of the line from the side to move's perspective
+    pub score: OxideScore,
+    /// If the score is exact or only a bound
+    pub bound: Bound,
+    /// Nodes searched so far across every thread
+    pub nodes: u64,
+    /// Search speed so far
+    pub nodes_per_second: u64,
+    /// Time since the search started
+    pub time: Duration,
+    /// How full the transposition table is in permille
+    pub hashfull: usize,
+    /// The line starting with its root move
+    pub pv: Vec<OxideMove>,
+}
+
+/// Progress reported while searching
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SearchEvent {
+    /// An iteration completed, one event per MultiPV line (and one per aspiration fail before it)
+    Iteration(IterationInfo),
+    /// The main thread started searching a root move (only reported once the search has run for a while)
+    CurrentMove {
+        depth: Depth,
+        chess_move: OxideMove,
+        /// Position in the root move order starting at 1
+        move_number: usize,
+    },
+    /// The search finished, with the reply expected to the best move to ponder on
+    BestMove {
+        best_move: Option<OxideMove>,
+        ponder_move: Option<OxideMove>,
+    },
+}
+
+/// Receiver of search events, called from the main search thread so it should return quickly
+pub trait SearchListener: Send + Sync {
+    fn on_event(&self, event: &SearchEvent);
+}
+
+impl<F: Fn(&SearchEvent) + Send + Sync> SearchListener for F {
+    #[inline]
+    fn on_event(&self, event: &SearchEvent) {
+        self(event)
+    }
+}
+
+/// Forwards search events over a channel
+pub struct ChannelListener {
+    sender: Mutex<Sender<SearchEvent>>,
+}
+
+impl ChannelListener {
+    pub fn new(sender: Sender<SearchEvent>) -> Self {
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+}
+
+impl SearchListener for ChannelListener {
+    fn on_event(&self, event: &SearchEvent) {
+        // Nobody listening anymore isn't the search's problem
+        if let Ok(sender) = self.sender.lock() {
+            let _ = sender.send(event.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use interface::game::SimpleChessMove;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn best_move() -> SearchEvent {
+        SearchEvent::BestMove { best_move: Some(OxideMove::new(G1, F3)), ponder_move: None }
+    }
+
+    #[test]
+    fn channel_listener_works() {
+        let (sender, receiver) = channel();
+        let listener = ChannelListener::new(sender);
+        listener.on_event(&best_move());
+        assert_eq!(receiver.try_recv(), Ok(best_move()));
+        drop(receiver);
+        listener.on_event(&best_move());
+    }
+
+    #[test]
+    fn closure_listener_works() {
+        let count = AtomicUsize::new(0);
+        let listener = |_: &SearchEvent| {
+            count.fetch_add(1, Ordering::Relaxed);
+        };
+        listener.on_event(&best_move());
+        listener.on_event(&best_move());
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+    }
+}
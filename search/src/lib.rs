@@ -0,0 +1,31 @@
+mod types;
+mod transposition;
+mod options;
+mod heuristics;
+mod parameters;
+mod reductions;
+mod see;
+mod move_picker;
+mod pv;
+mod result;
+mod thread;
+mod smp;
+mod bench;
+mod time;
+mod limits;
+mod info;
+mod notation;
+mod engine;
+
+pub use types::{Depth, MAX_PLY};
+pub use transposition::{TranspositionTable, TranspositionEntry, Bound, DEFAULT_HASH_MEGABYTES};
+pub use options::{SearchOptions, SearchOptionError, MAX_THREADS, MAX_HASH_MEGABYTES, MAX_MOVE_OVERHEAD_MILLISECONDS, DEFAULT_MOVE_OVERHEAD_MILLISECONDS, MAX_MULTI_PV};
+pub use parameters::SearchParameters;
+pub use result::{SearchResult, SearchLine};
+pub use smp::ThreadPool;
+pub use time::{Clock, SystemClock, TimeControl, TimeManager};
+pub use limits::SearchLimits;
+pub use info::{SearchEvent, SearchListener, ChannelListener, IterationInfo};
+pub use notation::{format_move, parse_move};
+pub use engine::{Engine, EngineError, START_FEN};
+pub use bench::{bench, BenchResult, BENCH_POSITIONS, DEFAULT_BENCH_DEPTH};
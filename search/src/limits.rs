@@ -0,0 +1,104 @@
+use oxide_interface::engine::OxideScore;
+use oxide_interface::game::OxideMove;
+use interface::engine::PositionalScore;
+use crate::time::TimeControl;
+use crate::types::{Depth, MAX_PLY};
+
+/// When a search should stop and which root moves it may consider
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SearchLimits {
+    /// Clock state from the GUI, ignored in infinite mode
+    pub time: TimeControl,
+    /// Deepest iteration to search
+    pub depth: Option<Depth>,
+    /// Exact number of nodes to search (node limited searches only run on one thread so they're deterministic)
+    pub nodes: Option<u64>,
+    /// Stop once a mate in this many moves or fewer is found
+    pub mate: Option<u32>,
+    /// Only consider these root moves (all legal moves when empty)
+    pub search_moves: Vec<OxideMove>,
+    /// Search until stopped
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    /// Limits searching to a fixed depth
+    pub fn depth(depth: Depth) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    /// Limits searching to an exact node count
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    /// Deepest iteration allowed
+    #[inline]
+    pub fn max_depth(&self) -> Depth {
+        let max_depth = MAX_PLY as Depth - 1;
+
+        if self.infinite {
+            max_depth
+        } else {
+            self.depth.map_or(max_depth, |depth| depth.max(1).min(max_depth))
+        }
+    }
+
+    /// If a root score completes a mate search
+    #[inline]
+    pub fn is_mate_found(&self, score: OxideScore) -> bool {
+        match (self.mate, score.mate_in()) {
+            (Some(moves), Some(plies)) => (plies as u32 + 1) / 2 <= moves,
+            _ => false,
+        }
+    }
+
+    /// If a root move may be searched
+    #[inline]
+    pub fn allows_root_move(&self, chess_move: OxideMove) -> bool {
+        self.search_moves.is_empty() || self.search_moves.contains(&chess_move)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::{ChessMove, SimpleChessMove};
+    use oxide_interface::game::OxideSquare::*;
+
+    #[test]
+    fn max_depth_works() {
+        assert_eq!(SearchLimits::default().max_depth(), MAX_PLY as Depth - 1);
+        assert_eq!(SearchLimits::depth(7).max_depth(), 7);
+        assert_eq!(SearchLimits::depth(0).max_depth(), 1);
+        assert_eq!(SearchLimits::depth(1_000).max_depth(), MAX_PLY as Depth - 1);
+        assert_eq!(SearchLimits { infinite: true, ..SearchLimits::depth(7) }.max_depth(), MAX_PLY as Depth - 1);
+    }
+
+    #[test]
+    fn is_mate_found_works() {
+        let mate_in_two = SearchLimits { mate: Some(2), ..SearchLimits::default() };
+        assert!(mate_in_two.is_mate_found(OxideScore::new_mate(1)));
+        assert!(mate_in_two.is_mate_found(OxideScore::new_mate(3)));
+        assert!(!mate_in_two.is_mate_found(OxideScore::new_mate(5)));
+        assert!(!mate_in_two.is_mate_found(OxideScore::new_mated(2)));
+        assert!(!mate_in_two.is_mate_found(OxideScore::new(900)));
+        assert!(!SearchLimits::default().is_mate_found(OxideScore::new_mate(1)));
+    }
+
+    #[test]
+    fn allows_root_move_works() {
+        let e4 = OxideMove::new_double_pawn_push(E2, E4);
+        let knight = OxideMove::new(G1, F3);
+        assert!(SearchLimits::default().allows_root_move(e4));
+        let only_e4 = SearchLimits { search_moves: vec![e4], ..SearchLimits::default() };
+        assert!(only_e4.allows_root_move(e4));
+        assert!(!only_e4.allows_root_move(knight));
+    }
+}
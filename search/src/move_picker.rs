@@ -0,0 +1,419 @@
+use oxide_interface::engine::{OxideBoard, OxidePosition};
+use oxide_interface::game::{OxideBitboard, OxideCastleRights, OxideMove, OxidePiece};
+use interface::engine::Board;
+use interface::game::{BoardMask, CastleRights, ChessMove, PieceArrangement, Position, Side, SimpleChessMove, Square, Piece};
+use attacks::{pawn_attacks, pawn_pushes, pseudo_attacks};
+use move_gen::{capture_moves, evasion_moves, quiet_moves};
+use crate::heuristics::{Heuristics, PieceTo};
+use crate::see::{captured_piece, exchange_value, see_ge};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Stage {
+    // Main search
+    TableMove,
+    GenerateCaptures,
+    GoodCaptures,
+    FirstKiller,
+    SecondKiller,
+    Countermove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    // In check
+    EvasionTableMove,
+    GenerateEvasions,
+    Evasions,
+    // Quiescence search and ProbCut
+    QuiescenceTableMove,
+    GenerateQuiescenceCaptures,
+    QuiescenceCaptures,
+    Done,
+}
+
+/// The piece and destination a move would have on the current board
+#[inline]
+pub(crate) fn piece_to(board: &OxideBoard, chess_move: OxideMove) -> PieceTo {
+    let position = board.position();
+    let piece = position.piece_on_square(chess_move.from());
+
+    PieceTo {
+        piece: <OxidePiece as Piece<OxidePosition>>::add_side(piece, position.side_to_move()),
+        to: chess_move.to(),
+    }
+}
+
+// Most valuable victim, least valuable attacker
+#[inline]
+fn capture_score(board: &OxideBoard, chess_move: OxideMove) -> i32 {
+    let victim_value = exchange_value(captured_piece(board, chess_move)) + exchange_value(chess_move.promotion());
+    let attacker = board.position().piece_on_square(chess_move.from());
+
+    victim_value * 8 - exchange_value(attacker) / 100
+}
+
+// Could the move be generated on this board (move-gen's moves or corrupted table/killer moves from another position)
+fn is_pseudo_legal(board: &OxideBoard, chess_move: OxideMove) -> bool {
+    let position = board.position();
+    let side = position.side_to_move();
+    if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+        let (castle, castle_move) = match (chess_move.is_king_castle(), side.is_white()) {
+            (true, true) => (OxideCastleRights::BOTH_KINGS, OxideMove::WHITE_KING_CASTLE),
+            (true, false) => (OxideCastleRights::BOTH_KINGS, OxideMove::BLACK_KING_CASTLE),
+            (false, true) => (OxideCastleRights::BOTH_QUEENS, OxideMove::WHITE_QUEEN_CASTLE),
+            (false, false) => (OxideCastleRights::BOTH_QUEENS, OxideMove::BLACK_QUEEN_CASTLE),
+        };
+        let castle = castle.for_side(side);
+
+        return chess_move == castle_move
+            && !board.in_check()
+            && position.castle_rights().contains(castle)
+            && castle.castle_path() & position.occupied() == OxideBitboard::EMPTY;
+    }
+
+    let from = chess_move.from();
+    let to = chess_move.to();
+    let from_mask = from.to_mask();
+    let to_mask = to.to_mask();
+    let piece = position.piece_on_square(from);
+    if position.side_on_square(from) != Some(side) || position.side_on_square(to) == Some(side) {
+        return false;
+    }
+
+    if chess_move.is_en_passant_capture() {
+        return piece == OxidePiece::Pawn
+            && position.en_passant_square() == Some(to)
+            && pawn_attacks::<OxidePosition>(from_mask, side) & to_mask != OxideBitboard::EMPTY;
+    }
+    if chess_move.is_capture() != (position.side_on_square(to) == Some(side.opposite_side())) {
+        return false;
+    }
+
+    if piece == OxidePiece::Pawn {
+        let last_rank = if side.is_white() { OxideBitboard::RANK_8 } else { OxideBitboard::RANK_1 };
+        if chess_move.is_promotion() != (to_mask & last_rank != OxideBitboard::EMPTY) {
+            return false;
+        }
+
+        let single_push = pawn_pushes::<OxidePosition>(from_mask, side) & position.empty();
+        if chess_move.is_capture() {
+            pawn_attacks::<OxidePosition>(from_mask, side) & to_mask != OxideBitboard::EMPTY
+        } else if chess_move.is_double_pawn_push() {
+            let start_rank = if side.is_white() { OxideBitboard::RANK_2 } else { OxideBitboard::RANK_7 };
+            from_mask & start_rank != OxideBitboard::EMPTY && pawn_pushes::<OxidePosition>(single_push, side) & position.empty() & to_mask != OxideBitboard::EMPTY
+        } else {
+            single_push & to_mask != OxideBitboard::EMPTY
+        }
+    } else {
+        !chess_move.is_promotion()
+            && !chess_move.is_double_pawn_push()
+            && pseudo_attacks::<OxidePosition>(piece, from, position.occupied()) & to_mask != OxideBitboard::EMPTY
+    }
+}
+
+/// Lazily yields moves in stages from most to least promising so most nodes never generate quiet moves
+pub(crate) struct MovePicker {
+    stage: Stage,
+    table_move: Option<OxideMove>,
+    killers: [Option<OxideMove>; 2],
+    countermove: Option<OxideMove>,
+    previous_moves: [Option<PieceTo>; 2],
+    skip_quiets: bool,
+    // Minimum exchange gain for captures in quiescence mode
+    capture_threshold: i32,
+    // Scored moves for the current stage, moves before `index` were already yielded
+    moves: Vec<(OxideMove, i32)>,
+    index: usize,
+    bad_captures: Vec<OxideMove>,
+}
+
+impl MovePicker {
+    fn with_stage(board: &OxideBoard, stage: Stage, table_move: Option<OxideMove>) -> Self {
+        Self {
+            stage,
+            table_move: table_move.filter(|&m| is_pseudo_legal(board, m)),
+            killers: [None; 2],
+            countermove: None,
+            previous_moves: [None; 2],
+            skip_quiets: false,
+            capture_threshold: 0,
+            moves: Vec::new(),
+            index: 0,
+            bad_captures: Vec::new(),
+        }
+    }
+
+    /// Picker for the main search, given the moves earlier in the line for ordering quiets
+    pub fn new(board: &OxideBoard, table_move: Option<OxideMove>, heuristics: &Heuristics, previous_moves: [Option<PieceTo>; 2], ply: usize) -> Self {
+        if board.in_check() {
+            return Self::with_stage(board, Stage::EvasionTableMove, table_move);
+        }
+
+        Self {
+            killers: heuristics.killers(ply),
+            countermove: heuristics.countermove(previous_moves[0]),
+            previous_moves,
+            ..Self::with_stage(board, Stage::TableMove, table_move)
+        }
+    }
+
+    /// Picker for quiescence search, yielding only winning or even captures (or every evasion when in check)
+    pub fn new_quiescence(board: &OxideBoard, table_move: Option<OxideMove>) -> Self {
+        if board.in_check() {
+            Self::with_stage(board, Stage::EvasionTableMove, table_move)
+        } else {
+            // Only tactical table moves are useful in quiescence
+            let table_move = table_move.filter(|m| m.is_capture() || m.is_promotion());
+            Self::with_stage(board, Stage::QuiescenceTableMove, table_move)
+        }
+    }
+
+    /// Picker for ProbCut, yielding only captures that win at least `threshold` by static exchange
+    pub fn new_probcut(board: &OxideBoard, table_move: Option<OxideMove>, threshold: i32) -> Self {
+        debug_assert!(!board.in_check(), "Attempting to pick ProbCut captures while in check");
+        let table_move = table_move.filter(|&m| m.is_capture() && is_pseudo_legal(board, m) && see_ge(board, m, threshold));
+
+        Self {
+            capture_threshold: threshold,
+            ..Self::with_stage(board, Stage::QuiescenceTableMove, table_move)
+        }
+    }
+
+    /// Stop yielding quiet moves (killers, countermove and quiets) for the rest of the node
+    #[inline]
+    pub fn skip_quiets(&mut self) {
+        self.skip_quiets = true;
+    }
+
+    #[inline]
+    fn is_special_quiet(&self, chess_move: OxideMove) -> bool {
+        self.killers.contains(&Some(chess_move)) || self.countermove == Some(chess_move)
+    }
+
+    // Select the best remaining move of the current stage (selection sort, most stages end with a cutoff before sorting everything)
+    fn select_best(&mut self) -> Option<OxideMove> {
+        if self.index >= self.moves.len() {
+            return None;
+        }
+
+        let best_index = (self.index..self.moves.len())
+            .max_by_key(|&i| self.moves[i].1)
+            .expect("Remaining moves should not be empty");
+        self.moves.swap(self.index, best_index);
+        self.index += 1;
+
+        Some(self.moves[self.index - 1].0)
+    }
+
+    fn set_moves(&mut self, moves: Vec<(OxideMove, i32)>) {
+        self.moves = moves;
+        self.index = 0;
+    }
+
+    /// Next move to search (None once exhausted), moves are pseudo-legal and filtered by `Board::is_legal`
+    pub fn next_move(&mut self, board: &OxideBoard, heuristics: &Heuristics) -> Option<OxideMove> {
+        loop {
+            let candidate = self.next_pseudo_legal(board, heuristics)?;
+            if board.is_legal(&candidate) {
+                return Some(candidate);
+            }
+        }
+    }
+
+    fn next_pseudo_legal(&mut self, board: &OxideBoard, heuristics: &Heuristics) -> Option<OxideMove> {
+        loop {
+            match self.stage {
+                Stage::TableMove | Stage::EvasionTableMove | Stage::QuiescenceTableMove => {
+                    self.stage = match self.stage {
+                        Stage::TableMove => Stage::GenerateCaptures,
+                        Stage::EvasionTableMove => Stage::GenerateEvasions,
+                        _ => Stage::GenerateQuiescenceCaptures,
+                    };
+                    if self.table_move.is_some() {
+                        return self.table_move;
+                    }
+                },
+                Stage::GenerateCaptures | Stage::GenerateQuiescenceCaptures => {
+                    let table_move = self.table_move;
+                    let moves = capture_moves::<OxidePosition, OxideBoard>(board)
+                        .filter(|&m| Some(m) != table_move)
+                        .map(|m| (m, capture_score(board, m)))
+                        .collect();
+                    self.set_moves(moves);
+                    self.stage = if self.stage == Stage::GenerateCaptures { Stage::GoodCaptures } else { Stage::QuiescenceCaptures };
+                },
+                Stage::GoodCaptures => {
+                    while let Some(chess_move) = self.select_best() {
+                        if see_ge(board, chess_move, 0) {
+                            return Some(chess_move);
+                        }
+                        self.bad_captures.push(chess_move);
+                    }
+                    self.stage = Stage::FirstKiller;
+                },
+                Stage::FirstKiller | Stage::SecondKiller | Stage::Countermove => {
+                    let special_quiet = match self.stage {
+                        Stage::FirstKiller => self.killers[0],
+                        Stage::SecondKiller => self.killers[1],
+                        _ => self.countermove.filter(|&m| !self.killers.contains(&Some(m))),
+                    };
+                    self.stage = match self.stage {
+                        Stage::FirstKiller => Stage::SecondKiller,
+                        Stage::SecondKiller => Stage::Countermove,
+                        _ => Stage::GenerateQuiets,
+                    };
+                    if let Some(chess_move) = special_quiet {
+                        if !self.skip_quiets && Some(chess_move) != self.table_move && !chess_move.is_capture() && is_pseudo_legal(board, chess_move) {
+                            return Some(chess_move);
+                        }
+                    }
+                },
+                Stage::GenerateQuiets => {
+                    if self.skip_quiets {
+                        self.set_moves(Vec::new());
+                    } else {
+                        let side_to_move = board.position().side_to_move();
+                        let mut moves = quiet_moves::<OxidePosition, OxideBoard>(board)
+                            .filter(|&m| Some(m) != self.table_move && !self.is_special_quiet(m))
+                            .map(|m| (m, heuristics.quiet_score(side_to_move, m, piece_to(board, m), self.previous_moves)))
+                            .collect::<Vec<_>>();
+                        // Stable sort by history, every quiet is likely searched once there wasn't a cutoff from the captures
+                        moves.sort_by(|(_, a), (_, b)| b.cmp(a));
+                        self.set_moves(moves);
+                    }
+                    self.stage = Stage::Quiets;
+                },
+                Stage::Quiets => {
+                    if !self.skip_quiets && self.index < self.moves.len() {
+                        self.index += 1;
+                        return Some(self.moves[self.index - 1].0);
+                    }
+                    self.bad_captures.reverse();
+                    self.stage = Stage::BadCaptures;
+                },
+                Stage::BadCaptures => {
+                    // Reversed so popping yields them in the order they were rejected (best first)
+                    return match self.bad_captures.pop() {
+                        Some(chess_move) => Some(chess_move),
+                        None => {
+                            self.stage = Stage::Done;
+                            None
+                        },
+                    };
+                },
+                Stage::GenerateEvasions => {
+                    let table_move = self.table_move;
+                    let side_to_move = board.position().side_to_move();
+                    let moves = evasion_moves::<OxidePosition, OxideBoard>(board)
+                        .filter(|&m| Some(m) != table_move)
+                        .map(|m| if m.is_capture() || m.is_promotion() {
+                            (m, (1 << 28) + capture_score(board, m))
+                        } else {
+                            (m, heuristics.history(side_to_move, m))
+                        })
+                        .collect();
+                    self.set_moves(moves);
+                    self.stage = Stage::Evasions;
+                },
+                Stage::Evasions => {
+                    let chess_move = self.select_best();
+                    if chess_move.is_none() {
+                        self.stage = Stage::Done;
+                    }
+                    return chess_move;
+                },
+                Stage::QuiescenceCaptures => {
+                    while let Some(chess_move) = self.select_best() {
+                        if see_ge(board, chess_move, self.capture_threshold) {
+                            return Some(chess_move);
+                        }
+                    }
+                    self.stage = Stage::Done;
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn board(fen: &str) -> OxideBoard {
+        OxideBoard::new(OxidePosition::from_fen(fen).expect("Failed to parse test case FEN"))
+    }
+
+    fn picked_moves(board: &OxideBoard, mut picker: MovePicker, heuristics: &Heuristics) -> Vec<OxideMove> {
+        let mut moves = Vec::new();
+        while let Some(chess_move) = picker.next_move(board, heuristics) {
+            moves.push(chess_move);
+        }
+
+        moves
+    }
+
+    #[test]
+    fn is_pseudo_legal_works() {
+        let start = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(is_pseudo_legal(&start, OxideMove::new_double_pawn_push(E2, E4)));
+        assert!(is_pseudo_legal(&start, OxideMove::new(G1, F3)));
+        assert!(!is_pseudo_legal(&start, OxideMove::new(E2, E4)));
+        assert!(!is_pseudo_legal(&start, OxideMove::new(G1, E2)));
+        assert!(!is_pseudo_legal(&start, OxideMove::new_capture(G1, F3)));
+        assert!(!is_pseudo_legal(&start, OxideMove::new(E7, E5)));
+        assert!(!is_pseudo_legal(&start, OxideMove::new(F1, C4)));
+        assert!(!is_pseudo_legal(&start, OxideMove::WHITE_KING_CASTLE));
+        let open = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert!(is_pseudo_legal(&open, OxideMove::WHITE_KING_CASTLE));
+        assert!(is_pseudo_legal(&open, OxideMove::WHITE_QUEEN_CASTLE));
+        assert!(!is_pseudo_legal(&open, OxideMove::BLACK_KING_CASTLE));
+        assert!(is_pseudo_legal(&open, OxideMove::new_capture(A1, A8)));
+    }
+
+    #[test]
+    fn picker_order_works() {
+        let board = board("4k3/8/3p4/2r1n3/1P1Q4/8/8/4K1N1 w - - 0 1");
+        let heuristics = Heuristics::default();
+        let table_move = OxideMove::new(G1, F3);
+        let moves = picked_moves(&board, MovePicker::new(&board, Some(table_move), &heuristics, [None, None], 0), &heuristics);
+        // Table move, then the winning pawn capture of the rook, then the undefended pawn
+        assert_eq!(moves[0], table_move);
+        assert_eq!(moves[1], OxideMove::new_capture(B4, C5));
+        let queen_takes_knight = moves.iter().position(|&m| m == OxideMove::new_capture(D4, E5)).unwrap();
+        let queen_takes_pawn = moves.iter().position(|&m| m == OxideMove::new_capture(D4, D6)).unwrap();
+        let quiet = moves.iter().position(|&m| m == OxideMove::new(E1, E2)).unwrap();
+        // Taking the pawn defended knight loses the queen so it comes after the quiets
+        assert!(queen_takes_pawn < quiet && quiet < queen_takes_knight);
+        // Every move once and only once
+        let mut all_moves = move_gen::legal_moves::<OxidePosition, OxideBoard>(&board).collect::<Vec<_>>();
+        assert_eq!(moves.len(), all_moves.len());
+        all_moves.retain(|m| !moves.contains(m));
+        assert!(all_moves.is_empty());
+    }
+
+    #[test]
+    fn picker_skips_invalid_table_move() {
+        let board = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let heuristics = Heuristics::default();
+        let moves = picked_moves(&board, MovePicker::new(&board, Some(OxideMove::new(E7, E5)), &heuristics, [None, None], 0), &heuristics);
+        assert_eq!(moves.len(), 20);
+        assert!(!moves.contains(&OxideMove::new(E7, E5)));
+    }
+
+    #[test]
+    fn quiescence_picker_works() {
+        let board = board("4k3/8/3p4/2r1n3/1P1Q4/8/8/4K1N1 w - - 0 1");
+        let heuristics = Heuristics::default();
+        let moves = picked_moves(&board, MovePicker::new_quiescence(&board, Some(OxideMove::new(G1, F3))), &heuristics);
+        assert_eq!(moves, vec![OxideMove::new_capture(B4, C5), OxideMove::new_capture(D4, D6)]);
+    }
+
+    #[test]
+    fn probcut_picker_works() {
+        let board = board("4k3/8/3p4/2r1n3/1P1Q4/8/8/4K1N1 w - - 0 1");
+        let heuristics = Heuristics::default();
+        let moves = picked_moves(&board, MovePicker::new_probcut(&board, Some(OxideMove::new_capture(D4, D6)), 200), &heuristics);
+        assert_eq!(moves, vec![OxideMove::new_capture(B4, C5)]);
+    }
+}
@@ -0,0 +1,51 @@
+use oxide_interface::engine::{OxideBoard, OxidePosition};
+use oxide_interface::game::{OxideMove, OxidePiece};
+use interface::game::{ChessMove, SimpleChessMove};
+use move_gen::legal_moves;
+
+/// Long algebraic notation used by UCI (`e2e4`, `e7e8q`, castles as the king's move)
+pub fn format_move(chess_move: OxideMove) -> String {
+    let promotion = match chess_move.promotion() {
+        OxidePiece::Knight => "n",
+        OxidePiece::Bishop => "b",
+        OxidePiece::Rook => "r",
+        OxidePiece::Queen => "q",
+        _ => "",
+    };
+
+    format!("{}{}{}", chess_move.from(), chess_move.to(), promotion).to_ascii_lowercase()
+}
+
+/// Find the legal move written in long algebraic notation (case insensitive)
+pub fn parse_move(board: &OxideBoard, text: &str) -> Option<OxideMove> {
+    let text = text.trim().to_ascii_lowercase();
+    legal_moves::<OxidePosition, OxideBoard>(board).find(|&chess_move| format_move(chess_move) == text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::engine::Board;
+    use interface::game::Position;
+    use oxide_interface::game::OxideSquare::*;
+
+    #[test]
+    fn format_move_works() {
+        assert_eq!(format_move(OxideMove::new_double_pawn_push(E2, E4)), "e2e4");
+        assert_eq!(format_move(OxideMove::new_capture(D4, E5)), "d4e5");
+        assert_eq!(format_move(OxideMove::new_promoting_capture(E7, F8, OxidePiece::Queen)), "e7f8q");
+        assert_eq!(format_move(OxideMove::new_promotion(A2, A1, OxidePiece::Knight)), "a2a1n");
+        assert_eq!(format_move(OxideMove::WHITE_KING_CASTLE), "e1g1");
+        assert_eq!(format_move(OxideMove::BLACK_QUEEN_CASTLE), "e8c8");
+    }
+
+    #[test]
+    fn parse_move_works() {
+        let position = OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let board = OxideBoard::new(position);
+        assert_eq!(parse_move(&board, "e2e4"), Some(OxideMove::new_double_pawn_push(E2, E4)));
+        assert_eq!(parse_move(&board, "G1F3"), Some(OxideMove::new(G1, F3)));
+        assert_eq!(parse_move(&board, "e2e5"), None);
+        assert_eq!(parse_move(&board, "nonsense"), None);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::error::Error;
+use crate::transposition::DEFAULT_HASH_MEGABYTES;
+
+/// Maximum number of search threads allowed by the `Threads` option
+pub const MAX_THREADS: usize = 512;
+/// Maximum transposition table size allowed by the `Hash` option
+pub const MAX_HASH_MEGABYTES: usize = 65536;
+/// Maximum number of lines allowed by the `MultiPV` option
+pub const MAX_MULTI_PV: usize = 256;
+/// Maximum time reserved per move for communication lag allowed by the `Move Overhead` option
+pub const MAX_MOVE_OVERHEAD_MILLISECONDS: usize = 5000;
+/// Default time reserved per move for communication lag
+pub const DEFAULT_MOVE_OVERHEAD_MILLISECONDS: usize = 10;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SearchOptionError {
+    UnknownOption(String), // Option name isn't one the search understands
+    InvalidValue(String), // Option value couldn't be parsed
+    OutOfRange(String), // Option value was parsed but outside of the allowed range
+    InvalidEvaluatorValue(String, String), // Evaluator rejected the value of one of its options (name, reason)
+}
+
+impl Display for SearchOptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            SearchOptionError::UnknownOption(name) => write!(f, "Unknown search option {}", name),
+            SearchOptionError::InvalidValue(name) => write!(f, "Invalid value for search option {}", name),
+            SearchOptionError::OutOfRange(name) => write!(f, "Value out of range for search option {}", name),
+            SearchOptionError::InvalidEvaluatorValue(name, reason) => write!(f, "Invalid value for evaluation option {} ({})", name, reason),
+        }
+    }
+}
+
+impl Error for SearchOptionError {}
+
+/// User configurable search settings
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SearchOptions {
+    /// Number of threads searching in parallel (including the main thread)
+    pub threads: usize,
+    /// Size of the shared transposition table
+    pub hash_megabytes: usize,
+    /// Time reserved per move for communication lag with the GUI
+    pub move_overhead_milliseconds: usize,
+    /// Number of best lines to search and report
+    pub multi_pv: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            hash_megabytes: DEFAULT_HASH_MEGABYTES,
+            move_overhead_milliseconds: DEFAULT_MOVE_OVERHEAD_MILLISECONDS,
+            multi_pv: 1,
+        }
+    }
+}
+
+fn parse_in_range(name: &str, value: &str, min: usize, max: usize) -> Result<usize, SearchOptionError> {
+    let parsed = value.trim().parse::<usize>().map_err(|_| SearchOptionError::InvalidValue(name.to_string()))?;
+
+    if parsed < min || parsed > max {
+        Err(SearchOptionError::OutOfRange(name.to_string()))
+    } else {
+        Ok(parsed)
+    }
+}
+
+impl SearchOptions {
+    /// Set an option by its UCI name (case insensitive)
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), SearchOptionError> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "threads" => self.threads = parse_in_range(name, value, 1, MAX_THREADS)?,
+            "hash" => self.hash_megabytes = parse_in_range(name, value, 1, MAX_HASH_MEGABYTES)?,
+            "multipv" => self.multi_pv = parse_in_range(name, value, 1, MAX_MULTI_PV)?,
+            "move overhead" => self.move_overhead_milliseconds = parse_in_range(name, value, 0, MAX_MOVE_OVERHEAD_MILLISECONDS)?,
+            _ => return Err(SearchOptionError::UnknownOption(name.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_option_works() {
+        let mut options = SearchOptions::default();
+        options.set_option("Threads", "8").unwrap();
+        options.set_option("hash", "128").unwrap();
+        options.set_option("Move Overhead", "0").unwrap();
+        options.set_option("MultiPV", "3").unwrap();
+        assert_eq!(options, SearchOptions { threads: 8, hash_megabytes: 128, move_overhead_milliseconds: 0, multi_pv: 3 });
+    }
+
+    #[test]
+    fn set_option_rejects_bad_values() {
+        let mut options = SearchOptions::default();
+        assert_eq!(options.set_option("Threads", "0"), Err(SearchOptionError::OutOfRange("Threads".to_string())));
+        assert_eq!(options.set_option("Threads", "many"), Err(SearchOptionError::InvalidValue("Threads".to_string())));
+        assert_eq!(options.set_option("Ponder", "true"), Err(SearchOptionError::UnknownOption("Ponder".to_string())));
+        assert_eq!(options, SearchOptions::default());
+    }
+}
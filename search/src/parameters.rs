@@ -0,0 +1,119 @@
+use crate::types::Depth;
+
+/// Tunable search coefficients (pruning and reduction margins)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SearchParameters {
+    /// Iterations from this depth on search the root in a window around the previous iteration's score
+    pub aspiration_min_depth: Depth,
+    /// Initial distance in centipawns of the aspiration window's bounds from the previous score, doubled on every fail
+    pub aspiration_window: i32,
+    /// Null move pruning only kicks in with at least this much depth left
+    pub null_move_min_depth: Depth,
+    /// Depth reduction applied to every null move search
+    pub null_move_base_reduction: Depth,
+    /// Extra reduction of one ply per this much depth
+    pub null_move_depth_divisor: Depth,
+    /// Extra reduction of one ply per this many centipawns the static eval is above beta
+    pub null_move_eval_divisor: i32,
+    /// Cap on the extra reduction from the static eval
+    pub null_move_max_eval_reduction: Depth,
+    /// Null move cutoffs at this depth or higher are verified with a reduced search
+    pub null_move_verification_depth: Depth,
+    /// Moves searched at full depth before late move reductions start
+    pub lmr_full_depth_moves: usize,
+    /// Minimum depth for late move reductions
+    pub lmr_min_depth: Depth,
+    /// Constant term of the reduction formula in hundredths of a ply
+    pub lmr_base: i32,
+    /// Divisor of ln(depth) * ln(move number) in hundredths
+    pub lmr_divisor: i32,
+    /// Reduce one ply less per this much history (and one more per this much negative history)
+    pub lmr_history_divisor: i32,
+    /// Late move pruning only applies at this depth or lower
+    pub lmp_max_depth: Depth,
+    /// Quiet moves searched before late move pruning at any depth
+    pub lmp_base: usize,
+    /// Additional quiet moves searched per depth squared
+    pub lmp_depth_factor: usize,
+    /// Total extensions allowed along a single line so they can't explode
+    pub max_line_extensions: Depth,
+    /// Singular extensions only apply with at least this much depth left
+    pub singular_min_depth: Depth,
+    /// How much shallower than the current depth the table entry can be for a singular extension
+    pub singular_table_depth_margin: Depth,
+    /// Centipawns per ply of depth below the table score that other moves must fail to reach for the table move to be singular
+    pub singular_margin: i32,
+    /// Reverse futility pruning only applies at this depth or lower
+    pub reverse_futility_max_depth: Depth,
+    /// Centipawns per ply of depth the static eval must exceed beta by for reverse futility pruning
+    pub reverse_futility_margin: i32,
+    /// Razoring only applies at this depth or lower
+    pub razoring_max_depth: Depth,
+    /// Centipawns per ply of depth the static eval must trail alpha by to drop into quiescence
+    pub razoring_margin: i32,
+    /// Futility pruning of quiet moves only applies at this depth or lower
+    pub futility_max_depth: Depth,
+    /// Constant term of the futility margin in centipawns
+    pub futility_base_margin: i32,
+    /// Additional futility margin in centipawns per ply of depth
+    pub futility_depth_margin: i32,
+    /// ProbCut only applies with at least this much depth left
+    pub probcut_min_depth: Depth,
+    /// Centipawns above beta a capture must score in the shallow search to predict a cutoff
+    pub probcut_margin: i32,
+    /// Depth reduction of the shallow ProbCut search
+    pub probcut_reduction: Depth,
+}
+
+impl Default for SearchParameters {
+    fn default() -> Self {
+        Self {
+            aspiration_min_depth: 5,
+            aspiration_window: 25,
+            null_move_min_depth: 3,
+            null_move_base_reduction: 3,
+            null_move_depth_divisor: 4,
+            null_move_eval_divisor: 200,
+            null_move_max_eval_reduction: 3,
+            null_move_verification_depth: 12,
+            lmr_full_depth_moves: 3,
+            lmr_min_depth: 3,
+            lmr_base: 75,
+            lmr_divisor: 225,
+            lmr_history_divisor: 4096,
+            lmp_max_depth: 3,
+            lmp_base: 3,
+            lmp_depth_factor: 2,
+            max_line_extensions: 16,
+            singular_min_depth: 8,
+            singular_table_depth_margin: 3,
+            singular_margin: 2,
+            reverse_futility_max_depth: 8,
+            reverse_futility_margin: 80,
+            razoring_max_depth: 2,
+            razoring_margin: 250,
+            futility_max_depth: 6,
+            futility_base_margin: 100,
+            futility_depth_margin: 120,
+            probcut_min_depth: 5,
+            probcut_margin: 200,
+            probcut_reduction: 4,
+        }
+    }
+}
+
+impl SearchParameters {
+    /// Maximum number of quiet moves to search at a given depth before late move pruning
+    #[inline]
+    pub fn late_move_count(&self, depth: Depth) -> usize {
+        let depth = depth.max(0) as usize;
+
+        self.lmp_base + depth * depth * self.lmp_depth_factor
+    }
+
+    /// How far below alpha the static eval must be for quiet moves to be futile at a given depth
+    #[inline]
+    pub fn futility_margin(&self, depth: Depth) -> i32 {
+        self.futility_base_margin + self.futility_depth_margin * depth
+    }
+}
@@ -0,0 +1,96 @@
+use oxide_interface::engine::{OxideBoard, OxidePosition};
+use oxide_interface::game::OxideMove;
+use interface::engine::Board;
+use move_gen::legal_moves;
+use crate::transposition::TranspositionTable;
+use crate::types::MAX_PLY;
+
+/// Triangular principal variation table, the line at each ply is the best move there followed by the line from the ply after
+pub(crate) struct PvTable {
+    lines: Vec<Vec<OxideMove>>,
+}
+
+impl Default for PvTable {
+    fn default() -> Self {
+        Self {
+            lines: (0..=MAX_PLY).map(|_| Vec::with_capacity(MAX_PLY)).collect(),
+        }
+    }
+}
+
+impl PvTable {
+    /// Forget the line at a ply when entering a node there
+    #[inline]
+    pub fn clear(&mut self, ply: usize) {
+        self.lines[ply].clear();
+    }
+
+    /// A move improved alpha at a PV node, its line becomes the move followed by the child's line
+    pub fn update(&mut self, ply: usize, chess_move: OxideMove) {
+        let (parents, children) = self.lines.split_at_mut(ply + 1);
+        let line = &mut parents[ply];
+        line.clear();
+        line.push(chess_move);
+        line.extend_from_slice(&children[0]);
+    }
+
+    /// The current best line from a ply
+    #[inline]
+    pub fn line(&self, ply: usize) -> &[OxideMove] {
+        &self.lines[ply]
+    }
+}
+
+/// Extend a line cut short (by table cutoffs at PV nodes) by following table moves, up to `max_length` moves
+pub(crate) fn extend_from_table(board: &OxideBoard, table: &TranspositionTable, line: &mut Vec<OxideMove>, max_length: usize) {
+    let mut board = *board;
+    for &chess_move in line.iter() {
+        board.make_move_unchecked(chess_move);
+    }
+
+    let mut keys = Vec::with_capacity(max_length);
+    while line.len() < max_length {
+        let key = board.position().zobrist_key();
+        // Following table moves around a repetition would never end
+        if keys.contains(&key) {
+            break;
+        }
+        keys.push(key);
+
+        let table_move = match table.probe(key).and_then(|entry| entry.best_move) {
+            Some(table_move) => table_move,
+            None => break,
+        };
+        // Table entries can come from a colliding position
+        if !legal_moves::<OxidePosition, OxideBoard>(&board).any(|m| m == table_move) {
+            break;
+        }
+
+        board.make_move_unchecked(table_move);
+        line.push(table_move);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::{ChessMove, SimpleChessMove};
+    use oxide_interface::game::OxideSquare::*;
+
+    #[test]
+    fn update_works() {
+        let mut pv = PvTable::default();
+        let (e4, e5, f3) = (OxideMove::new_double_pawn_push(E2, E4), OxideMove::new_double_pawn_push(E7, E5), OxideMove::new(G1, F3));
+        pv.clear(3);
+        pv.update(2, f3);
+        assert_eq!(pv.line(2), &[f3]);
+        pv.update(1, e5);
+        pv.update(0, e4);
+        assert_eq!(pv.line(0), &[e4, e5, f3]);
+        // A new best move at a ply drops the previous line there
+        pv.clear(2);
+        pv.update(1, f3);
+        assert_eq!(pv.line(1), &[f3]);
+        assert_eq!(pv.line(0), &[e4, e5, f3]);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::parameters::SearchParameters;
+use crate::types::Depth;
+
+const TABLE_SIZE: usize = 64;
+
+/// Precomputed log-based late move reductions indexed by depth and move number
+#[derive(Clone)]
+pub(crate) struct ReductionTable {
+    reductions: Box<[[Depth; TABLE_SIZE]; TABLE_SIZE]>,
+}
+
+impl ReductionTable {
+    pub fn new(parameters: &SearchParameters) -> Self {
+        let mut reductions = Box::new([[0; TABLE_SIZE]; TABLE_SIZE]);
+        let base = parameters.lmr_base as f64 / 100.0;
+        let divisor = parameters.lmr_divisor as f64 / 100.0;
+        for (depth, row) in reductions.iter_mut().enumerate().skip(1) {
+            for (move_number, reduction) in row.iter_mut().enumerate().skip(1) {
+                *reduction = (base + (depth as f64).ln() * (move_number as f64).ln() / divisor).max(0.0) as Depth;
+            }
+        }
+
+        Self {
+            reductions,
+        }
+    }
+
+    /// Base reduction for the nth move (1 indexed) searched at a given depth
+    #[inline]
+    pub fn reduction(&self, depth: Depth, move_number: usize) -> Depth {
+        let depth = (depth.max(0) as usize).min(TABLE_SIZE - 1);
+        let move_number = move_number.min(TABLE_SIZE - 1);
+
+        self.reductions[depth][move_number]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reduction_works() {
+        let table = ReductionTable::new(&SearchParameters::default());
+        assert_eq!(table.reduction(1, 1), 0);
+        assert_eq!(table.reduction(3, 1), 0);
+        assert!(table.reduction(10, 20) > table.reduction(4, 4));
+        // Grows with depth and move number
+        for depth in 1..40 {
+            for move_number in 1..40 {
+                assert!(table.reduction(depth + 1, move_number) >= table.reduction(depth, move_number));
+                assert!(table.reduction(depth, move_number + 1) >= table.reduction(depth, move_number));
+            }
+        }
+        // Out of range lookups clamp
+        assert_eq!(table.reduction(500, 500), table.reduction(63, 63));
+    }
+}
@@ -0,0 +1,31 @@
+use oxide_interface::game::OxideMove;
+use oxide_interface::engine::OxideScore;
+use crate::types::Depth;
+
+/// One of the best lines found at the root
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SearchLine {
+    /// Score of the line's first move from the side to move's perspective
+    pub score: OxideScore,
+    /// Iteration the line was found in
+    pub depth: Depth,
+    /// Principal variation starting with the root move
+    pub pv: Vec<OxideMove>,
+}
+
+/// Outcome of a search from a single thread, or the best one picked across threads
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SearchResult {
+    /// Best move found at the root (None if there were no legal moves)
+    pub best_move: Option<OxideMove>,
+    /// Score of the best move from the side to move's perspective
+    pub score: OxideScore,
+    /// Deepest fully completed iteration
+    pub depth: Depth,
+    /// Nodes searched
+    pub nodes: u64,
+    /// Principal variation starting with the best move
+    pub pv: Vec<OxideMove>,
+    /// Best lines for each MultiPV slot sorted by score (the first is the principal variation)
+    pub lines: Vec<SearchLine>,
+}
@@ -0,0 +1,177 @@
+use oxide_interface::engine::{OxideBoard, OxidePosition};
+use oxide_interface::game::{OxideBitboard, OxideMove, OxidePiece, OxideSide, OxideSquare};
+use interface::engine::Board;
+use interface::game::{BoardMask, ChessMove, PieceArrangement, Position, Side, SimpleChessMove, Square, Piece, Shiftable};
+use attacks::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks};
+
+// Attackers in order of least valuable first
+const EXCHANGE_ORDER: [OxidePiece; 6] = [OxidePiece::Pawn, OxidePiece::Knight, OxidePiece::Bishop, OxidePiece::Rook, OxidePiece::Queen, OxidePiece::King];
+
+/// Material value of a piece for exchange evaluation and capture ordering
+#[inline]
+pub(crate) fn exchange_value(piece: OxidePiece) -> i32 {
+    match piece {
+        OxidePiece::Pawn => 100,
+        OxidePiece::Knight => 320,
+        OxidePiece::Bishop => 330,
+        OxidePiece::Rook => 500,
+        OxidePiece::Queen => 900,
+        OxidePiece::King => 20_000,
+        OxidePiece::Empty => 0,
+    }
+}
+
+/// The piece a move captures (a pawn for en-passant, empty for non-captures)
+#[inline]
+pub(crate) fn captured_piece(board: &OxideBoard, chess_move: OxideMove) -> OxidePiece {
+    if chess_move.is_en_passant_capture() {
+        OxidePiece::Pawn
+    } else if chess_move.is_capture() {
+        board.position().piece_on_square(chess_move.to())
+    } else {
+        OxidePiece::Empty
+    }
+}
+
+#[inline]
+fn sided_mask(position: &OxidePosition, piece: OxidePiece, side: OxideSide) -> OxideBitboard {
+    position.sided_piece_mask(<OxidePiece as Piece<OxidePosition>>::add_side(piece, side))
+}
+
+// Every piece of either side attacking a square given an occupancy
+fn attackers_to(position: &OxidePosition, square: OxideSquare, occupied: OxideBitboard) -> OxideBitboard {
+    let square_mask = square.to_mask();
+    let diagonal_sliders = position.piece_mask(OxidePiece::Bishop) | position.piece_mask(OxidePiece::Queen);
+    let cardinal_sliders = position.piece_mask(OxidePiece::Rook) | position.piece_mask(OxidePiece::Queen);
+
+    // A pawn attacks a square if an opposing pawn on that square would attack it
+    (pawn_attacks::<OxidePosition>(square_mask, OxideSide::Black) & sided_mask(position, OxidePiece::Pawn, OxideSide::White))
+        | (pawn_attacks::<OxidePosition>(square_mask, OxideSide::White) & sided_mask(position, OxidePiece::Pawn, OxideSide::Black))
+        | (knight_attacks::<OxidePosition>(square_mask) & position.piece_mask(OxidePiece::Knight))
+        | (king_attacks::<OxidePosition>(square_mask) & position.piece_mask(OxidePiece::King))
+        | (bishop_attacks::<OxidePosition>(square_mask, occupied) & diagonal_sliders)
+        | (rook_attacks::<OxidePosition>(square_mask, occupied) & cardinal_sliders)
+}
+
+/// Static exchange evaluation: if the sequence of captures on a move's destination gains at least `threshold` for the side moving
+pub(crate) fn see_ge(board: &OxideBoard, chess_move: OxideMove, threshold: i32) -> bool {
+    if chess_move.is_king_castle() || chess_move.is_queen_castle() {
+        return 0 >= threshold;
+    }
+
+    let position = board.position();
+    let from = chess_move.from();
+    let to = chess_move.to();
+    // A promotion gains the promoted piece in place of the pawn, which is then what can be recaptured
+    let promotion_gain = if chess_move.is_promotion() { exchange_value(chess_move.promotion()) - exchange_value(OxidePiece::Pawn) } else { 0 };
+    let mut swap = exchange_value(captured_piece(board, chess_move)) + promotion_gain - threshold;
+    if swap < 0 {
+        return false;
+    }
+
+    swap = exchange_value(position.piece_on_square(from)) + promotion_gain - swap;
+    if swap <= 0 {
+        return true;
+    }
+
+    let diagonal_sliders = position.piece_mask(OxidePiece::Bishop) | position.piece_mask(OxidePiece::Queen);
+    let cardinal_sliders = position.piece_mask(OxidePiece::Rook) | position.piece_mask(OxidePiece::Queen);
+    let to_mask = to.to_mask();
+    let mut occupied = position.occupied() ^ from.to_mask() ^ to_mask;
+    let mut side = position.side_to_move();
+    if chess_move.is_en_passant_capture() {
+        occupied ^= if side.is_white() { to_mask.south_shift() } else { to_mask.north_shift() };
+    }
+    let mut attackers = attackers_to(position, to, occupied);
+    let mut result = true;
+
+    loop {
+        side = side.opposite_side();
+        attackers &= occupied;
+        let side_attackers = attackers & position.mask_for_side(side);
+        if side_attackers == OxideBitboard::EMPTY {
+            break;
+        }
+        result = !result;
+
+        let (piece, piece_mask) = EXCHANGE_ORDER.iter()
+            .map(|&piece| (piece, side_attackers & sided_mask(position, piece, side)))
+            .find(|&(_, mask)| mask != OxideBitboard::EMPTY)
+            .expect("Side attackers should contain a piece");
+
+        if piece == OxidePiece::King {
+            // The king can only capture last, if the opponent still has attackers the capture is illegal
+            return if attackers & !position.mask_for_side(side) != OxideBitboard::EMPTY { !result } else { result };
+        }
+
+        swap = exchange_value(piece) - swap;
+        if swap < result as i32 {
+            break;
+        }
+
+        let least_valuable = OxideSquare::from_mask(piece_mask).expect("Piece mask should not be empty");
+        occupied ^= least_valuable.to_mask();
+        // Removing an attacker can reveal sliders behind it
+        if piece == OxidePiece::Pawn || piece == OxidePiece::Bishop || piece == OxidePiece::Queen {
+            attackers |= bishop_attacks::<OxidePosition>(to_mask, occupied) & diagonal_sliders;
+        }
+        if piece == OxidePiece::Rook || piece == OxidePiece::Queen {
+            attackers |= rook_attacks::<OxidePosition>(to_mask, occupied) & cardinal_sliders;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn board(fen: &str) -> OxideBoard {
+        OxideBoard::new(OxidePosition::from_fen(fen).expect("Failed to parse test case FEN"))
+    }
+
+    #[test]
+    fn see_ge_works() {
+        // Undefended pawn
+        let undefended = board("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1");
+        assert!(see_ge(&undefended, OxideMove::new_capture(E1, E5), 100));
+        assert!(!see_ge(&undefended, OxideMove::new_capture(E1, E5), 101));
+        // Knight takes a pawn defended by the d7 knight and the f6 bishop
+        let defended = board("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1");
+        assert!(!see_ge(&defended, OxideMove::new_capture(D3, E5), 0));
+        assert!(see_ge(&defended, OxideMove::new_capture(D3, E5), -220));
+        // Quiet moves to safe squares are even
+        let start = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(see_ge(&start, OxideMove::new(G1, F3), 0));
+        // Moving a queen onto a pawn attacked square loses it
+        let hanging = board("4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1");
+        assert!(!see_ge(&hanging, OxideMove::new(D1, D5), 0));
+    }
+
+    #[test]
+    fn see_ge_x_rays_works() {
+        // Rook takes a pawn defended once, backed up by a second rook behind it
+        let battery = board("4k3/4r3/8/4p3/8/8/4R3/4RK2 w - - 0 1");
+        assert!(see_ge(&battery, OxideMove::new_capture(E2, E5), 100));
+        let no_battery = board("4k3/4r3/8/4p3/8/8/4R3/5K2 w - - 0 1");
+        assert!(!see_ge(&no_battery, OxideMove::new_capture(E2, E5), 1));
+    }
+
+    #[test]
+    fn see_ge_promotions_works() {
+        // Promoting on an undefended square gains the queen for the pawn
+        let undefended = board("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(see_ge(&undefended, OxideMove::new_promotion(B7, B8, OxidePiece::Queen), 800));
+        assert!(!see_ge(&undefended, OxideMove::new_promotion(B7, B8, OxidePiece::Queen), 801));
+        // Promoting capture of an undefended rook
+        let capture = board("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(see_ge(&capture, OxideMove::new_promoting_capture(A7, B8, OxidePiece::Queen), 1300));
+        assert!(!see_ge(&capture, OxideMove::new_promoting_capture(A7, B8, OxidePiece::Queen), 1301));
+        // The rook recaptures the new queen, losing the pawn
+        let defended = board("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(!see_ge(&defended, OxideMove::new_promotion(B7, B8, OxidePiece::Queen), 0));
+        assert!(see_ge(&defended, OxideMove::new_promotion(B7, B8, OxidePiece::Queen), -100));
+    }
+}
@@ -0,0 +1,457 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use oxide_interface::engine::{OxideBoard, OxidePosition, OxideScore, OxideKeyHistory};
+use oxide_interface::game::OxideMove;
+use interface::engine::{Board, Evaluator};
+use interface::game::Position;
+use crate::options::{SearchOptions, SearchOptionError};
+use crate::parameters::SearchParameters;
+use crate::transposition::TranspositionTable;
+use crate::thread::SearchThread;
+use crate::time::{Clock, SystemClock, TimeManager};
+use crate::limits::SearchLimits;
+use crate::result::SearchResult;
+use crate::info::{SearchListener, SearchEvent};
+use crate::pv::extend_from_table;
+
+// How often a finished infinite or pondering search checks if it was stopped
+const INFINITE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// A helper thread kept alive between searches, waiting for its next search
+struct Helper<E: Evaluator<OxidePosition, Score = OxideScore> + Send + 'static> {
+    jobs: Option<Sender<(SearchThread<E>, SearchLimits)>>,
+    results: Receiver<SearchResult>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<E: Evaluator<OxidePosition, Score = OxideScore> + Send + 'static> Helper<E> {
+    fn spawn() -> Self {
+        let (jobs, job_receiver) = channel::<(SearchThread<E>, SearchLimits)>();
+        let (result_sender, results) = channel();
+        let handle = thread::spawn(move || {
+            for (mut search_thread, limits) in job_receiver {
+                if result_sender.send(search_thread.iterative_deepening(&limits)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs: Some(jobs),
+            results,
+            handle: Some(handle),
+        }
+    }
+
+    // Returns false if the thread is gone (it panicked during an earlier search)
+    fn start(&self, search_thread: SearchThread<E>, limits: SearchLimits) -> bool {
+        self.jobs.as_ref().map_or(false, |jobs| jobs.send((search_thread, limits)).is_ok())
+    }
+}
+
+impl<E: Evaluator<OxidePosition, Score = OxideScore> + Send + 'static> Drop for Helper<E> {
+    fn drop(&mut self) {
+        // Closing the job channel ends the thread's loop
+        self.jobs.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Lazy SMP search: every thread searches the same root on its own board and they only communicate through the shared table
+pub struct ThreadPool<E: Evaluator<OxidePosition, Score = OxideScore> + Clone + Send + 'static> {
+    options: SearchOptions,
+    parameters: SearchParameters,
+    evaluator: E,
+    table: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+    listener: Option<Arc<dyn SearchListener>>,
+    history: OxideKeyHistory,
+    helpers: Vec<Helper<E>>,
+}
+
+impl<E: Evaluator<OxidePosition, Score = OxideScore> + Clone + Send + 'static> ThreadPool<E> {
+    pub fn new(options: SearchOptions, evaluator: E) -> Self {
+        Self {
+            table: Arc::new(TranspositionTable::new(options.hash_megabytes)),
+            options,
+            parameters: SearchParameters::default(),
+            evaluator,
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            clock: Arc::new(SystemClock::default()),
+            listener: None,
+            history: OxideKeyHistory::default(),
+            helpers: (1..options.threads).map(|_| Helper::spawn()).collect(),
+        }
+    }
+
+    /// Current search options
+    #[inline]
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    /// Current tunable search coefficients
+    #[inline]
+    pub fn parameters(&self) -> &SearchParameters {
+        &self.parameters
+    }
+
+    /// Replace the tunable search coefficients used by future searches
+    pub fn set_parameters(&mut self, parameters: SearchParameters) {
+        self.parameters = parameters;
+    }
+
+    /// Flag that stops every running search thread when set (safe to set from another thread), set before a search it stops it immediately
+    #[inline]
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Flag that makes a search ignore its time limits and keep going until it's cleared (on ponderhit) or the search is stopped
+    #[inline]
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        self.pondering.clone()
+    }
+
+    /// Replace the clock timed searches are measured with
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Receive progress events from future searches (or stop receiving them with None)
+    pub fn set_listener(&mut self, listener: Option<Arc<dyn SearchListener>>) {
+        self.listener = listener;
+    }
+
+    /// Positions of the game up to and including the next searched one, so repetitions of them are seen as draws (ignored when searching any other position)
+    pub fn set_history(&mut self, history: OxideKeyHistory) {
+        self.history = history;
+    }
+
+    /// Set an option by its UCI name, resizing the table if the hash size changed and the helpers if the thread count did (options the search doesn't have go to the evaluator)
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), SearchOptionError> {
+        let previous_hash_megabytes = self.options.hash_megabytes;
+        match self.options.set_option(name, value) {
+            Err(SearchOptionError::UnknownOption(_)) => return match self.evaluator.set_option(name, value) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(SearchOptionError::UnknownOption(name.to_string())),
+                Err(reason) => Err(SearchOptionError::InvalidEvaluatorValue(name.to_string(), reason)),
+            },
+            result => result?,
+        }
+        if self.options.hash_megabytes != previous_hash_megabytes {
+            self.table = Arc::new(TranspositionTable::new(self.options.hash_megabytes));
+        }
+        let helper_count = self.options.threads - 1;
+        self.helpers.truncate(helper_count);
+        while self.helpers.len() < helper_count {
+            self.helpers.push(Helper::spawn());
+        }
+
+        Ok(())
+    }
+
+    /// Empty the shared transposition table
+    pub fn clear_hash(&self) {
+        self.table.clear();
+    }
+
+    /// Search a position on every configured thread until a limit is reached or the stop flag is set, both flags are cleared once it finishes
+    ///
+    /// Node limited searches clear the table first so they always search the same nodes
+    pub fn search(&mut self, board: &OxideBoard, limits: &SearchLimits) -> SearchResult {
+        // Entries left by earlier searches would change which nodes a node limited search visits
+        if limits.nodes.is_some() {
+            self.table.clear();
+        }
+        self.table.new_search();
+        let node_counter = Arc::new(AtomicU64::new(0));
+        let history = if self.history.last_key() == Some(board.position().zobrist_key()) {
+            self.history.clone()
+        } else {
+            OxideKeyHistory::new(board.position())
+        };
+
+        // Helpers would make node limited searches depend on thread scheduling
+        let mut started_helpers = Vec::with_capacity(self.helpers.len());
+        if limits.nodes.is_none() {
+            for (index, helper) in self.helpers.iter().enumerate() {
+                let mut search_thread = SearchThread::new(index + 1, *board, self.evaluator.clone(), self.parameters, self.table.clone(), self.stop.clone(), node_counter.clone());
+                search_thread.set_history(history.clone());
+                if helper.start(search_thread, limits.clone()) {
+                    started_helpers.push(helper);
+                }
+            }
+        }
+
+        let mut main_thread = SearchThread::new(0, *board, self.evaluator.clone(), self.parameters, self.table.clone(), self.stop.clone(), node_counter);
+        main_thread.set_history(history);
+        main_thread.set_multi_pv(self.options.multi_pv);
+        if let Some(listener) = &self.listener {
+            main_thread.set_listener(listener.clone());
+        }
+        main_thread.set_ponder_flag(self.pondering.clone());
+        if !limits.infinite {
+            let move_overhead = Duration::from_millis(self.options.move_overhead_milliseconds as u64);
+            if let Some(time) = TimeManager::new(&limits.time, board.position().side_to_move(), move_overhead, self.clock.clone()) {
+                main_thread.set_time_manager(time);
+            }
+        }
+        let main_result = main_thread.iterative_deepening(limits);
+        // Infinite and pondering searches only end when stopped (or on ponderhit), even if every depth was searched
+        while (limits.infinite || self.pondering.load(Ordering::Relaxed)) && !self.stop.load(Ordering::Relaxed) {
+            thread::sleep(INFINITE_POLL_INTERVAL);
+        }
+
+        // Once the main thread is done the helpers' work is only useful through the table
+        self.stop.store(true, Ordering::Relaxed);
+        let helper_results = started_helpers.into_iter()
+            .filter_map(|helper| helper.results.recv().ok())
+            .collect::<Vec<_>>();
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(false, Ordering::Relaxed);
+
+        let result = if self.options.multi_pv > 1 {
+            // Helpers only search a single line, the main thread's lines are the only complete set
+            let nodes = main_result.nodes + helper_results.iter().map(|result| result.nodes).sum::<u64>();
+            SearchResult { nodes, ..main_result }
+        } else {
+            select_best(main_result, helper_results)
+        };
+
+        if let Some(listener) = &self.listener {
+            listener.on_event(&SearchEvent::BestMove {
+                best_move: result.best_move,
+                ponder_move: self.ponder_move(board, &result),
+            });
+        }
+
+        result
+    }
+
+    // The expected reply to the best move, from the table when the line stopped at the best move
+    fn ponder_move(&self, board: &OxideBoard, result: &SearchResult) -> Option<OxideMove> {
+        if let Some(&ponder_move) = result.pv.get(1) {
+            return Some(ponder_move);
+        }
+
+        let mut line = vec![result.best_move?];
+        extend_from_table(board, &self.table, &mut line, 2);
+        line.get(1).copied()
+    }
+}
+
+// Prefer the deepest completed iteration, breaking ties by score and then in favor of the main thread
+fn select_best(main_result: SearchResult, helper_results: Vec<SearchResult>) -> SearchResult {
+    let nodes = main_result.nodes + helper_results.iter().map(|result| result.nodes).sum::<u64>();
+    let best = helper_results.into_iter()
+        .filter(|result| result.best_move.is_some())
+        .fold(main_result, |best, result| {
+            if (result.depth, result.score) > (best.depth, best.score) {
+                result
+            } else {
+                best
+            }
+        });
+
+    SearchResult {
+        nodes,
+        ..best
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::engine::PositionalScore;
+    use interface::game::{ChessMove, SimpleChessMove};
+    use oxide_interface::game::OxideSquare::*;
+    use crate::types::Depth;
+    use crate::options::SearchOptions;
+    use std::sync::atomic::AtomicU64;
+    use evaluation::HandcraftedEvaluator;
+    use move_gen::legal_moves;
+    use std::sync::mpsc::channel;
+    use crate::time::TimeControl;
+    use crate::info::ChannelListener;
+    use crate::transposition::Bound;
+
+    fn result(from_depth: Depth, score: i32, nodes: u64) -> SearchResult {
+        SearchResult {
+            best_move: Some(OxideMove::new(G1, F3)),
+            score: OxideScore::new(score),
+            depth: from_depth,
+            nodes,
+            pv: vec![OxideMove::new(G1, F3)],
+            lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn select_best_prefers_depth_then_score() {
+        let main = result(6, 20, 100);
+        let deeper = SearchResult { best_move: Some(OxideMove::new_double_pawn_push(E2, E4)), ..result(7, 10, 50) };
+        let shallow_better = result(5, 90, 25);
+        let deeper_move = deeper.best_move;
+        let best = select_best(main, vec![shallow_better, deeper]);
+        assert_eq!(best.best_move, deeper_move);
+        assert_eq!(best.depth, 7);
+        assert_eq!(best.nodes, 175);
+    }
+
+    #[test]
+    fn select_best_keeps_main_on_ties() {
+        let main = result(6, 20, 100);
+        let helper = SearchResult { best_move: Some(OxideMove::new_double_pawn_push(E2, E4)), ..result(6, 20, 100) };
+        let main_move = main.best_move;
+        assert_eq!(select_best(main, vec![helper]).best_move, main_move);
+    }
+
+    fn pool() -> ThreadPool<HandcraftedEvaluator> {
+        ThreadPool::new(SearchOptions { hash_megabytes: 1, ..SearchOptions::default() }, HandcraftedEvaluator::default())
+    }
+
+    fn board(fen: &str) -> OxideBoard {
+        OxideBoard::new(OxidePosition::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn mate_in_one_is_found() {
+        let result = pool().search(&board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"), &SearchLimits::depth(4));
+        assert_eq!(result.best_move, Some(OxideMove::new(A1, A8)));
+        assert_eq!(result.score.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn node_limited_search_is_deterministic() {
+        let board = board("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        let first = pool().search(&board, &SearchLimits::nodes(20_000));
+        let second = pool().search(&board, &SearchLimits::nodes(20_000));
+        assert!(first.best_move.is_some());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn depth_limited_search_returns_a_legal_move() {
+        let mut board = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let result = pool().search(&board, &SearchLimits::depth(4));
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.pv.first().copied(), result.best_move);
+        // Every move of the line is legal where it's played
+        for &chess_move in &result.pv {
+            assert!(legal_moves::<OxidePosition, OxideBoard>(&board).any(|legal_move| legal_move == chess_move), "{} isn't legal", chess_move);
+            board.make_move_unchecked(chess_move);
+        }
+    }
+
+    // Every reading of the time moves it forward, so a timed search ends after a fixed number of readings
+    #[derive(Default)]
+    struct TickingClock {
+        readings: AtomicU64,
+    }
+
+    impl Clock for TickingClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.readings.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    #[test]
+    fn node_limit_works() {
+        for &nodes in &[1, 1_000, 12_345] {
+            let result = pool().search(&board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), &SearchLimits::nodes(nodes));
+            assert_eq!(result.nodes, nodes);
+            assert!(result.best_move.is_some());
+        }
+    }
+
+    #[test]
+    fn node_limited_search_ignores_earlier_searches() {
+        let board = board("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        let mut pool = pool();
+        let first = pool.search(&board, &SearchLimits::nodes(20_000));
+        // Fills the table with entries the next search could otherwise use
+        pool.search(&board, &SearchLimits::depth(5));
+        let second = pool.search(&board, &SearchLimits::nodes(20_000));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn depth_limit_works() {
+        for &depth in &[1, 3, 5] {
+            let result = pool().search(&board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"), &SearchLimits::depth(depth));
+            assert_eq!(result.depth, depth);
+            assert!(result.lines.iter().all(|line| line.depth == depth));
+        }
+    }
+
+    #[test]
+    fn mate_limit_works() {
+        // Ra7 confines the king to the back rank for Rb8
+        let board = board("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+        let limits = SearchLimits { mate: Some(2), ..SearchLimits::default() };
+        let result = pool().search(&board, &limits);
+        assert_eq!(result.score.mate_in(), Some(3));
+        assert!(result.depth < limits.max_depth());
+        // A longer mate than asked for doesn't end the search
+        let limits = SearchLimits { mate: Some(1), depth: Some(6), ..SearchLimits::default() };
+        let result = pool().search(&board, &limits);
+        assert_eq!(result.score.mate_in(), Some(3));
+        assert_eq!(result.depth, 6);
+    }
+
+    #[test]
+    fn move_time_works() {
+        let mut pool = pool();
+        pool.set_clock(Arc::new(TickingClock::default()));
+        let limits = SearchLimits {
+            time: TimeControl { move_time: Some(Duration::from_millis(200)), ..TimeControl::default() },
+            ..SearchLimits::default()
+        };
+        let result = pool.search(&board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), &limits);
+        assert!(result.best_move.is_some());
+        assert!(result.depth < limits.max_depth());
+    }
+
+    #[test]
+    fn search_moves_limit_works() {
+        let board = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let limits = SearchLimits { search_moves: vec![OxideMove::new(G1, F2)], ..SearchLimits::depth(3) };
+        let result = pool().search(&board, &limits);
+        assert_eq!(result.best_move, Some(OxideMove::new(G1, F2)));
+        assert!(result.score.mate_in().is_none());
+    }
+
+    #[test]
+    fn aspiration_fails_report_bounds() {
+        let (sender, receiver) = channel();
+        let mut pool = pool();
+        pool.set_listener(Some(Arc::new(ChannelListener::new(sender))));
+        // A window of a centipawn around the previous score fails on nearly every iteration
+        pool.set_parameters(SearchParameters { aspiration_min_depth: 2, aspiration_window: 1, ..SearchParameters::default() });
+        pool.search(&board("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4"), &SearchLimits::depth(6));
+
+        let iterations = receiver.try_iter()
+            .filter_map(|event| match event {
+                SearchEvent::Iteration(info) => Some((info.depth, info.bound)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(iterations.iter().any(|&(_, bound)| bound != Bound::Exact));
+        // Full window iterations are exact, later ones end with an exact line after any bounds
+        assert_eq!(iterations[0], (1, Bound::Exact));
+        for depth in 1..=6 {
+            let depth_bounds = iterations.iter().filter(|&&(iteration, _)| iteration == depth).map(|&(_, bound)| bound).collect::<Vec<_>>();
+            assert_eq!(depth_bounds.last(), Some(&Bound::Exact), "Depth {} didn't end exact", depth);
+            assert_eq!(depth_bounds.iter().filter(|&&bound| bound == Bound::Exact).count(), 1);
+        }
+    }
+}
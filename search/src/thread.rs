@@ -0,0 +1,867 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use oxide_interface::engine::{OxideBoard, OxideBoardState, OxidePosition, OxideScore, OxideKeyHistory};
+use oxide_interface::game::{OxideMove, OxideBitboard, OxidePiece};
+use interface::engine::{Board, Evaluator, PositionalScore};
+use interface::game::{ChessMove, Position, PieceArrangement, Piece, BoardMask};
+use interface::types::PlyCount;
+use move_gen::{legal_moves, BoardOutcome};
+use crate::transposition::{TranspositionTable, TranspositionEntry, Bound};
+use crate::heuristics::{Heuristics, PieceTo};
+use crate::move_picker::{MovePicker, piece_to};
+use crate::pv::{PvTable, extend_from_table};
+use crate::time::TimeManager;
+use crate::limits::SearchLimits;
+use crate::info::{SearchListener, SearchEvent, IterationInfo};
+use crate::parameters::SearchParameters;
+use crate::reductions::ReductionTable;
+use crate::result::{SearchResult, SearchLine};
+use crate::types::{Depth, MAX_PLY};
+
+// How many nodes to search between polling the shared stop flag
+const STOP_POLL_NODES: u64 = 1024;
+// Current root moves are only reported once searching takes long enough for them to be interesting
+const CURRENT_MOVE_DELAY: Duration = Duration::from_secs(3);
+const INFINITE_SCORE: OxideScore = OxideScore::INFINITE;
+
+// What the search knows about each ply of the line currently being searched
+#[derive(Copy, Clone, Default)]
+struct PlyState {
+    // The piece and destination of the move made at this ply (None for null moves)
+    current_move: Option<PieceTo>,
+    // If the move made at this ply captured a piece
+    capture: bool,
+    // Move skipped while verifying if the table move is singular
+    excluded_move: Option<OxideMove>,
+    // Total extensions made along the line leading to this ply
+    extensions: Depth,
+}
+
+// What a reduced search without the table move says about it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Singularity {
+    Singular, // Every other move failed low, so the table move is extended
+    MultiCut(OxideScore), // Another move beat beta too, so the node fails high without searching it
+    NotSingular, // Other moves are close to the table move
+}
+
+// A legal move at the root with its score and line from the last iteration that searched it
+struct RootMove {
+    chess_move: OxideMove,
+    score: OxideScore,
+    pv: Vec<OxideMove>,
+    // Nodes spent searching the move in the last iteration
+    nodes: u64,
+}
+
+/// A single search thread with its own board copy and ordering heuristics that shares a transposition table
+pub(crate) struct SearchThread<E: Evaluator<OxidePosition, Score = OxideScore>> {
+    id: usize,
+    board: OxideBoard,
+    // Positions of the game and the current line, for repetition detection
+    history: OxideKeyHistory,
+    evaluator: E,
+    table: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    parameters: SearchParameters,
+    reductions: ReductionTable,
+    heuristics: Heuristics,
+    stack: [PlyState; MAX_PLY + 1],
+    pv: PvTable,
+    // Only the main thread manages time, it stops the helpers through the shared flag
+    time: Option<TimeManager>,
+    // Time limits are ignored while set, until the opponent plays the expected move
+    pondering: Option<Arc<AtomicBool>>,
+    node_limit: Option<u64>,
+    multi_pv: usize,
+    listener: Option<Arc<dyn SearchListener>>,
+    // Nodes searched by every thread, added to in batches
+    node_counter: Arc<AtomicU64>,
+    start: Instant,
+    sel_depth: usize,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl<E: Evaluator<OxidePosition, Score = OxideScore>> SearchThread<E> {
+    pub fn new(id: usize, board: OxideBoard, evaluator: E, parameters: SearchParameters, table: Arc<TranspositionTable>, stop: Arc<AtomicBool>, node_counter: Arc<AtomicU64>) -> Self {
+        Self {
+            id,
+            history: OxideKeyHistory::new(board.position()),
+            board,
+            evaluator,
+            table,
+            stop,
+            reductions: ReductionTable::new(&parameters),
+            parameters,
+            heuristics: Heuristics::default(),
+            stack: [PlyState::default(); MAX_PLY + 1],
+            pv: PvTable::default(),
+            time: None,
+            pondering: None,
+            node_limit: None,
+            multi_pv: 1,
+            listener: None,
+            node_counter,
+            start: Instant::now(),
+            sel_depth: 0,
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    /// Positions played before the searched one (ending with it) so the search can see repetitions of them
+    pub fn set_history(&mut self, history: OxideKeyHistory) {
+        debug_assert_eq!(history.last_key(), Some(self.board.position().zobrist_key()), "History doesn't end with the searched position");
+        self.history = history;
+    }
+
+    /// Stop searching when the time manager says so
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = Some(time);
+    }
+
+    /// Ignore time limits while the flag is set
+    pub fn set_ponder_flag(&mut self, pondering: Arc<AtomicBool>) {
+        self.pondering = Some(pondering);
+    }
+
+    /// Search the best `multi_pv` root moves each with their own line instead of only the best
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+    }
+
+    /// Report progress to a listener (only the main thread should)
+    pub fn set_listener(&mut self, listener: Arc<dyn SearchListener>) {
+        self.listener = Some(listener);
+    }
+
+    /// Iteratively deepen until a limit is reached or the shared stop flag is raised
+    pub fn iterative_deepening(&mut self, limits: &SearchLimits) -> SearchResult {
+        let max_depth = limits.max_depth();
+        self.node_limit = limits.nodes;
+        self.start = Instant::now();
+        let mut result = SearchResult::default();
+        let mut root_moves = legal_moves::<OxidePosition, OxideBoard>(&self.board)
+            .filter(|&m| limits.allows_root_move(m))
+            .map(|m| RootMove { chess_move: m, score: -INFINITE_SCORE, pv: vec![m], nodes: 0 })
+            .collect::<Vec<_>>();
+        result.best_move = root_moves.first().map(|root_move| root_move.chess_move);
+        // Moves from the root update the evaluation from it
+        self.evaluator.reset(self.board.position());
+
+        // Helper threads skip ahead so they fill the table with deeper results and desynchronize from the main thread
+        let depth_offset = if self.id == 0 { 0 } else { 1 + self.id as Depth % 2 };
+        let mut depth = 1 + depth_offset;
+        while depth <= max_depth && !root_moves.is_empty() {
+            self.sel_depth = 0;
+            let score = self.search_root(&mut root_moves, depth);
+            if self.stopped {
+                break;
+            }
+
+            result.lines = root_moves.iter()
+                .take(self.multi_pv)
+                .map(|root_move| {
+                    let mut pv = root_move.pv.clone();
+                    extend_from_table(&self.board, &self.table, &mut pv, depth as usize);
+                    SearchLine { score: root_move.score, depth, pv }
+                })
+                .collect();
+            result.best_move = Some(root_moves[0].chess_move);
+            result.score = score;
+            result.depth = depth;
+            result.pv = result.lines[0].pv.clone();
+            self.report_iteration(&result);
+            depth += 1;
+            if limits.is_mate_found(score) {
+                break;
+            }
+
+            let pondering = self.is_pondering();
+            if let Some(time) = &mut self.time {
+                let iteration_nodes = root_moves.iter().map(|root_move| root_move.nodes).sum::<u64>().max(1);
+                time.record_iteration(root_moves[0].chess_move, score, root_moves[0].nodes as f64 / iteration_nodes as f64);
+                if !pondering && !time.should_start_iteration() {
+                    break;
+                }
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn search_root(&mut self, root_moves: &mut Vec<RootMove>, depth: Depth) -> OxideScore {
+        for root_move in root_moves.iter_mut() {
+            root_move.nodes = 0;
+        }
+
+        // Each slot searches the moves left after excluding the best moves of the earlier slots
+        let slots = self.multi_pv.min(root_moves.len());
+        for pv_index in 0..slots {
+            self.search_root_slot(&mut root_moves[pv_index..], depth, pv_index + 1);
+            if self.stopped {
+                return OxideScore::default();
+            }
+        }
+        // A later slot can score higher than an earlier one from search instability
+        root_moves[..slots].sort_by(|a, b| b.score.cmp(&a.score));
+
+        // Only the best line is stored, the other slots' results at the root would contradict it
+        let (best_move, best_score) = (root_moves[0].chess_move, root_moves[0].score);
+        self.table.store(self.board.position().zobrist_key(), TranspositionEntry {
+            best_move: Some(best_move),
+            score: best_score,
+            depth,
+            bound: Bound::Exact,
+        });
+
+        best_score
+    }
+
+    fn search_root_slot(&mut self, root_moves: &mut [RootMove], depth: Depth, first_move_number: usize) {
+        // Deeper iterations rarely move far from the slot's previous score, a narrow window around it cuts off more
+        let previous_score = root_moves[0].score;
+        let mut window = OxideScore::new(self.parameters.aspiration_window);
+        let (mut alpha, mut beta) = if depth >= self.parameters.aspiration_min_depth && !previous_score.is_mate() {
+            (previous_score - window, previous_score + window)
+        } else {
+            (-INFINITE_SCORE, INFINITE_SCORE)
+        };
+
+        loop {
+            let score = self.search_root_window(root_moves, depth, first_move_number, alpha, beta);
+            if self.stopped {
+                return;
+            }
+
+            // The score is only a bound until a search ends inside the window, widen the side it failed on and search again
+            let bound = if score <= alpha {
+                alpha = (score - window).max(-INFINITE_SCORE);
+                Bound::Upper
+            } else if score >= beta {
+                beta = (score + window).min(INFINITE_SCORE);
+                Bound::Lower
+            } else {
+                return;
+            };
+            window = window + window;
+            let line = SearchLine { score, depth, pv: root_moves[0].pv.clone() };
+            self.report_line(first_move_number, &line, bound);
+        }
+    }
+
+    // Search the root moves in a window, returning the best score (a bound if it's outside the window)
+    fn search_root_window(&mut self, root_moves: &mut [RootMove], depth: Depth, first_move_number: usize, mut alpha: OxideScore, beta: OxideScore) -> OxideScore {
+        let mut best_score = -INFINITE_SCORE;
+        let mut searched = 0;
+
+        for (index, root_move) in root_moves.iter_mut().enumerate() {
+            let chess_move = root_move.chess_move;
+            if let Some(listener) = &self.listener {
+                if self.start.elapsed() >= CURRENT_MOVE_DELAY {
+                    listener.on_event(&SearchEvent::CurrentMove { depth, chess_move, move_number: first_move_number + index });
+                }
+            }
+            self.stack[0].current_move = Some(piece_to(&self.board, chess_move));
+            self.stack[0].capture = chess_move.is_capture();
+            self.stack[1].extensions = 0;
+            let nodes_before = self.nodes;
+            let state = self.make_move(chess_move);
+            let move_score = -self.negamax(-beta, -alpha, depth - 1, 1, true);
+            self.undo_move(chess_move, state);
+            root_move.nodes += self.nodes - nodes_before;
+            if self.stopped {
+                return best_score;
+            }
+
+            root_move.score = move_score;
+            best_score = best_score.max(move_score);
+            searched = index + 1;
+            if move_score > alpha {
+                self.pv.update(0, chess_move);
+                root_move.pv = self.pv.line(0).to_vec();
+                if move_score >= beta {
+                    break;
+                }
+                alpha = move_score;
+            }
+        }
+
+        // Moves after a fail high keep their order, stable sort keeps the previous iteration's order between equal scores
+        root_moves[..searched].sort_by(|a, b| b.score.cmp(&a.score));
+
+        best_score
+    }
+
+    fn report_iteration(&self, result: &SearchResult) {
+        for (index, line) in result.lines.iter().enumerate() {
+            self.report_line(index + 1, line, Bound::Exact);
+        }
+    }
+
+    fn report_line(&self, multi_pv: usize, line: &SearchLine, bound: Bound) {
+        let listener = match &self.listener {
+            Some(listener) => listener,
+            None => return,
+        };
+
+        let time = self.start.elapsed();
+        let nodes = self.node_counter.load(Ordering::Relaxed) + self.nodes % STOP_POLL_NODES;
+        listener.on_event(&SearchEvent::Iteration(IterationInfo {
+            depth: line.depth,
+            sel_depth: self.sel_depth,
+            multi_pv,
+            score: line.score,
+            bound,
+            nodes,
+            nodes_per_second: (nodes as u128 * 1_000_000 / time.as_micros().max(1)) as u64,
+            time,
+            hashfull: self.table.hashfull(),
+            pv: line.pv.clone(),
+        }));
+    }
+
+    // Count a node and check if the search has to stop, nodes entered once it has aren't counted
+    #[inline]
+    fn poll_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        self.nodes += 1;
+        // Node limits are checked every node so they're exact
+        if self.node_limit.map_or(false, |limit| self.nodes >= limit) {
+            self.stopped = true;
+        }
+        if self.nodes % STOP_POLL_NODES == 0 {
+            self.node_counter.fetch_add(STOP_POLL_NODES, Ordering::Relaxed);
+            if !self.is_pondering() && self.time.as_ref().map_or(false, TimeManager::is_out_of_time) {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+            if self.stop.load(Ordering::Relaxed) {
+                self.stopped = true;
+            }
+        }
+
+        self.stopped
+    }
+
+    #[inline]
+    fn is_pondering(&self) -> bool {
+        self.pondering.as_ref().map_or(false, |pondering| pondering.load(Ordering::Relaxed))
+    }
+
+    // Zugzwang is common when the side to move only has pawns, making null move observations unsound
+    #[inline]
+    fn has_non_pawn_material(&self) -> bool {
+        let position = self.board.position();
+        let side_to_move = position.side_to_move();
+        let pawns_and_king = position.sided_piece_mask(<OxidePiece as Piece<OxidePosition>>::add_side(OxidePiece::Pawn, side_to_move))
+            | position.sided_piece_mask(<OxidePiece as Piece<OxidePosition>>::add_side(OxidePiece::King, side_to_move));
+
+        position.mask_for_side(side_to_move) & !pawns_and_king != OxideBitboard::EMPTY
+    }
+
+    #[inline]
+    fn make_move(&mut self, chess_move: OxideMove) -> OxideBoardState {
+        let changes = self.board.piece_changes(chess_move);
+        let state = self.board.make_move_unchecked(chess_move);
+        self.history.push(self.board.position());
+        self.evaluator.push_move(self.board.position(), changes.removed(), changes.added());
+
+        state
+    }
+
+    #[inline]
+    fn undo_move(&mut self, chess_move: OxideMove, state: OxideBoardState) {
+        self.evaluator.pop_move();
+        self.history.pop();
+        self.board.undo_move_unchecked(chess_move, state);
+    }
+
+    #[inline]
+    fn make_null_move(&mut self) -> OxideBoardState {
+        let state = self.board.make_null_move();
+        self.history.push_null(self.board.position());
+        self.evaluator.push_move(self.board.position(), &[], &[]);
+
+        state
+    }
+
+    #[inline]
+    fn undo_null_move(&mut self, state: OxideBoardState) {
+        self.evaluator.pop_move();
+        self.history.pop();
+        self.board.undo_null_move(state);
+    }
+
+    // Repeating a position once is enough inside the search, the side that could avoid it would also be able to repeat it again
+    #[inline]
+    fn is_draw(&self) -> bool {
+        self.history.is_repetition(2) || self.board.position().has_insufficient_material() || self.board.is_fifty_move_draw()
+    }
+
+    // Direct or discovered
+    #[inline]
+    fn gives_check(&self, chess_move: OxideMove) -> bool {
+        self.board.gives_check(&chess_move) || self.board.is_discovery(&chess_move)
+    }
+
+    // ProbCut: if a capture beats beta by a margin in a reduced search, the full depth search very likely would too
+    fn probcut(&mut self, beta: OxideScore, static_eval: OxideScore, table_move: Option<OxideMove>, depth: Depth, ply: usize) -> Option<OxideScore> {
+        let probcut_beta = beta + OxideScore::new(self.parameters.probcut_margin);
+        let probcut_depth = depth - self.parameters.probcut_reduction;
+        let mut move_picker = MovePicker::new_probcut(&self.board, table_move, probcut_beta.centipawns() - static_eval.centipawns());
+        while let Some(chess_move) = move_picker.next_move(&self.board, &self.heuristics) {
+            self.stack[ply].current_move = Some(piece_to(&self.board, chess_move));
+            self.stack[ply].capture = true;
+            self.stack[ply + 1].extensions = self.stack[ply].extensions;
+            let state = self.make_move(chess_move);
+            // Verify with quiescence first, it's much cheaper and usually refutes the capture
+            let mut score = -self.quiescence(-probcut_beta, -probcut_beta + OxideScore::new(1), ply + 1);
+            if score >= probcut_beta {
+                score = -self.negamax(-probcut_beta, -probcut_beta + OxideScore::new(1), probcut_depth - 1, ply + 1, true);
+            }
+            self.undo_move(chess_move, state);
+            if self.stopped {
+                return None;
+            }
+
+            if score >= probcut_beta {
+                self.table.store(self.board.position().zobrist_key(), TranspositionEntry {
+                    best_move: Some(chess_move),
+                    score: score.to_node_relative(ply as PlyCount),
+                    depth: probcut_depth,
+                    bound: Bound::Lower,
+                });
+                return Some(score);
+            }
+        }
+
+        None
+    }
+
+    fn null_move_reduction(&self, depth: Depth, eval_margin: i32) -> Depth {
+        let parameters = &self.parameters;
+        let eval_reduction = (eval_margin / parameters.null_move_eval_divisor).min(parameters.null_move_max_eval_reduction);
+
+        parameters.null_move_base_reduction + depth / parameters.null_move_depth_divisor + eval_reduction
+    }
+
+    // Late move reduction for a move already made on the board
+    fn late_move_reduction(&self, chess_move: OxideMove, quiet_score: i32, depth: Depth, move_number: usize, ply: usize, pv_node: bool) -> Depth {
+        let mut reduction = self.reductions.reduction(depth, move_number);
+        if pv_node {
+            reduction -= 1;
+        }
+        if self.board.in_check() {
+            reduction -= 1;
+        }
+        if chess_move.is_capture() || chess_move.is_promotion() {
+            reduction -= 1;
+        } else {
+            if self.heuristics.is_killer(ply, chess_move) {
+                reduction -= 1;
+            }
+            reduction -= quiet_score / self.parameters.lmr_history_divisor;
+        }
+
+        // Never reduce straight into quiescence or extend
+        reduction.min(depth - 2).max(0)
+    }
+
+    // Extensions are capped per line so they can't explode
+    #[inline]
+    fn can_extend(&self, ply: usize) -> bool {
+        self.stack[ply].extensions < self.parameters.max_line_extensions
+    }
+
+    // The table move is singular if every other move fails low against a bound lowered from its score at reduced depth
+    fn singularity(&mut self, table_move: OxideMove, table_score: OxideScore, depth: Depth, beta: OxideScore, ply: usize) -> Singularity {
+        let singular_beta = OxideScore::new(table_score.centipawns() - self.parameters.singular_margin * depth);
+        self.stack[ply].excluded_move = Some(table_move);
+        let singular_score = self.negamax(singular_beta - OxideScore::new(1), singular_beta, (depth - 1) / 2, ply, false);
+        self.stack[ply].excluded_move = None;
+
+        if singular_score < singular_beta {
+            Singularity::Singular
+        } else if singular_beta >= beta {
+            Singularity::MultiCut(singular_beta)
+        } else {
+            Singularity::NotSingular
+        }
+    }
+
+    // Extension of a move just made at `ply` for giving check or recapturing on the square the previous move captured on
+    fn check_or_recapture_extension(&self, chess_move: OxideMove, ply: usize) -> Depth {
+        if !self.can_extend(ply) {
+            return 0;
+        }
+
+        let recapture = chess_move.is_capture() && ply >= 1 && self.stack[ply - 1].capture
+            && self.stack[ply - 1].current_move.map(|previous| previous.to) == self.stack[ply].current_move.map(|current| current.to);
+        (self.board.in_check() || recapture) as Depth
+    }
+
+    #[inline]
+    fn previous_moves(&self, ply: usize) -> [Option<PieceTo>; 2] {
+        [
+            if ply >= 1 { self.stack[ply - 1].current_move } else { None },
+            if ply >= 2 { self.stack[ply - 2].current_move } else { None },
+        ]
+    }
+
+    fn negamax(&mut self, mut alpha: OxideScore, beta: OxideScore, depth: Depth, ply: usize, allow_null: bool) -> OxideScore {
+        self.pv.clear(ply);
+        if depth <= 0 || ply >= MAX_PLY {
+            return self.quiescence(alpha, beta, ply);
+        }
+
+        self.sel_depth = self.sel_depth.max(ply);
+        if self.poll_stop() {
+            return OxideScore::default();
+        }
+        if self.is_draw() {
+            return OxideScore::DRAW_SCORE;
+        }
+
+        // Singular verification searches exclude the table move so neither trust nor overwrite the node's entry
+        let excluded_move = self.stack[ply].excluded_move;
+        let key = self.board.position().zobrist_key();
+        let table_entry = if excluded_move.is_none() { self.table.probe(key) } else { None }
+            .map(|entry| TranspositionEntry { score: entry.score.to_root_relative(ply as PlyCount), ..entry });
+        if let Some(entry) = table_entry {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower if entry.score >= beta => return entry.score,
+                    Bound::Upper if entry.score <= alpha => return entry.score,
+                    _ => {},
+                }
+            }
+        }
+
+        let in_check = self.board.in_check();
+        let pv_node = beta.centipawns() as i64 - alpha.centipawns() as i64 > 1;
+        let static_eval = if in_check { -INFINITE_SCORE } else { self.evaluator.evaluate(self.board.position()) };
+        let forward_prune = !pv_node && !in_check && excluded_move.is_none();
+
+        // Reverse futility pruning: the static eval is so far above beta that a quiet move is unlikely to bring it back down
+        if forward_prune && depth <= self.parameters.reverse_futility_max_depth && !beta.is_mate()
+            && static_eval.centipawns() - self.parameters.reverse_futility_margin * depth >= beta.centipawns() {
+            return static_eval;
+        }
+
+        // Razoring: far enough below alpha near the horizon that only captures could help
+        if forward_prune && depth <= self.parameters.razoring_max_depth && !alpha.is_mate()
+            && static_eval.centipawns() + self.parameters.razoring_margin * depth < alpha.centipawns() {
+            let score = self.quiescence(alpha, alpha + OxideScore::new(1), ply);
+            if score <= alpha {
+                return score;
+            }
+        }
+
+        if allow_null && forward_prune && depth >= self.parameters.null_move_min_depth && !beta.is_mate() && self.has_non_pawn_material() {
+            if static_eval >= beta {
+                let reduction = self.null_move_reduction(depth, static_eval.centipawns().saturating_sub(beta.centipawns()));
+                self.stack[ply].current_move = None;
+                self.stack[ply].capture = false;
+                self.stack[ply + 1].extensions = self.stack[ply].extensions;
+                let state = self.make_null_move();
+                let null_score = -self.negamax(-beta, -beta + OxideScore::new(1), depth - 1 - reduction, ply + 1, false);
+                self.undo_null_move(state);
+                if self.stopped {
+                    return OxideScore::default();
+                }
+
+                if null_score >= beta {
+                    // Don't trust unproven mates from passing the turn
+                    let null_score = if null_score.is_mate() { beta } else { null_score };
+                    if depth < self.parameters.null_move_verification_depth {
+                        return null_score;
+                    }
+
+                    let verified_score = self.negamax(beta - OxideScore::new(1), beta, depth - reduction, ply, false);
+                    if verified_score >= beta {
+                        return null_score;
+                    }
+                }
+            }
+        }
+
+        let table_move = table_entry.and_then(|entry| entry.best_move);
+        if forward_prune && depth >= self.parameters.probcut_min_depth && !beta.is_mate() {
+            if let Some(score) = self.probcut(beta, static_eval, table_move, depth, ply) {
+                return score;
+            }
+        }
+
+        let side_to_move = self.board.position().side_to_move();
+        let previous_moves = self.previous_moves(ply);
+        let mut move_picker = MovePicker::new(&self.board, table_move, &self.heuristics, previous_moves, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITE_SCORE;
+        let mut best_move = None;
+        let mut searched_quiets = Vec::new();
+        let late_move_count = self.parameters.late_move_count(depth);
+        let prune_late_moves = !pv_node && !in_check && depth <= self.parameters.lmp_max_depth;
+        // Quiet moves can't raise the static eval enough to reach alpha near the horizon
+        let futile_quiets = forward_prune && depth <= self.parameters.futility_max_depth && !alpha.is_mate()
+            && static_eval.centipawns() + self.parameters.futility_margin(depth) <= alpha.centipawns();
+        let mut move_number = 0;
+        while let Some(chess_move) = move_picker.next_move(&self.board, &self.heuristics) {
+            if Some(chess_move) == excluded_move {
+                continue;
+            }
+
+            let quiet = !chess_move.is_capture() && !chess_move.is_promotion();
+            let current_move = piece_to(&self.board, chess_move);
+            let quiet_score = if quiet {
+                if prune_late_moves && best_move.is_some() && searched_quiets.len() >= late_move_count && !best_score.is_mate() {
+                    // Only the losing captures remain worth searching
+                    move_picker.skip_quiets();
+                    continue;
+                }
+                if futile_quiets && best_move.is_some() && !self.gives_check(chess_move) {
+                    continue;
+                }
+                searched_quiets.push((chess_move, current_move));
+                self.heuristics.quiet_score(side_to_move, chess_move, current_move, previous_moves)
+            } else {
+                0
+            };
+
+            let mut extension = 0;
+            if self.can_extend(ply) && Some(chess_move) == table_move && excluded_move.is_none() && depth >= self.parameters.singular_min_depth {
+                if let Some(entry) = table_entry.filter(|entry| entry.bound != Bound::Upper && entry.depth >= depth - self.parameters.singular_table_depth_margin && !entry.score.is_mate()) {
+                    let singularity = self.singularity(chess_move, entry.score, depth, beta, ply);
+                    if self.stopped {
+                        return OxideScore::default();
+                    }
+
+                    match singularity {
+                        Singularity::Singular => extension = 1,
+                        Singularity::MultiCut(score) => return score,
+                        Singularity::NotSingular => {},
+                    }
+                }
+            }
+
+            move_number += 1;
+            self.stack[ply].current_move = Some(current_move);
+            self.stack[ply].capture = chess_move.is_capture();
+            let state = self.make_move(chess_move);
+            if extension == 0 {
+                extension = self.check_or_recapture_extension(chess_move, ply);
+            }
+            self.stack[ply + 1].extensions = self.stack[ply].extensions + extension;
+
+            let new_depth = depth - 1 + extension;
+            let score = if move_number == 1 {
+                -self.negamax(-beta, -alpha, new_depth, ply + 1, true)
+            } else {
+                // Principal variation search: prove the move is worse with a null window, reducing late moves
+                let null_beta = -alpha;
+                let null_alpha = null_beta - OxideScore::new(1);
+                let reduction = if depth >= self.parameters.lmr_min_depth && move_number > self.parameters.lmr_full_depth_moves {
+                    self.late_move_reduction(chess_move, quiet_score, depth, move_number, ply, pv_node)
+                } else {
+                    0
+                };
+
+                let mut score = -self.negamax(null_alpha, null_beta, new_depth - reduction, ply + 1, true);
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(null_alpha, null_beta, new_depth, ply + 1, true);
+                }
+                if score > alpha && score < beta {
+                    score = -self.negamax(-beta, -alpha, new_depth, ply + 1, true);
+                }
+
+                score
+            };
+            self.undo_move(chess_move, state);
+            if self.stopped {
+                return OxideScore::default();
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(chess_move);
+                if score > alpha {
+                    alpha = score;
+                    if pv_node && excluded_move.is_none() {
+                        self.pv.update(ply, chess_move);
+                    }
+                }
+                if alpha >= beta {
+                    if quiet {
+                        self.heuristics.update_quiet_cutoff(side_to_move, (chess_move, current_move), &searched_quiets, previous_moves, depth, ply);
+                    }
+                    break;
+                }
+            }
+        }
+
+        if move_number == 0 {
+            // Every move but the excluded one being illegal isn't mate or stalemate
+            if excluded_move.is_some() {
+                return alpha;
+            }
+
+            return if in_check {
+                OxideScore::new_mated(ply as PlyCount)
+            } else {
+                OxideScore::DRAW_SCORE
+            };
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        if excluded_move.is_none() {
+            self.table.store(key, TranspositionEntry {
+                best_move,
+                score: best_score.to_node_relative(ply as PlyCount),
+                depth,
+                bound,
+            });
+        }
+
+        best_score
+    }
+
+    fn quiescence(&mut self, mut alpha: OxideScore, beta: OxideScore, ply: usize) -> OxideScore {
+        // Lines end at the horizon, captures resolving it aren't part of the principal variation
+        self.pv.clear(ply);
+        self.sel_depth = self.sel_depth.max(ply);
+        if self.poll_stop() {
+            return OxideScore::default();
+        }
+
+        // Every evasion is searched when in check, standing pat isn't an option
+        let in_check = self.board.in_check();
+        let mut best_score = -INFINITE_SCORE;
+        if !in_check {
+            let stand_pat = self.evaluator.evaluate(self.board.position());
+            if stand_pat >= beta || ply >= MAX_PLY {
+                return stand_pat;
+            }
+            if stand_pat > alpha {
+                alpha = stand_pat;
+            }
+            best_score = stand_pat;
+        } else if ply >= MAX_PLY {
+            return self.evaluator.evaluate(self.board.position());
+        }
+
+        let table_move = self.table.probe(self.board.position().zobrist_key()).and_then(|entry| entry.best_move);
+        let mut move_picker = MovePicker::new_quiescence(&self.board, table_move);
+        while let Some(chess_move) = move_picker.next_move(&self.board, &self.heuristics) {
+            let state = self.make_move(chess_move);
+            let score = -self.quiescence(-beta, -alpha, ply + 1);
+            self.undo_move(chess_move, state);
+            if self.stopped {
+                return OxideScore::default();
+            }
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if in_check && best_score == -INFINITE_SCORE {
+            return OxideScore::new_mated(ply as PlyCount);
+        }
+
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::SimpleChessMove;
+    use oxide_interface::game::OxideSquare::*;
+    use evaluation::HandcraftedEvaluator;
+
+    fn thread(fen: &str) -> SearchThread<HandcraftedEvaluator> {
+        let board = OxideBoard::new(OxidePosition::from_fen(fen).unwrap());
+        SearchThread::new(0, board, HandcraftedEvaluator::default(), SearchParameters::default(), Arc::new(TranspositionTable::new(1)), Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)))
+    }
+
+    // Make a move at a ply the way the search does and return its check or recapture extension
+    fn play(thread: &mut SearchThread<HandcraftedEvaluator>, chess_move: OxideMove, ply: usize) -> Depth {
+        thread.stack[ply].current_move = Some(piece_to(&thread.board, chess_move));
+        thread.stack[ply].capture = chess_move.is_capture();
+        thread.make_move(chess_move);
+
+        thread.check_or_recapture_extension(chess_move, ply)
+    }
+
+    #[test]
+    fn check_extension_works() {
+        let mut checking = thread("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(play(&mut checking, OxideMove::new(A1, A8), 0), 1);
+        let mut quiet = thread("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(play(&mut quiet, OxideMove::new(A1, A2), 0), 0);
+        // Discovered checks count too
+        let mut discovery = thread("4k3/8/8/8/8/8/4N3/4RK2 w - - 0 1");
+        assert_eq!(play(&mut discovery, OxideMove::new(E2, C3), 0), 1);
+    }
+
+    #[test]
+    fn recapture_extension_works() {
+        let fen = "4k3/8/n1p5/3p4/1P2P3/8/8/4K3 w - - 0 1";
+        let mut recapture = thread(fen);
+        assert_eq!(play(&mut recapture, OxideMove::new_capture(E4, D5), 0), 0);
+        assert_eq!(play(&mut recapture, OxideMove::new_capture(C6, D5), 1), 1);
+        // Capturing elsewhere or not capturing doesn't extend
+        let mut elsewhere = thread(fen);
+        play(&mut elsewhere, OxideMove::new_capture(E4, D5), 0);
+        assert_eq!(play(&mut elsewhere, OxideMove::new_capture(A6, B4), 1), 0);
+        let mut quiet = thread(fen);
+        play(&mut quiet, OxideMove::new_capture(E4, D5), 0);
+        assert_eq!(play(&mut quiet, OxideMove::new(E8, E7), 1), 0);
+    }
+
+    #[test]
+    fn singularity_works() {
+        let depth = 8;
+        // Only taking the queen keeps the rook
+        let mut singular = thread("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        assert_eq!(singular.singularity(OxideMove::new_capture(D2, D5), OxideScore::new(400), depth, OxideScore::new(300), 0), Singularity::Singular);
+        // A queen up nearly every move beats beta
+        let mut multi_cut = thread("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let singular_beta = OxideScore::new(200 - multi_cut.parameters.singular_margin * depth);
+        assert_eq!(multi_cut.singularity(OxideMove::new(D1, D2), OxideScore::new(200), depth, OxideScore::new(100), 0), Singularity::MultiCut(singular_beta));
+        // Other openings are about as good as the table move
+        let mut not_singular = thread("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(not_singular.singularity(OxideMove::new_double_pawn_push(E2, E4), OxideScore::new(0), depth, OxideScore::new(100), 0), Singularity::NotSingular);
+        // The excluded move is cleared afterwards
+        assert_eq!(not_singular.stack[0].excluded_move, None);
+    }
+
+    #[test]
+    fn extensions_are_capped_per_line() {
+        let mut capped = thread("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        capped.stack[0].extensions = capped.parameters.max_line_extensions;
+        assert!(!capped.can_extend(0));
+        assert_eq!(play(&mut capped, OxideMove::new(A1, A8), 0), 0);
+        capped.stack[0].extensions = capped.parameters.max_line_extensions - 1;
+        assert!(capped.can_extend(0));
+    }
+}
@@ -0,0 +1,332 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use oxide_interface::engine::OxideScore;
+use oxide_interface::game::{OxideMove, OxideSide};
+use interface::engine::PositionalScore;
+use interface::game::Side;
+
+// Moves assumed left in the game when the GUI doesn't say (sudden death)
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// The hard limit can be this many times the optimum time
+const MAXIMUM_TIME_RATIO: u32 = 5;
+// Never plan to use more than this percentage of the remaining time on one move
+const MAXIMUM_TIME_PERCENT: u32 = 80;
+// Iterations changing the best move count fully, earlier changes decay by half each iteration
+const INSTABILITY_DECAY: f64 = 0.5;
+// Extra fraction of the optimum time per unit of best move instability
+const INSTABILITY_FACTOR: f64 = 0.5;
+// Centipawn score drop from the previous iteration that earns the largest extension
+const SCORE_DROP_LIMIT: i32 = 100;
+// Extra fraction of the optimum time for the largest score drop
+const SCORE_DROP_FACTOR: f64 = 0.5;
+// Share of an iteration's nodes spent on the best move above which it clearly dominates
+const DOMINANT_EFFORT: f64 = 0.85;
+// Fraction of the optimum time used when the best move clearly dominates
+const DOMINANT_FACTOR: f64 = 0.5;
+// Instability below which the best move counts as settled (unchanged for the last two iterations)
+const SETTLED_INSTABILITY: f64 = 0.5;
+
+/// Source of the current time, injectable so time management can be tested without sleeping
+pub trait Clock: Send + Sync {
+    /// Time since an arbitrary fixed point
+    fn now(&self) -> Duration;
+}
+
+/// Wall clock time
+pub struct SystemClock {
+    epoch: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// Clock and move counter state sent by the GUI with `go`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeControl {
+    /// White's remaining time (`wtime`)
+    pub white_time: Option<Duration>,
+    /// Black's remaining time (`btime`)
+    pub black_time: Option<Duration>,
+    /// White's increment per move (`winc`)
+    pub white_increment: Duration,
+    /// Black's increment per move (`binc`)
+    pub black_increment: Duration,
+    /// Moves until the next time control (`movestogo`), sudden death if None
+    pub moves_to_go: Option<u32>,
+    /// Exact time to search (`movetime`)
+    pub move_time: Option<Duration>,
+}
+
+impl TimeControl {
+    /// If there is any time limit for a side
+    pub fn is_limited(&self, side: OxideSide) -> bool {
+        self.move_time.is_some() || self.remaining(side).0.is_some()
+    }
+
+    #[inline]
+    fn remaining(&self, side: OxideSide) -> (Option<Duration>, Duration) {
+        if side.is_white() {
+            (self.white_time, self.white_increment)
+        } else {
+            (self.black_time, self.black_increment)
+        }
+    }
+}
+
+/// Decides how long a search should run from the time control and how the search is going
+pub struct TimeManager {
+    clock: Arc<dyn Clock>,
+    start: Duration,
+    optimum: Duration,
+    maximum: Duration,
+    // Whether the optimum can be scaled (it can't with a fixed move time)
+    adaptive: bool,
+    scale: f64,
+    instability: f64,
+    previous_best: Option<(OxideMove, OxideScore)>,
+}
+
+impl TimeManager {
+    /// Budget the time for a move by the side to move starting now, None if the time control has no limits
+    pub fn new(time_control: &TimeControl, side: OxideSide, move_overhead: Duration, clock: Arc<dyn Clock>) -> Option<Self> {
+        let (optimum, maximum, adaptive) = if let Some(move_time) = time_control.move_time {
+            let move_time = move_time.checked_sub(move_overhead).unwrap_or_default();
+            (move_time, move_time, false)
+        } else {
+            let (time, increment) = time_control.remaining(side);
+            let time_left = time?.checked_sub(move_overhead).unwrap_or_default();
+            let moves_to_go = time_control.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            // Increments for the moves after this one will be available by then
+            let budget = (time_left + increment * (moves_to_go - 1)) / moves_to_go;
+            let maximum = (budget * MAXIMUM_TIME_RATIO).min(time_left * MAXIMUM_TIME_PERCENT / 100);
+
+            (budget.min(maximum), maximum, true)
+        };
+
+        Some(Self {
+            start: clock.now(),
+            clock,
+            optimum,
+            maximum,
+            adaptive,
+            scale: 1.0,
+            instability: 0.0,
+            previous_best: None,
+        })
+    }
+
+    /// Time spent since the search started
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now() - self.start
+    }
+
+    /// Time the search should normally take before adjustments
+    #[inline]
+    pub fn optimum(&self) -> Duration {
+        self.optimum
+    }
+
+    /// Hard limit the search must stop at
+    #[inline]
+    pub fn maximum(&self) -> Duration {
+        self.maximum
+    }
+
+    /// Optimum time scaled by how the search is going, never above the hard limit
+    pub fn adjusted_optimum(&self) -> Duration {
+        self.optimum.mul_f64(self.scale).min(self.maximum)
+    }
+
+    /// Account for a completed iteration's best move, its score and share of the iteration's nodes (0 to 1)
+    pub fn record_iteration(&mut self, best_move: OxideMove, score: OxideScore, best_move_effort: f64) {
+        if !self.adaptive {
+            return;
+        }
+
+        self.instability *= INSTABILITY_DECAY;
+        let mut score_drop = 0;
+        if let Some((previous_move, previous_score)) = self.previous_best {
+            if previous_move != best_move {
+                self.instability += 1.0;
+            }
+            if !score.is_mate() && !previous_score.is_mate() {
+                score_drop = (previous_score.centipawns() - score.centipawns()).max(0).min(SCORE_DROP_LIMIT);
+            }
+        }
+        self.previous_best = Some((best_move, score));
+
+        let instability_scale = 1.0 + self.instability * INSTABILITY_FACTOR;
+        let score_drop_scale = 1.0 + SCORE_DROP_FACTOR * score_drop as f64 / SCORE_DROP_LIMIT as f64;
+        let dominance_scale = if best_move_effort >= DOMINANT_EFFORT && self.instability < SETTLED_INSTABILITY { DOMINANT_FACTOR } else { 1.0 };
+        self.scale = instability_scale * score_drop_scale * dominance_scale;
+    }
+
+    /// If another iteration is worth starting (it would likely not finish once the adjusted optimum has passed)
+    #[inline]
+    pub fn should_start_iteration(&self) -> bool {
+        self.elapsed() < self.adjusted_optimum()
+    }
+
+    /// If the search must stop immediately
+    #[inline]
+    pub fn is_out_of_time(&self) -> bool {
+        self.elapsed() >= self.maximum
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+    use interface::game::SimpleChessMove;
+    use oxide_interface::game::OxideSquare::*;
+
+    #[derive(Default)]
+    struct ManualClock {
+        now: Mutex<Duration>,
+    }
+
+    impl ManualClock {
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    fn millis(milliseconds: u64) -> Duration {
+        Duration::from_millis(milliseconds)
+    }
+
+    fn sudden_death(white_time: u64, white_increment: u64) -> TimeControl {
+        TimeControl {
+            white_time: Some(millis(white_time)),
+            black_time: Some(millis(1)),
+            white_increment: millis(white_increment),
+            ..TimeControl::default()
+        }
+    }
+
+    fn manager(time_control: &TimeControl, clock: Arc<ManualClock>) -> TimeManager {
+        TimeManager::new(time_control, OxideSide::White, millis(10), clock).expect("Time control should be limited")
+    }
+
+    #[test]
+    fn no_limits_works() {
+        let clock = Arc::new(ManualClock::default());
+        assert!(TimeManager::new(&TimeControl::default(), OxideSide::White, millis(10), clock.clone()).is_none());
+        let white_only = sudden_death(60_000, 0);
+        assert!(white_only.is_limited(OxideSide::Black));
+        assert!(TimeManager::new(&TimeControl { black_time: None, ..white_only }, OxideSide::Black, millis(10), clock).is_none());
+    }
+
+    #[test]
+    fn move_time_works() {
+        let clock = Arc::new(ManualClock::default());
+        let time_control = TimeControl { move_time: Some(millis(1_000)), ..TimeControl::default() };
+        let mut time = manager(&time_control, clock.clone());
+        assert_eq!(time.optimum(), millis(990));
+        assert_eq!(time.maximum(), millis(990));
+        // Fixed move times don't adapt
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(0), 1.0);
+        assert_eq!(time.adjusted_optimum(), millis(990));
+        clock.advance(millis(989));
+        assert!(time.should_start_iteration());
+        assert!(!time.is_out_of_time());
+        clock.advance(millis(1));
+        assert!(time.is_out_of_time());
+    }
+
+    #[test]
+    fn sudden_death_works() {
+        let clock = Arc::new(ManualClock::default());
+        let time = manager(&sudden_death(60_010, 0), clock.clone());
+        assert_eq!(time.optimum(), millis(2_000));
+        assert_eq!(time.maximum(), millis(10_000));
+        clock.advance(millis(2_000));
+        assert!(!time.should_start_iteration());
+        assert!(!time.is_out_of_time());
+    }
+
+    #[test]
+    fn increment_and_moves_to_go_works() {
+        let clock = Arc::new(ManualClock::default());
+        let time = manager(&TimeControl { moves_to_go: Some(4), ..sudden_death(10_010, 1_000) }, clock.clone());
+        assert_eq!(time.optimum(), millis(3_250));
+        // The last move before the time control can't use everything
+        let time = manager(&TimeControl { moves_to_go: Some(1), ..sudden_death(10_010, 0) }, clock);
+        assert_eq!(time.maximum(), millis(8_000));
+        assert_eq!(time.optimum(), millis(8_000));
+    }
+
+    #[test]
+    fn overhead_exceeding_time_works() {
+        let clock = Arc::new(ManualClock::default());
+        let time = manager(&sudden_death(5, 0), clock);
+        assert_eq!(time.maximum(), Duration::default());
+        assert!(time.is_out_of_time());
+    }
+
+    #[test]
+    fn instability_extends() {
+        let clock = Arc::new(ManualClock::default());
+        let mut time = manager(&sudden_death(60_010, 0), clock);
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(20), 0.5);
+        assert_eq!(time.adjusted_optimum(), time.optimum());
+        time.record_iteration(OxideMove::new(B1, C3), OxideScore::new(20), 0.5);
+        assert_eq!(time.adjusted_optimum(), millis(3_000));
+        // Extensions fade once the best move settles
+        time.record_iteration(OxideMove::new(B1, C3), OxideScore::new(20), 0.5);
+        assert_eq!(time.adjusted_optimum(), millis(2_500));
+    }
+
+    #[test]
+    fn score_drop_extends() {
+        let clock = Arc::new(ManualClock::default());
+        let mut time = manager(&sudden_death(60_010, 0), clock);
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(20), 0.5);
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(-30), 0.5);
+        assert_eq!(time.adjusted_optimum(), millis(2_500));
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(-500), 0.5);
+        assert_eq!(time.adjusted_optimum(), millis(3_000));
+        // Improving scores don't
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(0), 0.5);
+        assert_eq!(time.adjusted_optimum(), time.optimum());
+    }
+
+    #[test]
+    fn dominant_move_shortens() {
+        let clock = Arc::new(ManualClock::default());
+        let mut time = manager(&sudden_death(60_010, 0), clock.clone());
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(20), 0.95);
+        assert_eq!(time.adjusted_optimum(), millis(1_000));
+        clock.advance(millis(1_000));
+        assert!(!time.should_start_iteration());
+    }
+
+    #[test]
+    fn adjusted_optimum_is_capped() {
+        let clock = Arc::new(ManualClock::default());
+        let mut time = manager(&TimeControl { moves_to_go: Some(1), ..sudden_death(10_010, 0) }, clock);
+        time.record_iteration(OxideMove::new(G1, F3), OxideScore::new(20), 0.5);
+        time.record_iteration(OxideMove::new(B1, C3), OxideScore::new(-200), 0.5);
+        assert_eq!(time.adjusted_optimum(), time.maximum());
+    }
+}
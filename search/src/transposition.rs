@@ -0,0 +1,294 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::mem::size_of;
+use oxide_interface::game::OxideMove;
+use oxide_interface::engine::OxideScore;
+use interface::engine::PositionalScore;
+use crate::types::Depth;
+
+/// Size of the transposition table when not configured
+pub const DEFAULT_HASH_MEGABYTES: usize = 16;
+const BYTES_PER_MEGABYTE: usize = 1024 * 1024;
+// Generation is stored in the top 6 bits of an entry
+const GENERATION_MASK: u8 = 0x3F;
+// Slots sampled to estimate how full the table is
+const HASHFULL_SAMPLE: usize = 1000;
+
+/// What a stored score says about the true score of a position
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bound {
+    /// The score is exact (every move was searched inside the window)
+    Exact,
+    /// The score failed high, the true score is at least this good
+    Lower,
+    /// The score failed low, the true score is at most this good
+    Upper,
+}
+
+impl Bound {
+    // Never encode as zero so a zeroed slot can't be mistaken for an entry
+    #[inline]
+    fn to_bits(self) -> u64 {
+        match self {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        }
+    }
+    #[inline]
+    fn from_bits(bits: u64) -> Option<Self> {
+        match bits {
+            1 => Some(Bound::Exact),
+            2 => Some(Bound::Lower),
+            3 => Some(Bound::Upper),
+            _ => None,
+        }
+    }
+}
+
+/// A previously searched position's result
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TranspositionEntry {
+    pub best_move: Option<OxideMove>,
+    pub score: OxideScore,
+    pub depth: Depth,
+    pub bound: Bound,
+}
+
+impl TranspositionEntry {
+    // Layout: 16 bits move, 32 bits score, 8 bits depth, 2 bits bound, 6 bits generation
+    #[inline]
+    fn pack(&self, generation: u8) -> u64 {
+        let packed_move = self.best_move.map_or(0, |m| m.to_packed()) as u64;
+        let score = self.score.centipawns() as u32 as u64;
+        let depth = self.depth.max(0).min(u8::MAX as Depth) as u64;
+
+        packed_move | score << 16 | depth << 48 | self.bound.to_bits() << 56 | ((generation & GENERATION_MASK) as u64) << 58
+    }
+    #[inline]
+    fn unpack(data: u64) -> Option<Self> {
+        Some(Self {
+            best_move: OxideMove::from_packed(data as u16),
+            score: OxideScore::new((data >> 16) as u32 as i32),
+            depth: (data >> 48 & 0xFF) as Depth,
+            bound: Bound::from_bits(data >> 56 & 0x3)?,
+        })
+    }
+}
+
+#[inline]
+fn generation_of(data: u64) -> u8 {
+    (data >> 58) as u8 & GENERATION_MASK
+}
+
+// A slot is written as (key ^ data, data) so a torn write from another thread fails validation on probe
+#[derive(Default)]
+struct TranspositionSlot {
+    checked_key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Hash table of searched positions shared lock-free between all search threads
+pub struct TranspositionTable {
+    slots: Box<[TranspositionSlot]>,
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// Create a table using roughly the given number of megabytes
+    pub fn new(megabytes: usize) -> Self {
+        let slot_count = (megabytes * BYTES_PER_MEGABYTE / size_of::<TranspositionSlot>()).max(1);
+        let slots = (0..slot_count)
+            .map(|_| TranspositionSlot::default())
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Self {
+            slots,
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    /// Number of entries the table can hold
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[inline]
+    fn slot(&self, key: u64) -> &TranspositionSlot {
+        // Map the key onto the table with a multiply instead of a modulo
+        let index = ((key as u128 * self.slots.len() as u128) >> 64) as usize;
+
+        &self.slots[index]
+    }
+
+    /// Look up a position by its zobrist key
+    pub fn probe(&self, key: u64) -> Option<TranspositionEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let checked_key = slot.checked_key.load(Ordering::Relaxed);
+
+        if data != 0 && checked_key ^ data == key {
+            TranspositionEntry::unpack(data)
+        } else {
+            None
+        }
+    }
+
+    /// Store a search result for a position, keeping the existing entry if it is more valuable
+    pub fn store(&self, key: u64, entry: TranspositionEntry) {
+        let slot = self.slot(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.checked_key.load(Ordering::Relaxed) ^ old_data;
+        let old_entry = if old_data != 0 { TranspositionEntry::unpack(old_data) } else { None };
+
+        let entry = match old_entry {
+            Some(old_entry) if old_key == key => {
+                // Same position: prefer deeper results from this search unless the new one is exact
+                if entry.bound != Bound::Exact && generation_of(old_data) == generation && old_entry.depth > entry.depth + 2 {
+                    return;
+                }
+                // Keep the old move if this search didn't find one
+                TranspositionEntry {
+                    best_move: entry.best_move.or(old_entry.best_move),
+                    ..entry
+                }
+            },
+            // Different position from this search that was searched much deeper
+            Some(old_entry) if generation_of(old_data) == generation && old_entry.depth > entry.depth + 4 => return,
+            _ => entry,
+        };
+
+        let data = entry.pack(generation);
+        slot.checked_key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Permille of the table used by the current search (estimated from a sample of slots)
+    pub fn hashfull(&self) -> usize {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let sample = &self.slots[..self.slots.len().min(HASHFULL_SAMPLE)];
+        let used = sample.iter()
+            .map(|slot| slot.data.load(Ordering::Relaxed))
+            .filter(|&data| data != 0 && generation_of(data) == generation)
+            .count();
+
+        used * 1000 / sample.len()
+    }
+
+    /// Mark the start of a new search so entries from older searches are replaced first
+    pub fn new_search(&self) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        self.generation.store(generation.wrapping_add(1) & GENERATION_MASK, Ordering::Relaxed);
+    }
+
+    /// Remove every entry from the table
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.checked_key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::ChessMove;
+    use oxide_interface::game::OxideSquare::*;
+
+    fn entry(depth: Depth, bound: Bound) -> TranspositionEntry {
+        TranspositionEntry {
+            best_move: Some(OxideMove::new_double_pawn_push(E2, E4)),
+            score: OxideScore::new(-35),
+            depth,
+            bound,
+        }
+    }
+
+    #[test]
+    fn store_and_probe_works() {
+        let table = TranspositionTable::new(1);
+        let key = 0x1234_5678_9ABC_DEF0;
+        assert_eq!(table.probe(key), None);
+        table.store(key, entry(7, Bound::Lower));
+        assert_eq!(table.probe(key), Some(entry(7, Bound::Lower)));
+    }
+
+    #[test]
+    fn probe_rejects_other_keys() {
+        let table = TranspositionTable::new(1);
+        table.store(0x1234_5678_9ABC_DEF0, entry(7, Bound::Exact));
+        assert_eq!(table.probe(0x1234_5678_9ABC_DEF1), None);
+    }
+
+    #[test]
+    fn probe_rejects_torn_writes() {
+        let table = TranspositionTable::new(1);
+        let key = 0xDEAD_BEEF_0000_0001;
+        table.store(key, entry(7, Bound::Exact));
+        // Simulate another thread overwriting only the data half of the slot
+        let slot = table.slot(key);
+        slot.data.store(entry(3, Bound::Upper).pack(0), Ordering::Relaxed);
+        assert_eq!(table.probe(key), None);
+    }
+
+    #[test]
+    fn store_keeps_deeper_entries() {
+        let table = TranspositionTable::new(1);
+        let key = 0x0F0F_0F0F_0F0F_0F0F;
+        table.store(key, entry(12, Bound::Lower));
+        table.store(key, entry(2, Bound::Upper));
+        assert_eq!(table.probe(key), Some(entry(12, Bound::Lower)));
+        // Exact scores always replace
+        table.store(key, entry(2, Bound::Exact));
+        assert_eq!(table.probe(key), Some(entry(2, Bound::Exact)));
+        // Older searches are always replaced
+        table.store(key, entry(12, Bound::Lower));
+        table.new_search();
+        table.store(key, entry(1, Bound::Upper));
+        assert_eq!(table.probe(key), Some(entry(1, Bound::Upper)));
+    }
+
+    #[test]
+    fn store_keeps_previous_move() {
+        let table = TranspositionTable::new(1);
+        let key = 42;
+        table.store(key, entry(4, Bound::Lower));
+        table.store(key, TranspositionEntry { best_move: None, ..entry(5, Bound::Upper) });
+        assert_eq!(table.probe(key).and_then(|e| e.best_move), Some(OxideMove::new_double_pawn_push(E2, E4)));
+    }
+
+    #[test]
+    fn clear_works() {
+        let table = TranspositionTable::new(1);
+        table.store(42, entry(4, Bound::Exact));
+        table.clear();
+        assert_eq!(table.probe(42), None);
+    }
+
+    #[test]
+    fn hashfull_works() {
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.hashfull(), 0);
+        // Keys landing in each of the first 500 slots
+        for index in 0..500u128 {
+            let key = ((index << 64) / table.capacity() as u128 + 1) as u64;
+            table.store(key, entry(4, Bound::Exact));
+        }
+        assert_eq!(table.hashfull(), 500);
+        // Entries from earlier searches will be replaced so don't count
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+    }
+
+    #[test]
+    fn mate_scores_round_trip() {
+        let table = TranspositionTable::new(1);
+        let mate = TranspositionEntry { score: -OxideScore::MATE_SCORE, ..entry(9, Bound::Exact) };
+        table.store(42, mate);
+        assert_eq!(table.probe(42), Some(mate));
+    }
+}
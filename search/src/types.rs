@@ -0,0 +1,5 @@
+/// Remaining search depth in plies (signed so reductions can go below zero)
+pub type Depth = i32;
+
+/// Maximum number of plies a single search line can reach
+pub const MAX_PLY: usize = 128;
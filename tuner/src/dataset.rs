@@ -0,0 +1,115 @@
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use interface::game::Position;
+use oxide_interface::engine::OxidePosition;
+
+/// A position labelled with the result of the game it was played in
+#[derive(Copy, Clone, Debug)]
+pub struct TuningPosition {
+    pub position: OxidePosition,
+    /// Result from white's perspective, 1 for a win, 0.5 for a draw and 0 for a loss
+    pub result: f64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DatasetError {
+    Io(String), // Reading the file failed
+    MissingResult(usize), // Line has neither a c9 opcode nor a semicolon separated result (line number)
+    InvalidResult(usize, String), // Result isn't 1-0, 0-1, 1/2-1/2 or a number from 0 to 1 (line number, result)
+    InvalidFen(usize, String), // Position couldn't be parsed (line number, reason)
+}
+
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            DatasetError::Io(reason) => write!(f, "Failed to read positions ({})", reason),
+            DatasetError::MissingResult(line) => write!(f, "Line {} has no result", line),
+            DatasetError::InvalidResult(line, result) => write!(f, "Line {} has an invalid result {}", line, result),
+            DatasetError::InvalidFen(line, reason) => write!(f, "Line {} has an invalid position ({})", line, reason),
+        }
+    }
+}
+
+impl Error for DatasetError {}
+
+/// Parse a game result as a score from white's perspective
+pub fn parse_result(result: &str) -> Option<f64> {
+    match result.trim().trim_matches('"') {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" | "1/2" => Some(0.5),
+        result => result.parse::<f64>().ok().filter(|result| (0.0..=1.0).contains(result)),
+    }
+}
+
+/// Parse an EPD line with a `c9` result opcode or a `FEN;result` line, blank lines and `#` comments are skipped
+pub fn parse_line(line: &str, line_number: usize) -> Result<Option<TuningPosition>, DatasetError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (fen, result) = if let Some(opcode) = line.find(" c9 ") {
+        // EPD only has the first four FEN fields before its opcodes
+        let fen = line.split_ascii_whitespace().take(4).collect::<Vec<_>>().join(" ");
+        let result = line[opcode + 4..].split(';').next().unwrap_or_default();
+        (fen, result)
+    } else {
+        let mut fields = line.rsplitn(2, ';');
+        let result = fields.next().unwrap_or_default();
+        let fen = fields.next().ok_or(DatasetError::MissingResult(line_number))?;
+        (fen.trim().to_string(), result)
+    };
+
+    let result = parse_result(result).ok_or_else(|| DatasetError::InvalidResult(line_number, result.trim().to_string()))?;
+    let position = OxidePosition::from_fen(&fen).map_err(|error| DatasetError::InvalidFen(line_number, format!("{:?}", error)))?;
+
+    Ok(Some(TuningPosition { position, result }))
+}
+
+/// Read every labelled position in a file
+pub fn load_positions<P: AsRef<Path>>(path: P) -> Result<Vec<TuningPosition>, DatasetError> {
+    let contents = fs::read_to_string(path).map_err(|error| DatasetError::Io(error.to_string()))?;
+
+    contents.lines()
+        .enumerate()
+        .filter_map(|(index, line)| parse_line(line, index + 1).transpose())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_result_works() {
+        assert_eq!(parse_result("1-0"), Some(1.0));
+        assert_eq!(parse_result(" \"0-1\""), Some(0.0));
+        assert_eq!(parse_result("1/2-1/2"), Some(0.5));
+        assert_eq!(parse_result("0.5"), Some(0.5));
+        assert_eq!(parse_result("1.5"), None);
+        assert_eq!(parse_result("*"), None);
+    }
+
+    #[test]
+    fn parse_line_works() {
+        let epd = parse_line("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 \"1/2-1/2\";", 1).unwrap().unwrap();
+        assert_eq!(epd.result, 0.5);
+        assert_eq!(epd.position.to_fen(), OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap().to_fen());
+
+        let with_opcodes = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - bm e4; c9 \"1-0\"; id \"pawn\";", 2).unwrap().unwrap();
+        assert_eq!(with_opcodes.result, 1.0);
+
+        let semicolon = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1;0.0", 3).unwrap().unwrap();
+        assert_eq!(semicolon.result, 0.0);
+        assert_eq!(semicolon.position.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        assert_eq!(parse_line("", 4).unwrap().map(|position| position.result), None);
+        assert_eq!(parse_line("# comment", 5).unwrap().map(|position| position.result), None);
+        assert_eq!(parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 6).unwrap_err(), DatasetError::MissingResult(6));
+        assert_eq!(parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1; 2-0", 7).unwrap_err(), DatasetError::InvalidResult(7, "2-0".to_string()));
+        assert!(matches!(parse_line("4k3/8/8/8/8/8/4X3/4K3 w - - 0 1;1-0", 8), Err(DatasetError::InvalidFen(8, _))));
+    }
+}
@@ -0,0 +1,9 @@
+mod dataset;
+mod resolve;
+mod texel;
+mod source;
+
+pub use dataset::{TuningPosition, DatasetError, load_positions, parse_line, parse_result};
+pub use resolve::{resolve, MAX_RESOLVE_PLY};
+pub use texel::{Adam, Tuner, TunerOptions, sigmoid, evaluation_error, fit_k};
+pub use source::to_rust_source;
@@ -0,0 +1,109 @@
+use std::env;
+use std::fs;
+use std::process;
+use evaluation::{EvaluationParameters, HandcraftedEvaluator};
+use tuner::{Tuner, TunerOptions, TuningPosition, load_positions, resolve, to_rust_source};
+
+const USAGE: &str = "Usage: tuner <positions> [--parameters <file>] [--iterations <count>] [--learning-rate <centipawns>] [--k <constant>] [--threads <count>] [--output <file>] [--rust]\n       tuner --dump";
+const DEFAULT_ITERATIONS: usize = 1000;
+// How often the tuned values are written out during a run
+const OUTPUT_INTERVAL: usize = 10;
+
+struct Arguments {
+    path: String,
+    parameters: Option<String>,
+    iterations: usize,
+    options: TunerOptions,
+    output: Option<String>,
+    // Write Rust source instead of a parameter file
+    rust: bool,
+}
+
+fn parse_arguments() -> Result<Arguments, String> {
+    let mut arguments = env::args().skip(1);
+    let mut path = None;
+    let mut parameters = None;
+    let mut iterations = DEFAULT_ITERATIONS;
+    let mut options = TunerOptions::default();
+    let mut output = None;
+    let mut rust = false;
+    while let Some(argument) = arguments.next() {
+        let mut value = || arguments.next().ok_or(format!("Missing value for {}", argument));
+        match argument.as_str() {
+            "--parameters" => parameters = Some(value()?),
+            "--iterations" => iterations = value()?.parse().map_err(|_| "Invalid iteration count".to_string())?,
+            "--learning-rate" => options.learning_rate = value()?.parse().map_err(|_| "Invalid learning rate".to_string())?,
+            "--k" => options.k = Some(value()?.parse().map_err(|_| "Invalid K".to_string())?),
+            "--threads" => options.threads = value()?.parse().map_err(|_| "Invalid thread count".to_string())?,
+            "--output" => output = Some(value()?),
+            "--rust" => rust = true,
+            _ if path.is_none() && !argument.starts_with("--") => path = Some(argument),
+            _ => return Err(format!("Unexpected argument {}", argument)),
+        }
+    }
+
+    Ok(Arguments {
+        path: path.ok_or_else(|| "Missing positions file".to_string())?,
+        parameters,
+        iterations,
+        options,
+        output,
+        rust,
+    })
+}
+
+fn write_parameters(parameters: &EvaluationParameters, arguments: &Arguments) {
+    let source = if arguments.rust {
+        to_rust_source(parameters)
+    } else {
+        parameters.to_toml()
+    };
+    match &arguments.output {
+        Some(path) => if let Err(error) = fs::write(path, source) {
+            eprintln!("Failed to write {} ({})", path, error);
+        },
+        None => print!("{}", source),
+    }
+}
+
+fn main() {
+    if env::args().nth(1).as_deref() == Some("--dump") {
+        print!("{}", EvaluationParameters::default().to_toml());
+        return;
+    }
+
+    let arguments = parse_arguments().unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(1);
+    });
+    let positions = load_positions(&arguments.path).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    let parameters = match &arguments.parameters {
+        Some(path) => EvaluationParameters::load(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        }),
+        None => EvaluationParameters::default(),
+    };
+    let mut evaluator = HandcraftedEvaluator::new(parameters);
+    let positions = positions.into_iter()
+        .map(|position| TuningPosition { position: resolve(&position.position, &mut evaluator), ..position })
+        .collect::<Vec<_>>();
+    eprintln!("Loaded and resolved {} positions", positions.len());
+
+    let mut tuner = Tuner::new(positions, parameters, arguments.options);
+    eprintln!("K {:.6} error {:.8}", tuner.k(), tuner.error());
+    for iteration in 1..=arguments.iterations {
+        let error = tuner.step();
+        eprintln!("Iteration {} error {:.8}", iteration, error);
+        // Only the final values go to stdout
+        if iteration % OUTPUT_INTERVAL == 0 && arguments.output.is_some() {
+            write_parameters(&tuner.parameters(), &arguments);
+        }
+    }
+
+    write_parameters(&tuner.parameters(), &arguments);
+}
@@ -0,0 +1,77 @@
+use oxide_interface::engine::{OxideBoard, OxidePosition};
+use interface::engine::{Board, Evaluator, PositionalScore};
+use move_gen::{capture_moves, evasion_moves};
+use evaluation::HandcraftedEvaluator;
+
+/// Deepest capture sequence followed when resolving a position
+pub const MAX_RESOLVE_PLY: usize = 32;
+
+const MATE_SCORE: i32 = 30_000;
+
+// Quiescence search over captures (and evasions in check) returning its score with the position its principal line ends at
+fn quiescence(board: &mut OxideBoard, evaluator: &mut HandcraftedEvaluator, mut alpha: i32, beta: i32, ply: usize) -> (i32, OxidePosition) {
+    let in_check = board.in_check();
+    let mut best = (-MATE_SCORE + ply as i32, *board.position());
+    if !in_check || ply >= MAX_RESOLVE_PLY {
+        let stand_pat = evaluator.evaluate(board.position()).centipawns();
+        if stand_pat >= beta || ply >= MAX_RESOLVE_PLY {
+            return (stand_pat, *board.position());
+        }
+        alpha = alpha.max(stand_pat);
+        best.0 = stand_pat;
+    }
+
+    let mut moves = if in_check {
+        evasion_moves(board).collect::<Vec<_>>()
+    } else {
+        capture_moves(board).collect::<Vec<_>>()
+    };
+    moves.retain(|chess_move| board.is_legal(chess_move));
+    for chess_move in moves {
+        let state = board.make_move_unchecked(chess_move);
+        let (score, leaf) = quiescence(board, evaluator, -beta, -alpha, ply + 1);
+        board.undo_move_unchecked(chess_move, state);
+
+        if -score > best.0 {
+            best = (-score, leaf);
+            alpha = alpha.max(-score);
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+
+    best
+}
+
+/// The quiet position a quiescence search from `position` settles on, so the evaluation isn't tuned on hanging pieces
+pub fn resolve(position: &OxidePosition, evaluator: &mut HandcraftedEvaluator) -> OxidePosition {
+    let mut board = OxideBoard::new(*position);
+
+    quiescence(&mut board, evaluator, -MATE_SCORE, MATE_SCORE, 0).1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+
+    #[test]
+    fn quiet_positions_resolve_to_themselves() {
+        let mut evaluator = HandcraftedEvaluator::default();
+        for fen in &["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"] {
+            let position = OxidePosition::from_fen(fen).unwrap();
+            assert_eq!(resolve(&position, &mut evaluator).to_fen(), *fen);
+        }
+    }
+
+    #[test]
+    fn hanging_pieces_are_captured() {
+        let mut evaluator = HandcraftedEvaluator::default();
+        let position = OxidePosition::from_fen("4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(resolve(&position, &mut evaluator).to_fen(), "4k3/8/8/3P4/8/8/8/4K3 b - - 0 1");
+        // Taking a defended pawn with the queen loses it, so the position is already quiet
+        let position = OxidePosition::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert_eq!(resolve(&position, &mut evaluator).to_fen(), "4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1");
+    }
+}
@@ -0,0 +1,131 @@
+use oxide_interface::engine::TaperedScore;
+use evaluation::{EvaluationParameters, ParameterVisitor};
+
+// Longer tables are wrapped onto several lines
+const MAX_INLINE_ENTRIES: usize = 15;
+const ENTRIES_PER_LINE: usize = 7;
+const FIELD_INDENT: &str = "            ";
+const ENTRY_INDENT: &str = "                ";
+// Square table columns fit at least a negative two digit value
+const MIN_COLUMN_WIDTH: usize = 3;
+
+fn score(score: &TaperedScore) -> String {
+    format!("s({}, {})", score.midgame(), score.endgame())
+}
+
+// Writes each field as it would appear in the `Default` implementation of `EvaluationParameters`, keeping the material
+// and square tables aside since the compiled-in ones live in `oxide_interface::engine::psqt`
+#[derive(Default)]
+struct RustSource {
+    fields: String,
+    piece_values: Vec<TaperedScore>,
+    piece_squares: Vec<Vec<TaperedScore>>,
+}
+
+impl RustSource {
+    fn field(&mut self, name: &str, value: String) {
+        self.fields.push_str(&format!("{}{}: {},\n", FIELD_INDENT, name, value));
+    }
+
+    fn table(&mut self, name: &str, entries: Vec<String>) {
+        if entries.len() <= MAX_INLINE_ENTRIES {
+            return self.field(name, format!("[{}]", entries.join(", ")));
+        }
+
+        self.fields.push_str(&format!("{}{}: [\n", FIELD_INDENT, name));
+        for line in entries.chunks(ENTRIES_PER_LINE) {
+            self.fields.push_str(&format!("{}{},\n", ENTRY_INDENT, line.join(", ")));
+        }
+        self.fields.push_str(&format!("{}],\n", FIELD_INDENT));
+    }
+}
+
+impl ParameterVisitor for RustSource {
+    fn value(&mut self, name: &'static str, value: &mut i32) {
+        self.field(name, value.to_string());
+    }
+
+    fn values(&mut self, name: &'static str, values: &mut [i32]) {
+        self.table(name, values.iter().map(i32::to_string).collect());
+    }
+
+    fn score(&mut self, name: &'static str, value: &mut TaperedScore) {
+        self.field(name, score(value));
+    }
+
+    fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]) {
+        if name == "piece_values" {
+            self.piece_values = scores.to_vec();
+        } else if name.ends_with("_squares") {
+            self.piece_squares.push(scores.to_vec());
+        } else {
+            self.table(name, scores.iter().map(score).collect());
+        }
+    }
+}
+
+fn piece_values(name: &str, values: &[i32]) -> String {
+    format!("pub const {}: [i32; 6] = [{}];", name, values.iter().map(i32::to_string).collect::<Vec<_>>().join(", "))
+}
+
+// A square table per piece laid out like a board, each column right aligned
+fn square_tables(name: &str, tables: &[Vec<i32>]) -> String {
+    let mut source = format!("pub const {}: [[i32; 64]; 6] = [\n", name);
+    for table in tables {
+        let widths = (0..8)
+            .map(|file| table.iter().skip(file).step_by(8).map(|value| value.to_string().len()).fold(MIN_COLUMN_WIDTH, usize::max))
+            .collect::<Vec<_>>();
+        source.push_str("    [\n");
+        for rank in table.chunks(8) {
+            let entries = rank.iter().zip(&widths).map(|(value, &width)| format!("{:>1$}", value, width)).collect::<Vec<_>>();
+            source.push_str(&format!("        {},\n", entries.join(", ")));
+        }
+        source.push_str("    ],\n");
+    }
+    source.push_str("];");
+
+    source
+}
+
+/// The `Default` implementation of `EvaluationParameters` followed by the material and square tables of
+/// `oxide_interface::engine::psqt`, each separated by a blank line, to paste over the compiled-in ones
+pub fn to_rust_source(parameters: &EvaluationParameters) -> String {
+    let mut source = RustSource::default();
+    let mut parameters = *parameters;
+    parameters.visit(&mut source);
+
+    let default = format!(
+        "impl Default for EvaluationParameters {{\n    fn default() -> Self {{\n        Self {{\n{}{}piece_values: compiled_piece_values(),\n{}piece_squares: compiled_piece_squares(),\n        }}\n    }}\n}}",
+        source.fields, FIELD_INDENT, FIELD_INDENT,
+    );
+    let midgame = |scores: &[TaperedScore]| scores.iter().map(|score| score.midgame()).collect::<Vec<_>>();
+    let endgame = |scores: &[TaperedScore]| scores.iter().map(|score| score.endgame()).collect::<Vec<_>>();
+
+    [
+        default,
+        piece_values("MIDGAME_PIECE_VALUES", &midgame(&source.piece_values)),
+        piece_values("ENDGAME_PIECE_VALUES", &endgame(&source.piece_values)),
+        square_tables("MIDGAME_TABLES", &source.piece_squares.iter().map(|table| midgame(table)).collect::<Vec<_>>()),
+        square_tables("ENDGAME_TABLES", &source.piece_squares.iter().map(|table| endgame(table)).collect::<Vec<_>>()),
+    ].join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_source_matches_parameters() {
+        let source = to_rust_source(&EvaluationParameters::default());
+        assert!(source.contains("            blocked_passed_pawn_scale: 160,\n"));
+        assert!(source.contains("            king_attack_units: [2, 2, 3, 5],\n"));
+        assert!(source.contains("pub const MIDGAME_PIECE_VALUES: [i32; 6] = [82, 337, 365, 477, 1025, 0];"));
+
+        let sections = source.trim_end().split("\n\n").collect::<Vec<_>>();
+        assert_eq!(sections.len(), 5);
+        assert!(include_str!("../../evaluation/src/parameters.rs").contains(sections[0]));
+        for section in &sections[1..] {
+            assert!(include_str!("../../oxide-interface/src/engine/psqt.rs").contains(section));
+        }
+    }
+}
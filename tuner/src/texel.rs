@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use interface::game::Side;
+use oxide_interface::engine::{TaperedScore, MAX_PHASE};
+use oxide_interface::game::OxideSide;
+use evaluation::{EvaluationParameters, EvaluationTerm, EvaluationTracer, HandcraftedEvaluator, NoTrace, ParameterVisitor};
+use crate::dataset::TuningPosition;
+
+// Range searched when fitting the scaling constant
+const MAX_K: f64 = 4.0;
+const K_SEARCH_ITERATIONS: usize = 100;
+
+/// Expected result from white's perspective for a white relative centipawn score
+#[inline]
+pub fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+fn white_scores(positions: &[TuningPosition], parameters: EvaluationParameters) -> Vec<f64> {
+    let mut evaluator = HandcraftedEvaluator::new(parameters);
+
+    positions.iter().map(|position| evaluator.evaluate_with(&position.position, &mut NoTrace) as f64).collect()
+}
+
+fn mean_squared_error(positions: &[TuningPosition], scores: &[f64], k: f64) -> f64 {
+    let total = positions.iter()
+        .zip(scores)
+        .map(|(position, &score)| (position.result - sigmoid(score, k)).powi(2))
+        .sum::<f64>();
+
+    total / positions.len().max(1) as f64
+}
+
+/// Mean squared difference between the results and the results predicted by evaluating with `parameters`
+pub fn evaluation_error(positions: &[TuningPosition], parameters: EvaluationParameters, k: f64) -> f64 {
+    mean_squared_error(positions, &white_scores(positions, parameters), k)
+}
+
+/// The scaling constant that best maps evaluations with `parameters` to results
+pub fn fit_k(positions: &[TuningPosition], parameters: EvaluationParameters) -> f64 {
+    let scores = white_scores(positions, parameters);
+    let (mut low, mut high) = (0.0, MAX_K);
+    // The error is unimodal in K so a ternary search converges on the minimum
+    for _ in 0..K_SEARCH_ITERATIONS {
+        let lower_third = low + (high - low) / 3.0;
+        let upper_third = high - (high - low) / 3.0;
+        if mean_squared_error(positions, &scores, lower_third) < mean_squared_error(positions, &scores, upper_third) {
+            high = upper_third;
+        } else {
+            low = lower_third;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+// Where each field's weights start in `EvaluationParameters::to_values`
+fn value_offsets() -> HashMap<&'static str, usize> {
+    struct Offsets(HashMap<&'static str, usize>, usize);
+
+    impl ParameterVisitor for Offsets {
+        fn value(&mut self, name: &'static str, _: &mut i32) {
+            self.0.insert(name, self.1);
+            self.1 += 1;
+        }
+
+        fn values(&mut self, name: &'static str, values: &mut [i32]) {
+            self.0.insert(name, self.1);
+            self.1 += values.len();
+        }
+
+        fn score(&mut self, name: &'static str, _: &mut TaperedScore) {
+            self.0.insert(name, self.1);
+            self.1 += 2;
+        }
+
+        fn scores(&mut self, name: &'static str, scores: &mut [TaperedScore]) {
+            self.0.insert(name, self.1);
+            self.1 += 2 * scores.len();
+        }
+    }
+
+    let mut offsets = Offsets(HashMap::new(), 0);
+    EvaluationParameters::default().visit(&mut offsets);
+
+    offsets.0
+}
+
+// How fast the white relative midgame and endgame scores of one evaluation change with each weight they used
+struct Coefficients<'a> {
+    offsets: &'a HashMap<&'static str, usize>,
+    phase: i32,
+    rates: Vec<(usize, f64, f64)>,
+}
+
+impl<'a> Coefficients<'a> {
+    fn new(offsets: &'a HashMap<&'static str, usize>) -> Self {
+        Self {
+            offsets,
+            phase: MAX_PHASE,
+            rates: Vec::new(),
+        }
+    }
+
+    // How fast the tapered score changes with each value it used
+    fn tapered_rates(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let phase = self.phase.max(0).min(MAX_PHASE) as f64;
+        let max_phase = MAX_PHASE as f64;
+
+        self.rates.iter().map(move |&(index, midgame, endgame)| (index, (midgame * phase + endgame * (max_phase - phase)) / max_phase))
+    }
+}
+
+impl EvaluationTracer for Coefficients<'_> {
+    const ENABLED: bool = true;
+
+    #[inline]
+    fn record(&mut self, _: EvaluationTerm, _: OxideSide, _: TaperedScore) {}
+
+    #[inline]
+    fn record_phase(&mut self, phase: i32) {
+        self.phase = phase;
+    }
+
+    fn record_score_weight(&mut self, name: &'static str, index: usize, side: OxideSide, count: f64) {
+        let count = if side.is_white() { count } else { -count };
+        let offset = self.offsets[name] + 2 * index;
+        self.rates.push((offset, count, 0.0));
+        self.rates.push((offset + 1, 0.0, count));
+    }
+
+    fn record_value_weight(&mut self, name: &'static str, index: usize, side: OxideSide, midgame: f64, endgame: f64) {
+        let sign = if side.is_white() { 1.0 } else { -1.0 };
+        self.rates.push((self.offsets[name] + index, sign * midgame, sign * endgame));
+    }
+}
+
+/// Adam optimizer keeping a running mean and variance of each parameter's gradient
+#[derive(Clone, Debug)]
+pub struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    moments: Vec<f64>,
+    velocities: Vec<f64>,
+    steps: i32,
+}
+
+impl Adam {
+    pub fn new(parameter_count: usize, learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            moments: vec![0.0; parameter_count],
+            velocities: vec![0.0; parameter_count],
+            steps: 0,
+        }
+    }
+
+    /// Move each value against its gradient
+    pub fn step(&mut self, values: &mut [f64], gradient: &[f64]) {
+        debug_assert_eq!(values.len(), gradient.len());
+        self.steps += 1;
+        let moment_correction = 1.0 - self.beta1.powi(self.steps);
+        let velocity_correction = 1.0 - self.beta2.powi(self.steps);
+        for (index, (value, &gradient)) in values.iter_mut().zip(gradient).enumerate() {
+            self.moments[index] = self.beta1 * self.moments[index] + (1.0 - self.beta1) * gradient;
+            self.velocities[index] = self.beta2 * self.velocities[index] + (1.0 - self.beta2) * gradient * gradient;
+            let moment = self.moments[index] / moment_correction;
+            let velocity = self.velocities[index] / velocity_correction;
+            *value -= self.learning_rate * moment / (velocity.sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// Settings for a tuning run
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TunerOptions {
+    /// Step size of the optimizer, in centipawns
+    pub learning_rate: f64,
+    /// Number of threads computing the gradient
+    pub threads: usize,
+    /// Scaling constant of the sigmoid, fitted to the starting parameters when not given
+    pub k: Option<f64>,
+}
+
+impl Default for TunerOptions {
+    fn default() -> Self {
+        Self {
+            learning_rate: 1.0,
+            threads: 1,
+            k: None,
+        }
+    }
+}
+
+/// Texel tuning of every evaluation parameter against game results with Adam
+///
+/// Each partial derivative comes from tracing how often every evaluation used each weight. Most weights are only summed so
+/// that count is exact, the weights scaling others (the blocked passed pawn scale and the king attack units feeding the
+/// squared king danger) are linearised around the current values.
+pub struct Tuner {
+    positions: Arc<Vec<TuningPosition>>,
+    k: f64,
+    threads: usize,
+    offsets: Arc<HashMap<&'static str, usize>>,
+    values: Vec<f64>,
+    optimizer: Adam,
+}
+
+impl Tuner {
+    pub fn new(positions: Vec<TuningPosition>, parameters: EvaluationParameters, options: TunerOptions) -> Self {
+        let k = options.k.unwrap_or_else(|| fit_k(&positions, parameters));
+        let values = parameters.to_values().into_iter().map(f64::from).collect::<Vec<_>>();
+
+        Self {
+            positions: Arc::new(positions),
+            k,
+            threads: options.threads.max(1),
+            offsets: Arc::new(value_offsets()),
+            optimizer: Adam::new(values.len(), options.learning_rate),
+            values,
+        }
+    }
+
+    /// Scaling constant of the sigmoid
+    #[inline]
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    fn rounded_values(&self) -> Vec<i32> {
+        self.values.iter().map(|value| value.round() as i32).collect()
+    }
+
+    /// Current parameters, rounded to whole centipawns
+    pub fn parameters(&self) -> EvaluationParameters {
+        EvaluationParameters::from_values(&self.rounded_values())
+    }
+
+    /// Error of the current parameters
+    pub fn error(&self) -> f64 {
+        evaluation_error(&self.positions, self.parameters(), self.k)
+    }
+
+    /// Partial derivative of the error for each parameter, split between the threads by position
+    pub fn gradient(&self) -> Vec<f64> {
+        let parameters = self.parameters();
+        let value_count = self.values.len();
+        let chunk_size = (self.positions.len().max(1) - 1) / self.threads + 1;
+        let handles = (0..self.positions.len()).step_by(chunk_size)
+            .map(|start| {
+                let (positions, offsets, k) = (self.positions.clone(), self.offsets.clone(), self.k);
+                thread::spawn(move || {
+                    let mut evaluator = HandcraftedEvaluator::new(parameters);
+                    let mut gradient = vec![0.0; value_count];
+                    for position in &positions[start..(start + chunk_size).min(positions.len())] {
+                        let mut coefficients = Coefficients::new(&offsets);
+                        let predicted = sigmoid(evaluator.evaluate_with(&position.position, &mut coefficients) as f64, k);
+                        // Chain rule through the squared error and the sigmoid
+                        let slope = -2.0 * (position.result - predicted) * predicted * (1.0 - predicted) * k * 10f64.ln() / 400.0;
+                        for (index, rate) in coefficients.tapered_rates() {
+                            gradient[index] += slope * rate;
+                        }
+                    }
+
+                    gradient
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut gradient = vec![0.0; value_count];
+        for handle in handles {
+            let partial = handle.join().expect("Gradient thread panicked");
+            gradient.iter_mut().zip(partial).for_each(|(total, partial)| *total += partial);
+        }
+        let position_count = self.positions.len().max(1) as f64;
+
+        gradient.into_iter().map(|derivative| derivative / position_count).collect()
+    }
+
+    /// Take one optimizer step and return the error after it
+    pub fn step(&mut self) -> f64 {
+        let gradient = self.gradient();
+        self.optimizer.step(&mut self.values, &gradient);
+        self.clamp_values();
+
+        self.error()
+    }
+
+    // Pull values stepped outside the ranges a parameter file allows back to their bounds, so the output always loads
+    fn clamp_values(&mut self) {
+        let rounded = self.rounded_values();
+        let mut parameters = EvaluationParameters::from_values(&rounded);
+        parameters.clamp();
+        for ((value, rounded), clamped) in self.values.iter_mut().zip(rounded).zip(parameters.to_values()) {
+            if rounded != clamped {
+                *value = f64::from(clamped);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interface::game::Position;
+    use oxide_interface::engine::{OxidePosition, TaperedScore};
+    use evaluation::MAX_PARAMETER_VALUE;
+
+    fn positions() -> Vec<TuningPosition> {
+        [
+            ("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", 1.0),
+            ("4k3/8/8/8/3p4/8/8/4K3 w - - 0 1", 0.0),
+            ("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", 0.5),
+            ("4k3/3p4/8/8/8/8/8/4K3 w - - 0 1", 0.5),
+        ].iter()
+            .map(|&(fen, result)| TuningPosition { position: OxidePosition::from_fen(fen).unwrap(), result })
+            .collect()
+    }
+
+    #[test]
+    fn sigmoid_works() {
+        assert_eq!(sigmoid(0.0, 1.0), 0.5);
+        assert!((sigmoid(400.0, 1.0) - 10.0 / 11.0).abs() < 1e-12);
+        assert!((sigmoid(-400.0, 1.0) - 1.0 / 11.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fit_k_works() {
+        let k = fit_k(&positions(), EvaluationParameters::default());
+        assert!(k > 0.0 && k < MAX_K);
+        let error = evaluation_error(&positions(), EvaluationParameters::default(), k);
+        assert!(error <= evaluation_error(&positions(), EvaluationParameters::default(), k * 0.9));
+        assert!(error <= evaluation_error(&positions(), EvaluationParameters::default(), k * 1.1));
+    }
+
+    #[test]
+    fn adam_works() {
+        let mut optimizer = Adam::new(2, 0.5);
+        let mut values = [3.0, -2.0];
+        optimizer.step(&mut values, &[1.0, -4.0]);
+        // Bias corrected, the first step moves each value by the learning rate
+        assert!((values[0] - 2.5).abs() < 1e-6);
+        assert!((values[1] + 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        let parameters = EvaluationParameters::default();
+        let options = TunerOptions { learning_rate: 1.0, threads: 2, k: Some(1.0) };
+        let tuner = Tuner::new(positions(), parameters, options);
+        let gradient = tuner.gradient();
+        let offsets = value_offsets();
+        // Passed pawn on the fifth rank, pawn value and the isolated pawn penalty, each in the endgame
+        for &index in &[offsets["passed_pawn"] + 9, offsets["piece_values"] + 1, offsets["isolated_pawn"] + 1] {
+            let mut values = parameters.to_values();
+            values[index] += 1;
+            let above = evaluation_error(&tuner.positions, EvaluationParameters::from_values(&values), tuner.k);
+            values[index] -= 2;
+            let below = evaluation_error(&tuner.positions, EvaluationParameters::from_values(&values), tuner.k);
+            let difference = (above - below) / 2.0;
+            assert!((gradient[index] - difference).abs() <= difference.abs() * 0.01 + 1e-12, "{} vs {} at {}", gradient[index], difference, index);
+        }
+        // No position has a rook or a knight
+        assert_eq!(gradient[offsets["rook_open_file"]], 0.0);
+        assert_eq!(gradient[offsets["knight_outpost"] + 1], 0.0);
+    }
+
+    #[test]
+    fn tuning_reduces_the_error() {
+        let parameters = EvaluationParameters {
+            passed_pawn: [TaperedScore::ZERO; 8],
+            ..EvaluationParameters::default()
+        };
+        let options = TunerOptions { learning_rate: 5.0, threads: 3, k: Some(1.0) };
+        let mut tuner = Tuner::new(positions(), parameters, options);
+        let before = tuner.error();
+        let gradient = tuner.gradient();
+        assert_eq!(gradient.len(), parameters.to_values().len());
+        // A bigger bonus for the passed pawn on the fifth rank fits the win better
+        assert!(gradient[9] < 0.0);
+
+        let mut after = before;
+        for _ in 0..5 {
+            after = tuner.step();
+        }
+        assert!(after < before);
+        assert!(tuner.parameters().passed_pawn[4].endgame() > 0);
+    }
+    #[test]
+    fn tuned_parameters_reload() {
+        let options = TunerOptions { learning_rate: 5.0, threads: 1, k: Some(1.0) };
+        let mut tuner = Tuner::new(positions(), EvaluationParameters::default(), options);
+        let offsets = value_offsets();
+        // Values a step could take past their bounds
+        tuner.values[offsets["blocked_passed_pawn_scale"]] = 258.0;
+        tuner.values[offsets["king_attack_units"]] = -2.0;
+        tuner.values[offsets["bishop_pair"]] = f64::from(MAX_PARAMETER_VALUE) + 40.0;
+        tuner.step();
+
+        let parameters = tuner.parameters();
+        assert_eq!(parameters.validate(), Ok(()));
+        assert!(parameters.blocked_passed_pawn_scale <= 256);
+        assert!(parameters.king_attack_units[0] >= 0);
+        assert_eq!(EvaluationParameters::from_toml(&parameters.to_toml()), Ok(parameters));
+    }
+}
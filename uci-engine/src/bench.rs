@@ -0,0 +1,46 @@
+use search::{bench, BenchResult, Depth, SearchOptions, ThreadPool};
+use evaluation::HandcraftedEvaluator;
+
+/// Parse the arguments of the `bench` command (an optional depth), defaulting to the standard bench depth
+pub fn parse_bench_depth(arguments: &[String]) -> Result<Depth, String> {
+    match arguments {
+        [] => Ok(search::DEFAULT_BENCH_DEPTH),
+        [depth] => depth.parse::<Depth>()
+            .ok()
+            .filter(|&depth| depth > 0)
+            .ok_or_else(|| format!("Invalid bench depth {}", depth)),
+        _ => Err("Usage: bench [depth]".to_string()),
+    }
+}
+
+/// Search the bench positions single threaded with the default evaluation, the node count is the engine's signature
+pub fn run_bench(depth: Depth) -> BenchResult {
+    let mut pool = ThreadPool::new(SearchOptions::default(), HandcraftedEvaluator::default());
+
+    bench(&mut pool, depth)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Changes whenever the search or evaluation does, update it along with any intended change in behavior
+    const BENCH_SIGNATURE_DEPTH: Depth = 4;
+    const BENCH_SIGNATURE: u64 = 76_248;
+
+    #[test]
+    fn parse_bench_depth_works() {
+        assert_eq!(parse_bench_depth(&[]), Ok(search::DEFAULT_BENCH_DEPTH));
+        assert_eq!(parse_bench_depth(&["5".to_string()]), Ok(5));
+        assert!(parse_bench_depth(&["0".to_string()]).is_err());
+        assert!(parse_bench_depth(&["deep".to_string()]).is_err());
+        assert!(parse_bench_depth(&["5".to_string(), "6".to_string()]).is_err());
+    }
+
+    #[test]
+    fn bench_signature_works() {
+        let result = run_bench(BENCH_SIGNATURE_DEPTH);
+        assert_eq!(result.nodes, BENCH_SIGNATURE);
+        assert_eq!(run_bench(BENCH_SIGNATURE_DEPTH).nodes, result.nodes);
+    }
+}
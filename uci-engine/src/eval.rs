@@ -0,0 +1,86 @@
+use evaluation::HandcraftedEvaluator;
+use oxide_interface::engine::OxidePosition;
+use interface::game::Position;
+
+/// Flag of the `eval` command printing JSON instead of the table
+pub const EVAL_JSON_FLAG: &str = "--json";
+/// Flag loading an evaluation parameter file at startup, as the `EvalParams` option would
+pub const EVAL_PARAMS_FLAG: &str = "--eval-params";
+
+/// Split a leading `--eval-params <path>` off the command line into the path (if given) and the remaining arguments
+pub fn split_eval_params(arguments: &[String]) -> Result<(Option<&str>, &[String]), String> {
+    match arguments {
+        [flag, path, rest @ ..] if flag == EVAL_PARAMS_FLAG => Ok((Some(path.as_str()), rest)),
+        [flag] if flag == EVAL_PARAMS_FLAG => Err(format!("Missing path after {}", EVAL_PARAMS_FLAG)),
+        _ => Ok((None, arguments)),
+    }
+}
+
+/// Parse the arguments of the `eval` command (an optional JSON flag then the FEN, quoted or not) into the position and if JSON was asked for
+pub fn parse_eval_arguments(arguments: &[String]) -> Result<(OxidePosition, bool), String> {
+    let (json, fen) = match arguments.split_first() {
+        Some((flag, fen)) if flag == EVAL_JSON_FLAG => (true, fen),
+        _ => (false, arguments),
+    };
+    if fen.is_empty() {
+        return Err("Usage: eval [--json] <fen>".to_string());
+    }
+
+    let fen = fen.join(" ");
+    OxidePosition::from_fen(&fen)
+        .map(|position| (position, json))
+        .map_err(|error| format!("Invalid FEN {} ({:?})", fen, error))
+}
+
+/// Render the breakdown of a position's evaluation printed by the `eval` debug command
+pub fn format_eval(evaluator: &mut HandcraftedEvaluator, position: &OxidePosition) -> String {
+    evaluator.trace(position).to_string()
+}
+
+/// The breakdown of a position's evaluation as JSON for tooling
+pub fn format_eval_json(evaluator: &mut HandcraftedEvaluator, position: &OxidePosition) -> String {
+    evaluator.trace(position).to_json()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_eval_works() {
+        let position = OxidePosition::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut evaluator = HandcraftedEvaluator::default();
+        let table = format_eval(&mut evaluator, &position);
+        assert!(table.starts_with("        Term |    White    |    Black    |    Total"));
+        assert!(table.contains("    Material |  4039  3868 |  4039  3868 |     0     0\n"));
+        assert!(table.ends_with("Phase: 24/24\nEvaluation: +0.00 (white side)"));
+        let json = format_eval_json(&mut evaluator, &position);
+        assert!(json.starts_with("{\"phase\":24,\"terms\":{\"material\":{\"white\":{\"mg\":4039,\"eg\":3868}"));
+        assert!(json.ends_with("\"score\":0}"));
+    }
+    #[test]
+    fn parse_eval_arguments_works() {
+        let arguments = |arguments: &[&str]| arguments.iter().map(|argument| argument.to_string()).collect::<Vec<_>>();
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        let (position, json) = parse_eval_arguments(&arguments(&[fen])).unwrap();
+        assert_eq!(position.to_fen(), fen);
+        assert!(!json);
+        // Unquoted FENs arrive split on spaces
+        let (position, json) = parse_eval_arguments(&arguments(&["--json", "4k3/8/8/8/8/8/4P3/4K3", "w", "-", "-", "0", "1"])).unwrap();
+        assert_eq!(position.to_fen(), fen);
+        assert!(json);
+        assert!(parse_eval_arguments(&arguments(&[])).is_err());
+        assert!(parse_eval_arguments(&arguments(&["--json"])).is_err());
+        assert!(parse_eval_arguments(&arguments(&["4x3/8/8/8/8/8/8/4K3 w - - 0 1"])).is_err());
+    }
+    #[test]
+    fn split_eval_params_works() {
+        let arguments = |arguments: &[&str]| arguments.iter().map(|argument| argument.to_string()).collect::<Vec<_>>();
+        let eval = arguments(&["eval", "--json"]);
+        assert_eq!(split_eval_params(&eval), Ok((None, &eval[..])));
+        let with_path = arguments(&["--eval-params", "tuned.toml", "eval", "--json"]);
+        assert_eq!(split_eval_params(&with_path), Ok((Some("tuned.toml"), &eval[..])));
+        assert_eq!(split_eval_params(&arguments(&["--eval-params", "tuned.toml"])), Ok((Some("tuned.toml"), &[][..])));
+        assert!(split_eval_params(&arguments(&["--eval-params"])).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::{stdout, Write};
+use search::{Bound, SearchEvent, SearchListener, format_move};
+
+/// Render a search event as the line a UCI GUI expects
+pub fn format_event(event: &SearchEvent) -> String {
+    match event {
+        SearchEvent::Iteration(info) => {
+            let bound = match info.bound {
+                Bound::Exact => "",
+                Bound::Lower => " lowerbound",
+                Bound::Upper => " upperbound",
+            };
+            let pv = info.pv.iter()
+                .map(|&chess_move| format_move(chess_move))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            format!(
+                "info depth {} seldepth {} multipv {} score {}{} nodes {} nps {} hashfull {} time {} pv {}",
+                info.depth, info.sel_depth, info.multi_pv, info.score, bound, info.nodes, info.nodes_per_second, info.hashfull, info.time.as_millis(), pv,
+            )
+        },
+        SearchEvent::CurrentMove { depth, chess_move, move_number } => {
+            format!("info depth {} currmove {} currmovenumber {}", depth, format_move(*chess_move), move_number)
+        },
+        SearchEvent::BestMove { best_move, ponder_move } => {
+            // A null move tells the GUI there was nothing to play
+            let best_move = best_move.map_or_else(|| "0000".to_string(), format_move);
+            match ponder_move {
+                Some(ponder_move) => format!("bestmove {} ponder {}", best_move, format_move(*ponder_move)),
+                None => format!("bestmove {}", best_move),
+            }
+        },
+    }
+}
+
+/// Writes search events to standard output for a UCI GUI
+#[derive(Default)]
+pub struct UciListener;
+
+impl SearchListener for UciListener {
+    fn on_event(&self, event: &SearchEvent) {
+        let mut stdout = stdout();
+        let _ = writeln!(stdout, "{}", format_event(event));
+        let _ = stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use interface::engine::PositionalScore;
+    use interface::game::{ChessMove, SimpleChessMove};
+    use oxide_interface::engine::OxideScore;
+    use oxide_interface::game::{OxideMove, OxideSquare::*};
+    use search::IterationInfo;
+
+    fn iteration(score: OxideScore, bound: Bound) -> SearchEvent {
+        SearchEvent::Iteration(IterationInfo {
+            depth: 7,
+            sel_depth: 12,
+            multi_pv: 1,
+            score,
+            bound,
+            nodes: 123_456,
+            nodes_per_second: 1_000_000,
+            time: Duration::from_millis(123),
+            hashfull: 42,
+            pv: vec![OxideMove::new_double_pawn_push(E2, E4), OxideMove::new_double_pawn_push(E7, E5), OxideMove::new(G1, F3)],
+        })
+    }
+
+    #[test]
+    fn format_iteration_works() {
+        assert_eq!(
+            format_event(&iteration(OxideScore::new(35), Bound::Exact)),
+            "info depth 7 seldepth 12 multipv 1 score cp 35 nodes 123456 nps 1000000 hashfull 42 time 123 pv e2e4 e7e5 g1f3",
+        );
+        assert_eq!(
+            format_event(&iteration(OxideScore::new_mated(4), Bound::Upper)),
+            "info depth 7 seldepth 12 multipv 1 score mate -2 upperbound nodes 123456 nps 1000000 hashfull 42 time 123 pv e2e4 e7e5 g1f3",
+        );
+    }
+
+    #[test]
+    fn format_current_move_works() {
+        let event = SearchEvent::CurrentMove { depth: 20, chess_move: OxideMove::new(G1, F3), move_number: 3 };
+        assert_eq!(format_event(&event), "info depth 20 currmove g1f3 currmovenumber 3");
+    }
+
+    #[test]
+    fn format_best_move_works() {
+        let best_move = Some(OxideMove::new_double_pawn_push(E2, E4));
+        let ponder_move = Some(OxideMove::new_double_pawn_push(E7, E5));
+        assert_eq!(format_event(&SearchEvent::BestMove { best_move, ponder_move }), "bestmove e2e4 ponder e7e5");
+        assert_eq!(format_event(&SearchEvent::BestMove { best_move, ponder_move: None }), "bestmove e2e4");
+        assert_eq!(format_event(&SearchEvent::BestMove { best_move: None, ponder_move: None }), "bestmove 0000");
+    }
+}
@@ -0,0 +1,10 @@
+mod info;
+mod eval;
+mod bench;
+mod uci;
+
+pub use search::{format_move, parse_move};
+pub use info::{format_event, UciListener};
+pub use eval::{format_eval, format_eval_json, parse_eval_arguments, split_eval_params, EVAL_JSON_FLAG, EVAL_PARAMS_FLAG};
+pub use bench::{parse_bench_depth, run_bench};
+pub use uci::{parse_go, parse_position, parse_set_option, run_uci, uci_options, ENGINE_NAME};
@@ -1,18 +1,54 @@
+use std::env;
+use std::io::{stdin, stdout};
+use std::process;
+use std::sync::Arc;
+use interface::engine::Evaluator;
+use evaluation::{HandcraftedEvaluator, SelectableEvaluator, EVAL_PARAMETERS_OPTION};
+use search::{Engine, SearchOptions};
+use uci_engine::{parse_bench_depth, run_bench, parse_eval_arguments, format_eval, format_eval_json, split_eval_params, run_uci, UciListener};
 
-use interface::game::{Square, ChessMove, SimpleChessMove};
-use oxide_interface::game::{OxideSquare, OxideBitboard, OxideMove, OxideSquare::*, OxidePiece};
+const USAGE: &str = "Usage: uci-engine [--eval-params <path>] [eval [--json] <fen>] | uci-engine bench [depth]";
+
+fn exit_with(error: impl std::fmt::Display) -> ! {
+    eprintln!("{}\n{}", error, USAGE);
+    process::exit(1);
+}
 
 fn main() {
-    const SQUARES: &[OxideSquare] = &[OxideSquare::A2, OxideSquare::B3, OxideSquare::H4, OxideSquare::E5];
-    let mask = SQUARES.iter().copied().collect::<OxideBitboard>();
-    for k in mask.into_iter() {
-        dbg!(k);
+    let arguments = env::args().skip(1).collect::<Vec<_>>();
+    let (eval_params, arguments) = split_eval_params(&arguments).unwrap_or_else(|error| exit_with(error));
+    match arguments.split_first() {
+        None => {
+            let engine = Engine::new(SearchOptions::default(), SelectableEvaluator::default());
+            if let Some(path) = eval_params {
+                engine.set_option(EVAL_PARAMETERS_OPTION, path).unwrap_or_else(|error| exit_with(error));
+            }
+            engine.set_listener(Some(Arc::new(UciListener))).expect("A new engine isn't searching");
+            run_uci(stdin().lock(), &mut stdout(), &engine);
+        },
+        // The bench signature is only comparable with the compiled-in parameters
+        Some((command, bench_arguments)) if command == "bench" && eval_params.is_none() => {
+            let depth = parse_bench_depth(bench_arguments).unwrap_or_else(|error| exit_with(error));
+            // The signature on its own line so scripts can compare it between builds
+            let result = run_bench(depth);
+            println!("{}", result);
+            println!("{}", result.nodes);
+        },
+        Some((command, eval_arguments)) if command == "eval" => {
+            let (position, json) = parse_eval_arguments(eval_arguments).unwrap_or_else(|error| exit_with(error));
+            let mut evaluator = HandcraftedEvaluator::default();
+            if let Some(path) = eval_params {
+                evaluator.set_option(EVAL_PARAMETERS_OPTION, path).unwrap_or_else(|error| exit_with(error));
+            }
+            if json {
+                println!("{}", format_eval_json(&mut evaluator, &position));
+            } else {
+                println!("{}", format_eval(&mut evaluator, &position));
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        },
     }
-    // let t = oxide_interface::game::OxideSquare::SQUARES;
-    for x in OxideSquare::SQUARES.iter().copied().filter(|s| SQUARES.contains(s)) {
-        println!("{}", x);
-    }
-
-    let mut t = OxideMove::new_promoting_capture(E7, F8, OxidePiece::Queen);
-    println!("{} {} {}", OxideMove::WHITE_KING_CASTLE, OxideMove::new(E7, F8), t);
 }
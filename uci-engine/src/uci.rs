@@ -0,0 +1,242 @@
+use std::io::{BufRead, Write};
+use std::time::Duration;
+use oxide_interface::engine::OxideBoard;
+use search::{parse_move, Depth, Engine, SearchLimits, START_FEN, DEFAULT_HASH_MEGABYTES, DEFAULT_MOVE_OVERHEAD_MILLISECONDS, MAX_HASH_MEGABYTES, MAX_MOVE_OVERHEAD_MILLISECONDS, MAX_MULTI_PV, MAX_THREADS};
+use evaluation::{EVAL_FILE_OPTION, EVAL_PARAMETERS_OPTION, USE_NNUE_OPTION};
+
+/// Name the engine identifies itself with
+pub const ENGINE_NAME: &str = "Oxide";
+
+// Parameters of `go`, ending the list of moves after `searchmoves`
+const GO_KEYWORDS: [&str; 11] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime"];
+
+/// The `option` lines sent in reply to `uci`
+pub fn uci_options() -> Vec<String> {
+    vec![
+        format!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS),
+        format!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_MEGABYTES, MAX_HASH_MEGABYTES),
+        format!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV),
+        format!("option name Move Overhead type spin default {} min 0 max {}", DEFAULT_MOVE_OVERHEAD_MILLISECONDS, MAX_MOVE_OVERHEAD_MILLISECONDS),
+        "option name Ponder type check default false".to_string(),
+        format!("option name {} type string default <empty>", EVAL_FILE_OPTION),
+        format!("option name {} type check default false", USE_NNUE_OPTION),
+        format!("option name {} type string default <empty>", EVAL_PARAMETERS_OPTION),
+    ]
+}
+
+/// Parse the arguments of `position` into the FEN and the moves played from it
+pub fn parse_position<'a>(arguments: &[&'a str]) -> Result<(String, Vec<&'a str>), String> {
+    let (fen, rest) = match arguments.split_first() {
+        Some((&"startpos", rest)) => (START_FEN.to_string(), rest),
+        Some((&"fen", rest)) => {
+            let fen_length = rest.iter().position(|&token| token == "moves").unwrap_or(rest.len());
+            (rest[..fen_length].join(" "), &rest[fen_length..])
+        },
+        _ => return Err("Expected startpos or fen".to_string()),
+    };
+
+    match rest.split_first() {
+        None => Ok((fen, Vec::new())),
+        Some((&"moves", moves)) => Ok((fen, moves.to_vec())),
+        Some((token, _)) => Err(format!("Unexpected {} after the position", token)),
+    }
+}
+
+/// Parse the arguments of `setoption` into the option's name and value (empty for buttons), both can contain spaces
+pub fn parse_set_option(arguments: &[&str]) -> Result<(String, String), String> {
+    match arguments.split_first() {
+        Some((&"name", rest)) => {
+            let name_length = rest.iter().position(|&token| token == "value").unwrap_or(rest.len());
+            if name_length == 0 {
+                return Err("Missing option name".to_string());
+            }
+            let value = rest.get(name_length + 1..).unwrap_or(&[]).join(" ");
+
+            Ok((rest[..name_length].join(" "), value))
+        },
+        _ => Err("Expected name".to_string()),
+    }
+}
+
+/// Parse the arguments of `go` into the search limits and if the search should ponder
+pub fn parse_go(board: &OxideBoard, arguments: &[&str]) -> Result<(SearchLimits, bool), String> {
+    let mut limits = SearchLimits::default();
+    let mut ponder = false;
+    let mut tokens = arguments.iter().copied().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "infinite" => limits.infinite = true,
+            "ponder" => ponder = true,
+            "searchmoves" => {
+                while let Some(text) = tokens.peek().copied().filter(|text| !GO_KEYWORDS.contains(text) && *text != "infinite") {
+                    limits.search_moves.push(parse_move(board, text).ok_or_else(|| format!("Illegal move {}", text))?);
+                    tokens.next();
+                }
+            },
+            _ => {
+                let value = tokens.next().ok_or_else(|| format!("Missing value for {}", token))?;
+                match token {
+                    "wtime" => limits.time.white_time = Some(parse_milliseconds(token, value)?),
+                    "btime" => limits.time.black_time = Some(parse_milliseconds(token, value)?),
+                    "winc" => limits.time.white_increment = parse_milliseconds(token, value)?,
+                    "binc" => limits.time.black_increment = parse_milliseconds(token, value)?,
+                    "movetime" => limits.time.move_time = Some(parse_milliseconds(token, value)?),
+                    "movestogo" => limits.time.moves_to_go = Some(parse_value(token, value)?),
+                    "depth" => limits.depth = Some(parse_value::<Depth>(token, value)?.max(1)),
+                    "nodes" => limits.nodes = Some(parse_value(token, value)?),
+                    "mate" => limits.mate = Some(parse_value(token, value)?),
+                    _ => return Err(format!("Unknown go parameter {}", token)),
+                }
+            },
+        }
+    }
+
+    Ok((limits, ponder))
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("Invalid {} {}", name, value))
+}
+
+// GUIs can send a negative clock once the engine has overstepped it
+fn parse_milliseconds(name: &str, value: &str) -> Result<Duration, String> {
+    parse_value::<i64>(name, value).map(|milliseconds| Duration::from_millis(milliseconds.max(0) as u64))
+}
+
+/// Handle UCI commands until `quit` or the end of the input, writing replies to the output (search output reaches the GUI through the engine's listener)
+pub fn run_uci<R: BufRead, W: Write>(input: R, output: &mut W, engine: &Engine) {
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let (command, arguments) = match tokens.split_first() {
+            Some((&command, arguments)) => (command, arguments),
+            None => continue,
+        };
+
+        let result = match command {
+            "uci" => {
+                let mut lines = vec![format!("id name {}", ENGINE_NAME)];
+                lines.extend(uci_options());
+                lines.push("uciok".to_string());
+                send(output, &lines.join("\n"));
+                Ok(())
+            },
+            "isready" => {
+                send(output, "readyok");
+                Ok(())
+            },
+            "ucinewgame" => engine.clear_hash().map_err(|error| error.to_string()),
+            "setoption" => parse_set_option(arguments)
+                .and_then(|(name, value)| if name.eq_ignore_ascii_case("Ponder") {
+                    // Pondering is up to the GUI, the option only tells it the engine can
+                    Ok(())
+                } else {
+                    engine.set_option(&name, &value).map_err(|error| error.to_string())
+                }),
+            "position" => parse_position(arguments)
+                .and_then(|(fen, moves)| engine.set_position(&fen, &moves).map_err(|error| error.to_string())),
+            "go" => parse_go(&engine.board(), arguments)
+                .and_then(|(limits, ponder)| {
+                    let started = if ponder { engine.ponder(limits) } else { engine.go(limits) };
+                    started.map_err(|error| error.to_string())
+                }),
+            "stop" => {
+                engine.stop();
+                Ok(())
+            },
+            "ponderhit" => {
+                engine.ponderhit();
+                Ok(())
+            },
+            "quit" => break,
+            _ => Err(format!("Unknown command {}", command)),
+        };
+        if let Err(error) = result {
+            send(output, &format!("info string {}", error));
+        }
+    }
+
+    engine.stop();
+    engine.wait();
+}
+
+fn send<W: Write>(output: &mut W, text: &str) {
+    let _ = writeln!(output, "{}", text);
+    let _ = output.flush();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+    use interface::engine::Board;
+    use interface::game::{ChessMove, Position, SimpleChessMove};
+    use oxide_interface::engine::OxidePosition;
+    use oxide_interface::game::{OxideMove, OxideSquare::*};
+    use search::SearchOptions;
+    use evaluation::SelectableEvaluator;
+
+    #[test]
+    fn parse_position_works() {
+        assert_eq!(parse_position(&["startpos"]), Ok((START_FEN.to_string(), vec![])));
+        assert_eq!(parse_position(&["startpos", "moves", "e2e4", "e7e5"]), Ok((START_FEN.to_string(), vec!["e2e4", "e7e5"])));
+        assert_eq!(
+            parse_position(&["fen", "4k3/8/8/8/8/8/4P3/4K3", "w", "-", "-", "0", "1", "moves", "e2e4"]),
+            Ok(("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".to_string(), vec!["e2e4"])),
+        );
+        assert!(parse_position(&[]).is_err());
+        assert!(parse_position(&["startpos", "e2e4"]).is_err());
+    }
+
+    #[test]
+    fn parse_set_option_works() {
+        assert_eq!(parse_set_option(&["name", "Hash", "value", "64"]), Ok(("Hash".to_string(), "64".to_string())));
+        assert_eq!(parse_set_option(&["name", "Move", "Overhead", "value", "30"]), Ok(("Move Overhead".to_string(), "30".to_string())));
+        assert_eq!(parse_set_option(&["name", "EvalFile", "value", "my", "net.nnue"]), Ok(("EvalFile".to_string(), "my net.nnue".to_string())));
+        assert_eq!(parse_set_option(&["name", "Clear", "Hash"]), Ok(("Clear Hash".to_string(), String::new())));
+        assert!(parse_set_option(&["name", "value", "1"]).is_err());
+        assert!(parse_set_option(&["Hash", "64"]).is_err());
+    }
+
+    #[test]
+    fn parse_go_works() {
+        let board = OxideBoard::new(OxidePosition::from_fen(START_FEN).unwrap());
+        let (limits, ponder) = parse_go(&board, &["wtime", "60000", "btime", "-50", "winc", "1000", "movestogo", "20", "ponder"]).unwrap();
+        assert_eq!(limits.time.white_time, Some(Duration::from_millis(60_000)));
+        assert_eq!(limits.time.black_time, Some(Duration::from_millis(0)));
+        assert_eq!(limits.time.white_increment, Duration::from_millis(1000));
+        assert_eq!(limits.time.moves_to_go, Some(20));
+        assert!(ponder);
+
+        let (limits, ponder) = parse_go(&board, &["depth", "6", "searchmoves", "e2e4", "g1f3", "nodes", "5000"]).unwrap();
+        assert_eq!(limits.depth, Some(6));
+        assert_eq!(limits.nodes, Some(5000));
+        assert_eq!(limits.search_moves, vec![OxideMove::new_double_pawn_push(E2, E4), OxideMove::new(G1, F3)]);
+        assert!(!ponder);
+
+        assert!(parse_go(&board, &["infinite"]).unwrap().0.infinite);
+        assert!(parse_go(&board, &["depth"]).is_err());
+        assert!(parse_go(&board, &["depth", "deep"]).is_err());
+        assert!(parse_go(&board, &["searchmoves", "e2e5"]).is_err());
+        assert!(parse_go(&board, &["fast"]).is_err());
+    }
+
+    #[test]
+    fn run_uci_works() {
+        let engine = Engine::new(SearchOptions { hash_megabytes: 1, ..SearchOptions::default() }, SelectableEvaluator::default());
+        let input = "uci\nsetoption name Hash value 2\nposition startpos moves e2e4 e7e5\ngo depth 3\nisready\nbogus\nquit\nisready\n";
+        let mut output = Vec::new();
+        run_uci(Cursor::new(input), &mut output, &engine);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("id name Oxide\noption name Threads"));
+        // Nothing is read after quit
+        assert!(output.ends_with("uciok\nreadyok\ninfo string Unknown command bogus\n"), "{}", output);
+        // Quitting stops the search, which still leaves a result
+        assert!(!engine.is_searching());
+        assert!(engine.wait().is_some());
+        assert_eq!(engine.board().position().to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+    }
+}